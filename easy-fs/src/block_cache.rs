@@ -134,6 +134,16 @@ pub fn get_block_cache(
         .get_block_cache(block_id, block_device)
 }
 
+/// The block if it is in the cache, without loading it.
+pub fn peek_block_cache(block_id: usize) -> Option<Arc<Mutex<BlockCache>>> {
+    BLOCK_CACHE_MANAGER
+        .lock()
+        .queue
+        .iter()
+        .find(|pair| pair.0 == block_id)
+        .map(|pair| Arc::clone(&pair.1))
+}
+
 pub fn block_cache_sync_all() {
     let manager = BLOCK_CACHE_MANAGER.lock();
     for (_, cache) in manager.queue.iter() {
@@ -1,11 +1,11 @@
-use super::{get_block_cache, BlockDevice, BLOCK_SZ};
+use super::{get_block_cache, peek_block_cache, BlockDevice, BLOCK_SZ};
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::fmt::{Debug, Formatter, Result};
 
 const EFS_MAGIC: u32 = 0x3b800001;
 const INODE_DIRECT_COUNT: usize = 28;
-const NAME_LENGTH_LIMIT: usize = 27;
+pub const NAME_LENGTH_LIMIT: usize = 27;
 const INODE_INDIRECT1_COUNT: usize = BLOCK_SZ / 4;
 const INODE_INDIRECT2_COUNT: usize = INODE_INDIRECT1_COUNT * INODE_INDIRECT1_COUNT;
 const DIRECT_BOUND: usize = INODE_DIRECT_COUNT;
@@ -144,6 +144,32 @@ impl DiskInode {
                 })
         }
     }
+    /// Like `get_block_id`, but only through indirect blocks in the cache,
+    /// `None` if one is not there.
+    pub fn peek_block_id(&self, inner_id: u32) -> Option<u32> {
+        let inner_id = inner_id as usize;
+        if inner_id < INODE_DIRECT_COUNT {
+            Some(self.direct[inner_id])
+        } else if inner_id < INDIRECT1_BOUND {
+            peek_block_cache(self.indirect1 as usize).map(|cache| {
+                cache.lock().read(0, |indirect_block: &IndirectBlock| {
+                    indirect_block[inner_id - INODE_DIRECT_COUNT]
+                })
+            })
+        } else {
+            let last = inner_id - INDIRECT1_BOUND;
+            let indirect1 = peek_block_cache(self.indirect2 as usize)?
+                .lock()
+                .read(0, |indirect2: &IndirectBlock| {
+                    indirect2[last / INODE_INDIRECT1_COUNT]
+                });
+            peek_block_cache(indirect1 as usize).map(|cache| {
+                cache.lock().read(0, |indirect1: &IndirectBlock| {
+                    indirect1[last % INODE_INDIRECT1_COUNT]
+                })
+            })
+        }
+    }
     pub fn increase_size(
         &mut self,
         new_size: u32,
@@ -11,8 +11,10 @@ mod vfs;
 
 pub const BLOCK_SZ: usize = 512;
 use bitmap::Bitmap;
-use block_cache::{block_cache_sync_all, get_block_cache};
+pub use block_cache::block_cache_sync_all;
+use block_cache::{get_block_cache, peek_block_cache};
 pub use block_dev::BlockDevice;
 pub use efs::EasyFileSystem;
+pub use layout::DIRENT_SZ;
 use layout::*;
 pub use vfs::Inode;
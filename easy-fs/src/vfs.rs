@@ -1,6 +1,6 @@
 use super::{
-    block_cache_sync_all, get_block_cache, BlockDevice, DirEntry, DiskInode, DiskInodeType,
-    EasyFileSystem, DIRENT_SZ,
+    block_cache_sync_all, get_block_cache, peek_block_cache, BlockDevice, DirEntry, DiskInode,
+    DiskInodeType, EasyFileSystem, BLOCK_SZ, DIRENT_SZ, NAME_LENGTH_LIMIT,
 };
 use alloc::string::String;
 use alloc::sync::Arc;
@@ -59,6 +59,20 @@ impl Inode {
         None
     }
 
+    /// The index of the entry `name` in this directory and its inode number.
+    fn find_dirent(&self, name: &str, disk_inode: &DiskInode) -> Option<(usize, u32)> {
+        assert!(disk_inode.is_dir());
+        let file_count = (disk_inode.size as usize) / DIRENT_SZ;
+        let mut dirent = DirEntry::empty();
+        (0..file_count).find_map(|i| {
+            assert_eq!(
+                disk_inode.read_at(DIRENT_SZ * i, dirent.as_bytes_mut(), &self.block_device),
+                DIRENT_SZ,
+            );
+            (dirent.name() == name).then(|| (i, dirent.inode_number()))
+        })
+    }
+
     pub fn find(&self, name: &str) -> Option<Arc<Inode>> {
         let fs = self.fs.lock();
         self.read_disk_inode(|disk_inode| {
@@ -91,7 +105,40 @@ impl Inode {
         disk_inode.increase_size(new_size, v, &self.block_device);
     }
 
+    /// Whether this inode is a directory, which can be searched.
+    pub fn is_dir(&self) -> bool {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.is_dir())
+    }
+
+    /// Number of this inode, the root directory is 0.
+    pub fn inode_id(&self) -> u32 {
+        let fs = self.fs.lock();
+        fs.get_inode_id(self.block_id as u32, self.block_offset)
+    }
+
+    /// Size of the file in bytes.
+    pub fn size(&self) -> usize {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| disk_inode.size as usize)
+    }
+
+    /// Create a file in this directory, return None if the name is taken
+    /// or too long.
     pub fn create(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::File)
+    }
+
+    /// Create an empty directory in this directory, return None if the name
+    /// is taken or too long.
+    pub fn create_dir(&self, name: &str) -> Option<Arc<Inode>> {
+        self.create_inode(name, DiskInodeType::Directory)
+    }
+
+    fn create_inode(&self, name: &str, type_: DiskInodeType) -> Option<Arc<Inode>> {
+        if name.len() > NAME_LENGTH_LIMIT {
+            return None;
+        }
         let mut fs = self.fs.lock();
         let op = |root_inode: &mut DiskInode| {
             // assert it is a directory
@@ -110,7 +157,7 @@ impl Inode {
         get_block_cache(new_inode_block_id as usize, Arc::clone(&self.block_device))
             .lock()
             .modify(new_inode_block_offset, |new_inode: &mut DiskInode| {
-                new_inode.initialize(DiskInodeType::File);
+                new_inode.initialize(type_);
             });
         self.modify_disk_inode(|root_inode| {
             // append file in the dirent
@@ -139,6 +186,54 @@ impl Inode {
         // release efs lock automatically by compiler
     }
 
+    /// Remove the entry `name` from this directory and return the inode it
+    /// names, which is not freed, see `release`. Return None if there is no
+    /// such entry, or it is a directory that is not empty.
+    pub fn unlink(&self, name: &str) -> Option<Arc<Inode>> {
+        let mut fs = self.fs.lock();
+        let (index, inode_id) = self.read_disk_inode(|dir| self.find_dirent(name, dir))?;
+        let (block_id, block_offset) = fs.get_disk_inode_pos(inode_id);
+        let inode = Arc::new(Self::new(
+            block_id,
+            block_offset,
+            self.fs.clone(),
+            self.block_device.clone(),
+        ));
+        if inode.read_disk_inode(|disk_inode| disk_inode.is_dir() && disk_inode.size > 0) {
+            return None;
+        }
+        self.modify_disk_inode(|dir| {
+            // the entries are kept packed, so the directory is rebuilt
+            // without the entry, which gives its blocks back as it shrinks
+            let file_count = (dir.size as usize) / DIRENT_SZ;
+            let mut entries = Vec::with_capacity(file_count - 1);
+            for i in (0..file_count).filter(|&i| i != index) {
+                let mut dirent = DirEntry::empty();
+                dir.read_at(i * DIRENT_SZ, dirent.as_bytes_mut(), &self.block_device);
+                entries.push(dirent);
+            }
+            for data_block in dir.clear_size(&self.block_device) {
+                fs.dealloc_data(data_block);
+            }
+            self.increase_size((entries.len() * DIRENT_SZ) as u32, dir, &mut fs);
+            for (i, dirent) in entries.iter().enumerate() {
+                dir.write_at(i * DIRENT_SZ, dirent.as_bytes(), &self.block_device);
+            }
+        });
+        block_cache_sync_all();
+        Some(inode)
+    }
+
+    /// Free the data blocks and the inode itself, once it is unlinked from
+    /// its directory and not used any more.
+    pub fn release(&self) {
+        self.clear();
+        let mut fs = self.fs.lock();
+        let inode_id = fs.get_inode_id(self.block_id as u32, self.block_offset);
+        fs.dealloc_inode(inode_id);
+        block_cache_sync_all();
+    }
+
     pub fn ls(&self) -> Vec<String> {
         let _fs = self.fs.lock();
         self.read_disk_inode(|disk_inode| {
@@ -156,11 +251,54 @@ impl Inode {
         })
     }
 
+    /// The inode number and name of the i-th entry of this directory, None
+    /// past the last one.
+    pub fn dirent_at(&self, i: usize) -> Option<(u32, String)> {
+        let _fs = self.fs.lock();
+        self.read_disk_inode(|disk_inode| {
+            assert!(disk_inode.is_dir());
+            let mut dirent = DirEntry::empty();
+            if disk_inode.read_at(i * DIRENT_SZ, dirent.as_bytes_mut(), &self.block_device)
+                != DIRENT_SZ
+            {
+                return None;
+            }
+            Some((dirent.inode_number(), String::from(dirent.name())))
+        })
+    }
+
     pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
         let _fs = self.fs.lock();
         self.read_disk_inode(|disk_inode| disk_inode.read_at(offset, buf, &self.block_device))
     }
 
+    /// Whether all data blocks holding `[offset, offset + len)` are cached.
+    /// The part past the end of file is ignored, a range starting there is
+    /// never resident. Nothing is loaded into the cache to find out, so the
+    /// range is not resident either if the inode or an indirect block on the
+    /// way is not cached.
+    pub fn is_resident(&self, offset: usize, len: usize) -> bool {
+        let _fs = self.fs.lock();
+        let inode_block = match peek_block_cache(self.block_id) {
+            Some(cache) => cache,
+            None => return false,
+        };
+        let inode_block = inode_block.lock();
+        inode_block.read(self.block_offset, |disk_inode: &DiskInode| {
+            let end = offset.saturating_add(len).min(disk_inode.size as usize);
+            if offset >= end {
+                return false;
+            }
+            (offset / BLOCK_SZ..(end + BLOCK_SZ - 1) / BLOCK_SZ).all(|inner_id| {
+                disk_inode
+                    .peek_block_id(inner_id as u32)
+                    .map_or(false, |block_id| {
+                        peek_block_cache(block_id as usize).is_some()
+                    })
+            })
+        })
+    }
+
     pub fn write_at(&self, offset: usize, buf: &[u8]) -> usize {
         let mut fs = self.fs.lock();
         let size = self.modify_disk_inode(|disk_inode| {
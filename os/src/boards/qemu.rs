@@ -17,42 +17,101 @@ pub const VIRTGPU_XRES: u32 = 1280;
 #[allow(unused)]
 pub const VIRTGPU_YRES: u32 = 800;
 
-use crate::drivers::block::BLOCK_DEVICE;
+use crate::drivers::block::{virtio_blk_reset, BLOCK_DEVICE};
 use crate::drivers::chardev::{CharDevice, UART};
-use crate::drivers::plic::{IntrTargetPriority, PLIC};
+use crate::drivers::plic::{dispatch_irq, register_irq, IntrTargetPriority, IRQ_NONE, PLIC};
 use crate::drivers::{KEYBOARD_DEVICE, MOUSE_DEVICE};
 
+pub const KEYBOARD_IRQ: u16 = 5;
+pub const MOUSE_IRQ: u16 = 6;
+pub const BLOCK_IRQ: u16 = 8;
+pub const UART_IRQ: u16 = 10;
+
 pub fn device_init() {
     use riscv::register::sie;
     let mut plic = unsafe { PLIC::new(VIRT_PLIC) };
     let hart_id: usize = 0;
-    let supervisor = IntrTargetPriority::Supervisor;
-    let machine = IntrTargetPriority::Machine;
-    plic.set_threshold(hart_id, supervisor, 0);
-    plic.set_threshold(hart_id, machine, 1);
-    //irq nums: 5 keyboard, 6 mouse, 8 block, 10 uart
-    for intr_src_id in [5usize, 6, 8, 10] {
-        plic.enable(hart_id, supervisor, intr_src_id);
-        plic.set_priority(intr_src_id, 1);
+    plic.init_hart(hart_id);
+    register_irq(KEYBOARD_IRQ, || KEYBOARD_DEVICE.handle_irq());
+    register_irq(MOUSE_IRQ, || MOUSE_DEVICE.handle_irq());
+    register_irq(BLOCK_IRQ, || BLOCK_DEVICE.handle_irq());
+    register_irq(UART_IRQ, || UART.handle_irq());
+    for irq in [KEYBOARD_IRQ, MOUSE_IRQ, BLOCK_IRQ, UART_IRQ] {
+        plic.enable(hart_id, IntrTargetPriority::Supervisor, irq as usize);
+        plic.set_priority(irq as usize, 1);
     }
     unsafe {
         sie::set_sext();
     }
 }
 
+/// Undo device_init and stop the block device, leaving no interrupt enabled
+/// and no DMA going on, for whatever runs on the machine next.
+pub fn device_teardown() {
+    use riscv::register::sie;
+    let mut plic = unsafe { PLIC::new(VIRT_PLIC) };
+    let hart_id: usize = 0;
+    unsafe {
+        sie::clear_sext();
+    }
+    for irq in [KEYBOARD_IRQ, MOUSE_IRQ, BLOCK_IRQ, UART_IRQ] {
+        plic.disable(hart_id, IntrTargetPriority::Supervisor, irq as usize);
+        plic.set_priority(irq as usize, 0);
+    }
+    virtio_blk_reset();
+}
+
 pub fn irq_handler() {
     let mut plic = unsafe { PLIC::new(VIRT_PLIC) };
     let intr_src_id = plic.claim(0, IntrTargetPriority::Supervisor);
-    match intr_src_id {
-        5 => KEYBOARD_DEVICE.handle_irq(),
-        6 => MOUSE_DEVICE.handle_irq(),
-        8 => BLOCK_DEVICE.handle_irq(),
-        10 => UART.handle_irq(),
-        _ => panic!("unsupported IRQ {}", intr_src_id),
+    // spurious, nothing to complete
+    if intr_src_id == IRQ_NONE {
+        return;
+    }
+    if !dispatch_irq(intr_src_id as u16) {
+        warn!("no handler for IRQ {}", intr_src_id);
     }
     plic.complete(0, IntrTargetPriority::Supervisor, intr_src_id);
 }
 
+/// A byte sent through the UART in loopback raises its interrupt, which
+/// reaches a handler registered in place of the UART one through the PLIC.
+pub fn irq_test() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use riscv::register::sstatus;
+    static HANDLED: AtomicUsize = AtomicUsize::new(0);
+    const TIMEOUT_MS: usize = 100;
+
+    let uart_handler = register_irq(UART_IRQ, || {
+        HANDLED.fetch_add(1, Ordering::Relaxed);
+        UART.handle_irq();
+    })
+    .unwrap();
+    // anything typed before is not part of the test
+    UART.discard_received();
+    // nothing may be printed while in loopback, it would be received too
+    UART.set_loopback(true);
+    UART.write(b'!');
+    let expire_ms = crate::timer::get_time_ms() + TIMEOUT_MS;
+    unsafe {
+        sstatus::set_sie();
+    }
+    while HANDLED.load(Ordering::Relaxed) == 0 && crate::timer::get_time_ms() < expire_ms {}
+    unsafe {
+        sstatus::clear_sie();
+    }
+    UART.set_loopback(false);
+    register_irq(UART_IRQ, uart_handler);
+    assert_ne!(HANDLED.load(Ordering::Relaxed), 0);
+    // only the byte sent counts, whatever else arrived with it
+    let mut received = false;
+    while !UART.read_buffer_is_empty() {
+        received |= UART.read() == b'!';
+    }
+    assert!(received);
+    println!("irq_test passed!");
+}
+
 //ref:: https://github.com/andre-richter/qemu-exit
 use core::arch::asm;
 
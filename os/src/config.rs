@@ -1,11 +1,67 @@
 #[allow(unused)]
 
 pub const USER_STACK_SIZE: usize = 4096 * 2;
+/// bytes at the top of the user stack of each thread set aside for its
+/// thread-local storage, which tp points to
+pub const USER_TLS_SIZE: usize = 0x100;
 pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
+/// tasks alive at once, zombies not reaped yet included. Each holds a kernel
+/// stack, so fork and thread creation fail beyond it instead of running the
+/// kernel out of memory
+pub const MAX_TASKS: usize = 256;
+/// stack a hart runs on from boot until it switches to the first task,
+/// which is where rust_main and the boot-time tests run. Must keep sp
+/// 16-byte aligned for the RISC-V ABI
+pub const BOOT_STACK_SIZE: usize = 4096 * 16;
+/// harts given a boot stack of their own, the others are parked at boot.
+/// Only the hart SBI boots on runs the kernel for now
+pub const MAX_HARTS: usize = 4;
+/// room left for sys_sbrk between the end of an app and its user stacks
+pub const USER_HEAP_SIZE: usize = 0x100_0000;
+/// a position-independent (ET_DYN) app is loaded here, where the other apps
+/// are linked, so that page 0 stays unmapped
+pub const PIE_BASE: usize = 0x10000;
+/// sys_mmap maps below this, in the lower half of the Sv39 address space
+pub const MMAP_END: usize = 1 << 38;
+/// syscall ids counted one by one in TaskInfo, larger ones share the last count
+pub const MAX_SYSCALL_NUM: usize = 500;
 pub const KERNEL_HEAP_SIZE: usize = 0x100_0000;
 pub const MEMORY_END: usize = 0x88000000;
+/// memory nodes a frame allocation can be hinted to
+pub const MEMORY_NODES: usize = 1;
 pub const PAGE_SIZE: usize = 0x1000;
 pub const PAGE_SIZE_BITS: usize = 0xc;
+/// a megapage of Sv39, mapped by a leaf in the middle level of a page table
+pub const HUGE_PAGE_SIZE: usize = 0x20_0000;
+/// how long a task runs before the timer interrupt switches to the next one,
+/// rounded up to whole timer ticks
+pub const TIME_SLICE_MS: usize = 10;
+/// allow initproc to make kernel operations fail on purpose with
+/// sys_fault_inject, in debug builds only
+pub const FAULT_INJECTION: bool = false;
+/// fill freed frames with a poison byte and check that it is intact when
+/// they are handed out again, which costs a pass over every frame both ways
+pub const FRAME_POISON: bool = cfg!(debug_assertions);
+/// fork shares the user pages copy-on-write instead of copying them
+pub const COW_FORK: bool = true;
+/// allow sys_kexec to replace the running kernel, in debug builds and for
+/// initproc only since there are no user ids to tell a privileged user by
+pub const KEXEC: bool = false;
+/// physical address a kexec'ed image is copied to, above the running kernel
+pub const KEXEC_LOAD_ADDR: usize = 0x8600_0000;
+pub const KEXEC_MAX_SIZE: usize = 0x40_0000;
+/// kexec into an image exiting QEMU at the end of the boot tests instead of
+/// running initproc, set by `make kexec-test`
+pub const KEXEC_TEST: bool = option_env!("KEXEC_TEST").is_some();
+/// kernel messages above this level are dropped, one of OFF, ERROR, WARN,
+/// INFO, DEBUG or TRACE, taken from the LOG environment variable at build
+/// time, see console::set_log_level to change it at runtime
+pub const LOG: &str = match option_env!("LOG") {
+    Some(level) => level,
+    None => "INFO",
+};
+/// color log messages by level, turn off when the console is not a terminal
+pub const LOG_COLOR: bool = true;
 
 pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
 pub const TRAP_CONTEXT_BASE: usize = TRAMPOLINE - PAGE_SIZE;
@@ -1,22 +1,199 @@
+use crate::config::{LOG, LOG_COLOR};
 use crate::drivers::chardev::CharDevice;
 use crate::drivers::chardev::UART;
+use crate::sbi::console_putchar;
+use crate::sync::UPIntrFreeCell;
+use alloc::string::String;
 use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use lazy_static::*;
+
+/// Set by `init` once the UART is mapped in the kernel address space and
+/// initialized. Output before that goes through SBI, which needs neither the
+/// mapping nor the heap the UART driver is allocated on.
+static UART_READY: AtomicBool = AtomicBool::new(false);
+/// bytes printed through SBI so far
+static SBI_BYTES: AtomicUsize = AtomicUsize::new(0);
 
 struct Stdout;
 
+impl Stdout {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if UART_READY.load(Ordering::Relaxed) {
+            for &byte in bytes {
+                UART.write(byte);
+            }
+        } else {
+            for &byte in bytes {
+                console_putchar(byte as usize);
+            }
+            SBI_BYTES.fetch_add(bytes.len(), Ordering::Relaxed);
+        }
+    }
+}
+
 impl Write for Stdout {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        for c in s.chars() {
-            UART.write(c as u8);
-        }
+        self.write_bytes(s.as_bytes());
         Ok(())
     }
 }
 
+lazy_static! {
+    /// output kept away from the UART while log_test checks it
+    static ref CAPTURED: UPIntrFreeCell<Option<String>> = unsafe { UPIntrFreeCell::new(None) };
+}
+
 pub fn print(args: fmt::Arguments) {
+    if let Some(captured) = CAPTURED.exclusive_access().as_mut() {
+        captured.write_fmt(args).unwrap();
+        return;
+    }
     Stdout.write_fmt(args).unwrap();
 }
 
+/// Print raw bytes, which need not be valid UTF-8 on their own, e.g. part
+/// of a character the rest of which comes in the next call.
+pub fn print_bytes(bytes: &[u8]) {
+    if let Some(captured) = CAPTURED.exclusive_access().as_mut() {
+        captured.push_str(&String::from_utf8_lossy(bytes));
+        return;
+    }
+    Stdout.write_bytes(bytes);
+}
+
+/// Messages of a level are printed if it is not above the log level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    /// unknown names fall back to Info
+    const fn from_name(name: &str) -> Self {
+        match name.as_bytes() {
+            b"OFF" => Self::Off,
+            b"ERROR" => Self::Error,
+            b"WARN" => Self::Warn,
+            b"DEBUG" => Self::Debug,
+            b"TRACE" => Self::Trace,
+            _ => Self::Info,
+        }
+    }
+    fn name(self) -> &'static str {
+        match self {
+            Self::Off => "OFF",
+            Self::Error => "ERROR",
+            Self::Warn => "WARN",
+            Self::Info => "INFO",
+            Self::Debug => "DEBUG",
+            Self::Trace => "TRACE",
+        }
+    }
+    /// ANSI color of the messages
+    fn color(self) -> u8 {
+        match self {
+            Self::Off => 0,
+            Self::Error => 31,
+            Self::Warn => 93,
+            Self::Info => 34,
+            Self::Debug => 32,
+            Self::Trace => 90,
+        }
+    }
+}
+
+static LOG_LEVEL: AtomicUsize = AtomicUsize::new(LogLevel::from_name(LOG) as usize);
+
+pub fn set_log_level(level: LogLevel) {
+    LOG_LEVEL.store(level as usize, Ordering::Relaxed);
+}
+
+/// Checked by the log macros before formatting anything.
+#[inline]
+pub fn log_enabled(level: LogLevel) -> bool {
+    level as usize <= LOG_LEVEL.load(Ordering::Relaxed)
+}
+
+/// `file` and `line` are those of the log macro call.
+pub fn log(level: LogLevel, file: &str, line: u32, args: fmt::Arguments) {
+    if LOG_COLOR {
+        print(format_args!(
+            "\x1b[{}m[{} {}:{}] {}\x1b[0m\n",
+            level.color(),
+            level.name(),
+            file,
+            line,
+            args
+        ));
+    } else {
+        print(format_args!(
+            "[{} {}:{}] {}\n",
+            level.name(),
+            file,
+            line,
+            args
+        ));
+    }
+}
+
+const BS: u8 = 0x08;
+const DEL: u8 = 0x7f;
+/// Ctrl-U
+const NAK: u8 = 0x15;
+
+/// Erase the last character on the terminal.
+fn erase_char() {
+    for c in [BS, b' ', BS] {
+        UART.write(c);
+    }
+}
+
+/// Read one line from the console into buf, echoing what is typed, and
+/// return its length including the ending `\n`. Backspace erases the last
+/// character and Ctrl-U the whole line. The last byte of buf is kept for
+/// the `\n`, characters typed past that are dropped without an echo.
+pub fn read_line(buf: &mut [u8]) -> usize {
+    if buf.is_empty() {
+        return 0;
+    }
+    let capacity = buf.len() - 1;
+    let mut len = 0;
+    loop {
+        match UART.read() {
+            b'\r' | b'\n' => {
+                UART.write(b'\n');
+                buf[len] = b'\n';
+                return len + 1;
+            }
+            BS | DEL => {
+                if len > 0 {
+                    len -= 1;
+                    erase_char();
+                }
+            }
+            NAK => {
+                while len > 0 {
+                    len -= 1;
+                    erase_char();
+                }
+            }
+            c => {
+                if len < capacity {
+                    buf[len] = c;
+                    len += 1;
+                    UART.write(c);
+                }
+            }
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! print {
     ($fmt: literal $(, $($arg: tt)+)?) => {
@@ -30,3 +207,116 @@ macro_rules! println {
         $crate::console::print(format_args!(concat!($fmt, "\n") $(, $($arg)+)?))
     }
 }
+
+#[macro_export]
+macro_rules! log {
+    ($level: expr, $fmt: literal $(, $($arg: tt)+)?) => {
+        // nothing is formatted if the level is disabled
+        if $crate::console::log_enabled($level) {
+            $crate::console::log($level, file!(), line!(), format_args!($fmt $(, $($arg)+)?));
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! error {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        $crate::log!($crate::console::LogLevel::Error, $fmt $(, $($arg)+)?)
+    }
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        $crate::log!($crate::console::LogLevel::Warn, $fmt $(, $($arg)+)?)
+    }
+}
+
+#[macro_export]
+macro_rules! info {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        $crate::log!($crate::console::LogLevel::Info, $fmt $(, $($arg)+)?)
+    }
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        $crate::log!($crate::console::LogLevel::Debug, $fmt $(, $($arg)+)?)
+    }
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($fmt: literal $(, $($arg: tt)+)?) => {
+        $crate::log!($crate::console::LogLevel::Trace, $fmt $(, $($arg)+)?)
+    }
+}
+
+/// Print through the UART from now on, which must be mapped already.
+pub fn init() {
+    UART.init();
+    UART_READY.store(true, Ordering::Relaxed);
+    println!(
+        "[kernel] console on the UART, {} bytes printed through SBI before",
+        SBI_BYTES.load(Ordering::Relaxed)
+    );
+}
+
+/// Log at every level with the log level set to each one in turn, the
+/// messages tell where they are logged.
+pub fn log_test() {
+    let saved = LOG_LEVEL.load(Ordering::Relaxed);
+    let levels = [
+        LogLevel::Error,
+        LogLevel::Warn,
+        LogLevel::Info,
+        LogLevel::Debug,
+        LogLevel::Trace,
+    ];
+    for threshold in [LogLevel::Off].iter().chain(levels.iter()) {
+        set_log_level(*threshold);
+        *CAPTURED.exclusive_access() = Some(String::new());
+        let line = line!();
+        error!("log {}", 1);
+        warn!("log {}", 2);
+        info!("log {}", 3);
+        debug!("log {}", 4);
+        trace!("log {}", 5);
+        let captured = CAPTURED.exclusive_access().take().unwrap();
+        let mut expected = String::new();
+        for (i, level) in levels.iter().enumerate() {
+            if level > threshold {
+                continue;
+            }
+            let message = alloc::format!(
+                "[{} src/console.rs:{}] log {}",
+                level.name(),
+                line as usize + i + 1,
+                i + 1
+            );
+            expected += &if LOG_COLOR {
+                let colors = [31, 93, 34, 32, 90];
+                alloc::format!("\x1b[{}m{}\x1b[0m\n", colors[i], message)
+            } else {
+                alloc::format!("{}\n", message)
+            };
+        }
+        assert_eq!(captured, expected);
+    }
+    LOG_LEVEL.store(saved, Ordering::Relaxed);
+    println!("log_test passed!");
+}
+
+const UART_BURST_LINES: usize = 32;
+
+/// Print a burst of lines, which must all go out through the UART.
+pub fn uart_burst_test() {
+    assert!(UART_READY.load(Ordering::Relaxed));
+    let sbi_bytes = SBI_BYTES.load(Ordering::Relaxed);
+    for i in 0..UART_BURST_LINES {
+        println!("uart burst {:>2}: {:=<64}", i, "");
+    }
+    assert_eq!(SBI_BYTES.load(Ordering::Relaxed), sbi_bytes);
+    println!("uart_burst_test passed!");
+}
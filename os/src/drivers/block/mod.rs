@@ -1,6 +1,6 @@
 mod virtio_blk;
 
-pub use virtio_blk::VirtIOBlock;
+pub use virtio_blk::{virtio_blk_reset, VirtIOBlock};
 
 use crate::board::BlockDeviceImpl;
 use alloc::sync::Arc;
@@ -8,6 +8,16 @@ use virtio_drivers::{BlkResp, RespStatus, VirtIOBlk, VirtIOHeader};
 
 #[allow(unused)]
 const VIRTIO0: usize = 0x10008000;
+/// offset of the device status register, writing 0 to it resets the device
+const VIRTIO_MMIO_STATUS: usize = 0x70;
+
+/// Reset the device, which drops the requests in flight so that it no
+/// longer writes to memory, e.g. once handed over to a kexec'ed image.
+pub fn virtio_blk_reset() {
+    unsafe {
+        ((VIRTIO0 + VIRTIO_MMIO_STATUS) as *mut u32).write_volatile(0);
+    }
+}
 
 pub struct VirtIOBlock {
     virtio_blk: UPIntrFreeCell<VirtIOBlk<'static, VirtioHal>>,
@@ -1,6 +1,6 @@
 use crate::mm::{
-    frame_alloc, frame_dealloc, kernel_token, FrameTracker, PageTable, PhysAddr, PhysPageNum,
-    StepByOne, VirtAddr,
+    frame_alloc, frame_dealloc, kernel_token, FrameTracker, PPNRange, PageTable, PhysAddr,
+    PhysPageNum, VirtAddr,
 };
 use crate::sync::UPIntrFreeCell;
 use alloc::vec::Vec;
@@ -31,10 +31,9 @@ impl Hal for VirtioHal {
 
     fn dma_dealloc(pa: usize, pages: usize) -> i32 {
         let pa = PhysAddr::from(pa);
-        let mut ppn_base: PhysPageNum = pa.into();
-        for _ in 0..pages {
-            frame_dealloc(ppn_base);
-            ppn_base.step();
+        let ppn_base: PhysPageNum = pa.into();
+        for ppn in PPNRange::new(ppn_base, ppn_base + pages) {
+            frame_dealloc(ppn);
         }
         0
     }
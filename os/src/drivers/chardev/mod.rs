@@ -3,7 +3,7 @@ mod ns16550a;
 use crate::board::CharDeviceImpl;
 use alloc::sync::Arc;
 use lazy_static::*;
-pub use ns16550a::NS16550a;
+pub use ns16550a::{rx_ring_test, uart_rx_test, NS16550a};
 
 pub trait CharDevice {
     fn init(&self);
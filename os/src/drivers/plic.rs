@@ -1,3 +1,7 @@
+use crate::sync::UPIntrFreeCell;
+use alloc::collections::BTreeMap;
+use lazy_static::*;
+
 #[allow(clippy::upper_case_acronyms)]
 pub struct PLIC {
     base_addr: usize,
@@ -56,6 +60,12 @@ impl PLIC {
     pub unsafe fn new(base_addr: usize) -> Self {
         Self { base_addr }
     }
+    /// Take the interrupts of every priority in supervisor mode on the hart,
+    /// and none in machine mode, which are left to SBI.
+    pub fn init_hart(&mut self, hart_id: usize) {
+        self.set_threshold(hart_id, IntrTargetPriority::Supervisor, 0);
+        self.set_threshold(hart_id, IntrTargetPriority::Machine, 1);
+    }
     pub fn set_priority(&mut self, intr_source_id: usize, priority: u32) {
         assert!(priority < 8);
         unsafe {
@@ -77,7 +87,6 @@ impl PLIC {
             reg_ptr.write_volatile(reg_ptr.read_volatile() | 1 << shift);
         }
     }
-    #[allow(unused)]
     pub fn disable(
         &mut self,
         hart_id: usize,
@@ -106,6 +115,8 @@ impl PLIC {
         let threshold_ptr = self.threshold_ptr_of_hart_with_priority(hart_id, target_priority);
         unsafe { threshold_ptr.read_volatile() & 7 }
     }
+    /// The highest priority interrupt pending, or IRQ_NONE if there is none
+    /// left, e.g. when another hart claimed it first.
     pub fn claim(&mut self, hart_id: usize, target_priority: IntrTargetPriority) -> u32 {
         let claim_comp_ptr = self.claim_comp_ptr_of_hart_with_priority(hart_id, target_priority);
         unsafe { claim_comp_ptr.read_volatile() }
@@ -122,3 +133,32 @@ impl PLIC {
         }
     }
 }
+
+/// claimed when no interrupt is pending, source 0 does not exist
+pub const IRQ_NONE: u32 = 0;
+
+pub type IrqHandler = fn();
+
+lazy_static! {
+    static ref IRQ_HANDLERS: UPIntrFreeCell<BTreeMap<u16, IrqHandler>> =
+        unsafe { UPIntrFreeCell::new(BTreeMap::new()) };
+}
+
+/// Call handler for each interrupt from source irq, return the handler it
+/// replaces.
+pub fn register_irq(irq: u16, handler: IrqHandler) -> Option<IrqHandler> {
+    IRQ_HANDLERS.exclusive_access().insert(irq, handler)
+}
+
+/// Run the handler of irq, return false if there is none.
+pub fn dispatch_irq(irq: u16) -> bool {
+    // a handler may register others, so it runs with the table released
+    let handler = IRQ_HANDLERS.exclusive_access().get(&irq).copied();
+    match handler {
+        Some(handler) => {
+            handler();
+            true
+        }
+        None => false,
+    }
+}
@@ -1,12 +1,25 @@
     .section .text.entry
     .globl _start
 _start:
-    la sp, boot_stack_top
+    # a0 holds the hartid, each hart boots on its own stack and the harts
+    # with no stack wait forever
+    li t0, {max_harts}
+    bgeu a0, t0, park
+    addi t0, a0, 1
+    li t1, {boot_stack_size}
+    mul t0, t0, t1
+    la sp, boot_stack_lower_bound
+    add sp, sp, t0
     call rust_main
+park:
+    wfi
+    j park
 
     .section .bss.stack
+    # the RISC-V ABI wants sp 16-byte aligned, a page is plenty
+    .p2align 12
     .globl boot_stack_lower_bound
 boot_stack_lower_bound:
-    .space 4096 * 16
+    .space {boot_stack_size} * {max_harts}
     .globl boot_stack_top
 boot_stack_top:
@@ -0,0 +1,150 @@
+//! Failures of selected kernel operations, armed by the boot test or from
+//! user space with `sys_fault_inject` so that tests can reach rare error
+//! paths.
+
+use crate::config::FAULT_INJECTION;
+use crate::fs::{open_file, OpenFlags};
+use crate::mm::{frames_allocated, MapPermission, MemorySet, VirtPageNum};
+use crate::sync::UPIntrFreeCell;
+use crate::timer::get_time;
+use lazy_static::*;
+
+/// Faults are only injected in debug builds, and only armed from user space
+/// with FAULT_INJECTION.
+pub const ENABLED: bool = FAULT_INJECTION && cfg!(debug_assertions);
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FaultKind {
+    /// `frame_alloc` returns `None`
+    FrameAlloc = 0,
+    /// exec rejects the ELF image
+    ElfParse = 1,
+    /// `copy_from_user` reads from an address that faults in the kernel
+    UserCopy = 2,
+    /// arming it makes sys_fault_inject itself recurse until the kernel
+    /// stack overflows
+    KernelStackOverflow = 3,
+}
+
+const FAULT_KIND_NUM: usize = 4;
+
+impl FaultKind {
+    pub fn from_usize(kind: usize) -> Option<Self> {
+        match kind {
+            0 => Some(Self::FrameAlloc),
+            1 => Some(Self::ElfParse),
+            2 => Some(Self::UserCopy),
+            3 => Some(Self::KernelStackOverflow),
+            _ => None,
+        }
+    }
+}
+
+struct FaultInjector {
+    /// how many of the next operations are still subject to failure
+    remaining: [usize; FAULT_KIND_NUM],
+    /// chance that each of them fails, in percent
+    probability: [usize; FAULT_KIND_NUM],
+    /// xorshift state
+    seed: u64,
+}
+
+impl FaultInjector {
+    fn new() -> Self {
+        Self {
+            remaining: [0; FAULT_KIND_NUM],
+            probability: [0; FAULT_KIND_NUM],
+            seed: 0,
+        }
+    }
+    fn rand(&mut self) -> u64 {
+        let mut x = self.seed;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.seed = x;
+        x
+    }
+    fn arm(&mut self, kind: FaultKind, times: usize, probability: usize) {
+        self.remaining[kind as usize] = times;
+        self.probability[kind as usize] = probability;
+        // xorshift never leaves the all-zero state
+        self.seed = get_time() as u64 | 1;
+    }
+    fn should_fail(&mut self, kind: FaultKind) -> bool {
+        let kind = kind as usize;
+        if self.remaining[kind] == 0 {
+            return false;
+        }
+        self.remaining[kind] -= 1;
+        let probability = self.probability[kind];
+        probability >= 100 || (self.rand() % 100) < probability as u64
+    }
+}
+
+lazy_static! {
+    static ref FAULT_INJECTOR: UPIntrFreeCell<FaultInjector> =
+        unsafe { UPIntrFreeCell::new(FaultInjector::new()) };
+}
+
+/// Each of the next `times` operations of `kind` fails with the given
+/// probability in percent. `times == 0` disarms it. A kernel stack overflow
+/// happens right away instead, if at all, so that no hot path checks for it.
+pub fn arm_fault(kind: FaultKind, times: usize, probability: usize) {
+    FAULT_INJECTOR
+        .exclusive_access()
+        .arm(kind, times, probability);
+    if kind == FaultKind::KernelStackOverflow && should_fail(kind) {
+        overflow_kernel_stack(0);
+    }
+}
+
+/// Recurse until the guard page below the kernel stack is hit.
+#[allow(unconditional_recursion)]
+fn overflow_kernel_stack(depth: usize) -> usize {
+    let mut frame = [depth; 32];
+    let sum = overflow_kernel_stack(depth + 1);
+    // keep the frame alive across the call
+    unsafe { core::ptr::write_volatile(&mut frame[0], sum) };
+    unsafe { core::ptr::read_volatile(&frame[31]) }
+}
+
+/// The injection hook, called by the operation before doing any work.
+pub fn should_fail(kind: FaultKind) -> bool {
+    cfg!(debug_assertions) && FAULT_INJECTOR.exclusive_access().should_fail(kind)
+}
+
+/// Populating a mapping as sys_mmap does for MAP_POPULATE runs out of
+/// frames part way: the whole mapping is removed again, with the frames of
+/// the pages populated before. Then exec rejects an image once.
+pub fn fault_inject_test() {
+    let mut memory_set = MemorySet::new_bare();
+    let (start, end) = (VirtPageNum(0x10000), VirtPageNum(0x10004));
+    memory_set.insert_lazy_area(
+        start.into(),
+        end.into(),
+        MapPermission::R | MapPermission::W | MapPermission::U,
+    );
+    // the first page brings the page tables for the others
+    assert!(memory_set.handle_lazy_fault(start));
+    let frames = frames_allocated();
+    assert!(memory_set.handle_lazy_fault(start + 1));
+    arm_fault(FaultKind::FrameAlloc, 1, 100);
+    assert!(!memory_set.populate_area(start, end));
+    assert_eq!(memory_set.is_resident(start), None);
+    assert_eq!(frames_allocated(), frames - 1);
+    // only the armed allocation failed
+    memory_set.insert_lazy_area(
+        start.into(),
+        end.into(),
+        MapPermission::R | MapPermission::U,
+    );
+    assert!(memory_set.populate_area(start, end));
+    drop(memory_set);
+
+    let elf_data = open_file("initproc", OpenFlags::RDONLY).unwrap().read_all();
+    arm_fault(FaultKind::ElfParse, 1, 100);
+    assert!(!MemorySet::elf_is_valid(&elf_data));
+    assert!(MemorySet::elf_is_valid(&elf_data));
+    println!("fault_inject_test passed!");
+}
@@ -1,19 +1,46 @@
-use super::File;
+use super::{File, Stat, StatMode};
 use crate::drivers::BLOCK_DEVICE;
 use crate::mm::UserBuffer;
 use crate::sync::UPIntrFreeCell;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
 use bitflags::*;
-use easy_fs::{EasyFileSystem, Inode};
+use easy_fs::{block_cache_sync_all, EasyFileSystem, Inode, DIRENT_SZ};
 use lazy_static::*;
 
+/// a record of `OSInode::read_dirents` starts with the inode number and the
+/// length of the name
+const DIRENT_HEADER_SIZE: usize = 8;
+const DIRENT_ALIGN: usize = 4;
+
+/// whence of `OSInode::seek`
+const SEEK_SET: usize = 0;
+const SEEK_CUR: usize = 1;
+const SEEK_END: usize = 2;
+
+/// An open regular file or directory. The offset belongs to the open file,
+/// so fds dup'ed from each other share it, files opened twice do not.
 pub struct OSInode {
     readable: bool,
     writable: bool,
+    ino: u32,
     inner: UPIntrFreeCell<OSInodeInner>,
 }
 
+/// The number of files open on an inode, and whether it was unlinked, in
+/// which case the last one to be closed frees it.
+struct OpenInode {
+    count: usize,
+    unlinked: bool,
+}
+
+lazy_static! {
+    static ref OPEN_INODES: UPIntrFreeCell<BTreeMap<u32, OpenInode>> =
+        unsafe { UPIntrFreeCell::new(BTreeMap::new()) };
+}
+
 pub struct OSInodeInner {
     offset: usize,
     inode: Arc<Inode>,
@@ -21,9 +48,19 @@ pub struct OSInodeInner {
 
 impl OSInode {
     pub fn new(readable: bool, writable: bool, inode: Arc<Inode>) -> Self {
+        let ino = inode.inode_id();
+        OPEN_INODES
+            .exclusive_access()
+            .entry(ino)
+            .or_insert(OpenInode {
+                count: 0,
+                unlinked: false,
+            })
+            .count += 1;
         Self {
             readable,
             writable,
+            ino,
             inner: unsafe { UPIntrFreeCell::new(OSInodeInner { offset: 0, inode }) },
         }
     }
@@ -41,6 +78,71 @@ impl OSInode {
         }
         v
     }
+    /// Move the offset to `offset` bytes from the start, the current offset
+    /// or the end of the file as `whence` says, return the new offset, or
+    /// None if whence is unknown or the offset would be negative. The offset
+    /// may go past the end, a write there leaves a hole of zeros.
+    pub fn seek(&self, offset: isize, whence: usize) -> Option<usize> {
+        let mut inner = self.inner.exclusive_access();
+        let base = match whence {
+            SEEK_SET => 0,
+            SEEK_CUR => inner.offset,
+            SEEK_END => inner.inode.size(),
+            _ => return None,
+        };
+        let new_offset = (base as isize).checked_add(offset)?;
+        if new_offset < 0 {
+            return None;
+        }
+        inner.offset = new_offset as usize;
+        Some(inner.offset)
+    }
+    pub fn is_dir(&self) -> bool {
+        self.inner.exclusive_access().inode.is_dir()
+    }
+    /// Read from `offset` on into buf, leaving the offset of the file alone,
+    /// return the number of bytes read.
+    pub fn read_at(&self, offset: usize, buf: &mut [u8]) -> usize {
+        self.inner.exclusive_access().inode.read_at(offset, buf)
+    }
+    /// the offset of the file
+    pub fn offset(&self) -> usize {
+        self.inner.exclusive_access().offset
+    }
+    /// Records of the directory entries from the offset on, as many as fit in
+    /// len bytes, moving the offset past them. Each record is the inode
+    /// number and the length of the name as `u32`s followed by the name
+    /// without `\0`, padded to a multiple of `DIRENT_ALIGN` bytes. Return
+    /// None if the next record does not fit, no records at the end of the
+    /// directory. The file must be a directory.
+    pub fn read_dirents(&self, len: usize) -> Option<Vec<u8>> {
+        let mut inner = self.inner.exclusive_access();
+        let mut records = Vec::new();
+        // the offset counts entries on the disk, whatever the record length
+        while let Some((ino, name)) = inner.inode.dirent_at(inner.offset / DIRENT_SZ) {
+            let record_len =
+                (DIRENT_HEADER_SIZE + name.len() + DIRENT_ALIGN - 1) & !(DIRENT_ALIGN - 1);
+            if records.len() + record_len > len {
+                if records.is_empty() {
+                    return None;
+                }
+                break;
+            }
+            records.extend_from_slice(&ino.to_ne_bytes());
+            records.extend_from_slice(&(name.len() as u32).to_ne_bytes());
+            records.extend_from_slice(name.as_bytes());
+            records.resize(
+                records.len() + record_len - DIRENT_HEADER_SIZE - name.len(),
+                0,
+            );
+            inner.offset += DIRENT_SZ;
+        }
+        Some(records)
+    }
+    /// whether `[offset, offset + len)` of the file is in the block cache
+    pub fn is_resident(&self, offset: usize, len: usize) -> bool {
+        self.inner.exclusive_access().inode.is_resident(offset, len)
+    }
 }
 
 lazy_static! {
@@ -50,6 +152,11 @@ lazy_static! {
     };
 }
 
+/// Write the modified blocks in the block cache back to the disk.
+pub fn sync_all() {
+    block_cache_sync_all();
+}
+
 pub fn list_apps() {
     println!("/**** APPS ****");
     for app in ROOT_INODE.ls() {
@@ -82,27 +189,103 @@ impl OpenFlags {
     }
 }
 
-pub fn open_file(name: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
-    let (readable, writable) = flags.read_write();
-    if flags.contains(OpenFlags::CREATE) {
-        if let Some(inode) = ROOT_INODE.find(name) {
-            // clear size
-            inode.clear();
-            Some(Arc::new(OSInode::new(readable, writable, inode)))
-        } else {
-            // create file
-            ROOT_INODE
-                .create(name)
-                .map(|inode| Arc::new(OSInode::new(readable, writable, inode)))
+/// The absolute path of `path` taken from the directory `cwd`, without
+/// `.`, `..` or empty components. `..` of the root is the root.
+pub fn absolute_path(cwd: &str, path: &str) -> String {
+    let base = if path.starts_with('/') { "" } else { cwd };
+    let mut components: Vec<&str> = Vec::new();
+    for component in base.split('/').chain(path.split('/')) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop();
+            }
+            name => components.push(name),
         }
-    } else {
-        ROOT_INODE.find(name).map(|inode| {
-            if flags.contains(OpenFlags::TRUNC) {
-                inode.clear();
+    }
+    let mut absolute = String::from("/");
+    absolute.push_str(&components.join("/"));
+    absolute
+}
+
+/// The inode at `path`, which is taken from the root directory.
+pub fn find_inode(path: &str) -> Option<Arc<Inode>> {
+    path.split('/')
+        .filter(|name| !name.is_empty())
+        .try_fold(ROOT_INODE.clone(), |dir, name| {
+            if dir.is_dir() {
+                dir.find(name)
+            } else {
+                None
             }
-            Arc::new(OSInode::new(readable, writable, inode))
         })
+}
+
+/// The directory `path` is in and its last component, taken from the root
+/// directory. None if the directory does not exist or path is the root.
+fn parent_of(path: &str) -> Option<(Arc<Inode>, &str)> {
+    let path = path.trim_end_matches('/');
+    let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+    if name.is_empty() {
+        return None;
+    }
+    find_inode(parent)
+        .filter(|dir| dir.is_dir())
+        .map(|dir| (dir, name))
+}
+
+/// Create an empty directory at `path`, taken from the root directory.
+/// Return None if something is there already or its parent does not exist.
+pub fn create_dir(path: &str) -> Option<Arc<Inode>> {
+    let (parent, name) = parent_of(path)?;
+    parent.create_dir(name)
+}
+
+/// Remove the file or empty directory at `path`, taken from the root
+/// directory. Its blocks are freed at once, or when the last file open on it
+/// is closed. Return false if nothing is there or it is a directory that is
+/// not empty.
+pub fn unlink(path: &str) -> bool {
+    let inode = match parent_of(path).and_then(|(parent, name)| parent.unlink(name)) {
+        Some(inode) => inode,
+        None => return false,
+    };
+    let open = OPEN_INODES
+        .exclusive_access()
+        .get_mut(&inode.inode_id())
+        .map(|open| open.unlinked = true)
+        .is_some();
+    if !open {
+        inode.release();
     }
+    true
+}
+
+/// Open the file at `path`, which is taken from the root directory.
+/// Directories can only be opened read-only.
+pub fn open_file(path: &str, flags: OpenFlags) -> Option<Arc<OSInode>> {
+    let (readable, writable) = flags.read_write();
+    let inode = match find_inode(path) {
+        Some(inode) if inode.is_dir() => {
+            if writable || flags.intersects(OpenFlags::CREATE | OpenFlags::TRUNC) {
+                return None;
+            }
+            inode
+        }
+        Some(inode) => {
+            if flags.intersects(OpenFlags::CREATE | OpenFlags::TRUNC) {
+                // clear size
+                inode.clear();
+            }
+            inode
+        }
+        None if flags.contains(OpenFlags::CREATE) => {
+            let (parent, name) = parent_of(path)?;
+            parent.create(name)?
+        }
+        None => return None,
+    };
+    Some(Arc::new(OSInode::new(readable, writable, inode)))
 }
 
 impl File for OSInode {
@@ -136,4 +319,42 @@ impl File for OSInode {
         }
         total_write_size
     }
+    fn stat(&self) -> Stat {
+        let inode = &self.inner.exclusive_access().inode;
+        Stat {
+            dev: 0,
+            ino: inode.inode_id() as u64,
+            mode: if inode.is_dir() {
+                StatMode::DIR
+            } else {
+                StatMode::FILE
+            },
+            nlink: match OPEN_INODES.exclusive_access().get(&self.ino) {
+                Some(open) if open.unlinked => 0,
+                _ => 1,
+            },
+            size: inode.size() as u64,
+        }
+    }
+    fn as_inode(&self) -> Option<&OSInode> {
+        Some(self)
+    }
+}
+
+impl Drop for OSInode {
+    fn drop(&mut self) {
+        let mut open_inodes = OPEN_INODES.exclusive_access();
+        let open = open_inodes.get_mut(&self.ino).unwrap();
+        open.count -= 1;
+        if open.count > 0 {
+            return;
+        }
+        let unlinked = open_inodes.remove(&self.ino).unwrap().unlinked;
+        drop(open_inodes);
+        if unlinked {
+            // freeing waits for the disk, with no cell borrowed
+            let inode = self.inner.exclusive_access().inode.clone();
+            inode.release();
+        }
+    }
 }
@@ -1,16 +1,95 @@
 mod inode;
 mod pipe;
+mod procfs;
+mod signalfd;
+mod socket;
 mod stdio;
+mod timerfd;
 
 use crate::mm::UserBuffer;
+use bitflags::*;
+
+bitflags! {
+    /// type of a file, as in the `st_mode` of POSIX
+    pub struct StatMode: u32 {
+        const FIFO = 0o010000;
+        const CHR = 0o020000;
+        const DIR = 0o040000;
+        const FILE = 0o100000;
+        const SOCK = 0o140000;
+    }
+}
+
+/// Metadata of an open file, as reported by sys_fstat.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Stat {
+    /// device the file is on, there is only the one of easy-fs
+    pub dev: u64,
+    /// inode number, 0 for files not on the filesystem
+    pub ino: u64,
+    pub mode: StatMode,
+    /// number of hard links
+    pub nlink: u32,
+    /// size in bytes, 0 for files other than regular files and directories
+    pub size: u64,
+}
+
+impl Stat {
+    /// a file not on the filesystem
+    pub fn new(mode: StatMode) -> Self {
+        Self {
+            dev: 0,
+            ino: 0,
+            mode,
+            nlink: 1,
+            size: 0,
+        }
+    }
+}
 
 pub trait File: Send + Sync {
     fn readable(&self) -> bool;
     fn writable(&self) -> bool;
     fn read(&self, buf: UserBuffer) -> usize;
     fn write(&self, buf: UserBuffer) -> usize;
+    /// files of no particular type report none
+    fn stat(&self) -> Stat {
+        Stat::new(StatMode::empty())
+    }
+    /// only sockets can be bound and sent to
+    fn as_socket(&self) -> Option<&Socket> {
+        None
+    }
+    /// shorter reads fail, since the file is only read in whole records
+    fn min_read_len(&self) -> usize {
+        0
+    }
+    /// nothing can be read now and the file is nonblocking
+    fn would_block(&self) -> bool {
+        false
+    }
+    /// only timerfds can be armed
+    fn as_timerfd(&self) -> Option<&TimerFd> {
+        None
+    }
+    /// only signalfds can have their mask changed
+    fn as_signalfd(&self) -> Option<&SignalFd> {
+        None
+    }
+    /// only regular files are kept in the block cache
+    fn as_inode(&self) -> Option<&OSInode> {
+        None
+    }
 }
 
-pub use inode::{list_apps, open_file, OSInode, OpenFlags, ROOT_INODE};
+pub use inode::{
+    absolute_path, create_dir, find_inode, list_apps, open_file, sync_all, unlink, OSInode,
+    OpenFlags, ROOT_INODE,
+};
 pub use pipe::{make_pipe, Pipe};
+pub use procfs::{open_proc, proc_pid, PROC_PREFIX};
+pub use signalfd::{SignalFd, SFD_NONBLOCK};
+pub use socket::{Socket, AF_UNIX, MAX_DATAGRAM_SIZE, SOCK_DGRAM};
 pub use stdio::{Stdin, Stdout};
+pub use timerfd::{ITimerSpec, TimerFd, CLOCK_MONOTONIC, TFD_NONBLOCK};
@@ -1,4 +1,4 @@
-use super::File;
+use super::{File, Stat, StatMode};
 use crate::mm::UserBuffer;
 use crate::sync::UPIntrFreeCell;
 use alloc::sync::{Arc, Weak};
@@ -170,4 +170,7 @@ impl File for Pipe {
             }
         }
     }
+    fn stat(&self) -> Stat {
+        Stat::new(StatMode::FIFO)
+    }
 }
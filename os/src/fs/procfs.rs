@@ -0,0 +1,141 @@
+//! Read-only files under `/proc` generated by the kernel, which are not on
+//! the filesystem.
+
+use super::{File, OpenFlags, Stat, StatMode};
+use crate::mm::{MapPermission, UserBuffer, VirtAddr};
+use crate::sync::UPIntrFreeCell;
+use crate::task::{current_process, pid2process, snapshot_tasks};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+pub const PROC_PREFIX: &str = "/proc/";
+
+/// A file whose text is generated on the first read, later reads go on in
+/// the same text.
+pub struct ProcFile {
+    generate: Box<dyn Fn() -> Vec<u8> + Send + Sync>,
+    inner: UPIntrFreeCell<ProcFileInner>,
+}
+
+struct ProcFileInner {
+    text: Option<Vec<u8>>,
+    offset: usize,
+}
+
+impl ProcFile {
+    fn new(generate: impl Fn() -> Vec<u8> + Send + Sync + 'static) -> Self {
+        Self {
+            generate: Box::new(generate),
+            inner: unsafe {
+                UPIntrFreeCell::new(ProcFileInner {
+                    text: None,
+                    offset: 0,
+                })
+            },
+        }
+    }
+}
+
+/// A listing of every task, one per line after a header line:
+/// `pid tid ppid status user_us kernel_us`.
+fn task_list_text() -> Vec<u8> {
+    // the snapshot holds the task locks only while it is taken, not while
+    // the text is formatted and copied out
+    let tasks = snapshot_tasks();
+    let mut text = String::from("PID TID PPID STATUS USER_US KERNEL_US\n");
+    for task in tasks {
+        text.push_str(&format!(
+            "{} {} {} {} {} {}\n",
+            task.pid, task.tid, task.ppid, task.status, task.user_us, task.kernel_us
+        ));
+    }
+    text.into_bytes()
+}
+
+/// The areas of the process `pid`, one per line: `start-end rwx`, nothing
+/// once it is gone.
+fn maps_text(pid: usize) -> Vec<u8> {
+    let process = match pid2process(pid) {
+        Some(process) => process,
+        None => return Vec::new(),
+    };
+    let areas = process.inner_exclusive_access().memory_set.describe();
+    let mut text = String::new();
+    for area in areas {
+        let perm = |flag, ch| if area.perm.contains(flag) { ch } else { '-' };
+        text.push_str(&format!(
+            "{:08x}-{:08x} {}{}{}\n",
+            VirtAddr::from(area.start).0,
+            VirtAddr::from(area.end).0,
+            perm(MapPermission::R, 'r'),
+            perm(MapPermission::W, 'w'),
+            perm(MapPermission::X, 'x'),
+        ));
+    }
+    text.into_bytes()
+}
+
+/// The pid named by a path under `/proc/<pid>/`, `self` for the current
+/// process.
+pub fn proc_pid(path: &str) -> Option<usize> {
+    match path.strip_prefix(PROC_PREFIX)?.split_once('/')?.0 {
+        "self" => Some(current_process().getpid()),
+        pid => pid.parse().ok(),
+    }
+}
+
+impl File for ProcFile {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, buf: UserBuffer) -> usize {
+        let mut inner = self.inner.exclusive_access();
+        if inner.text.is_none() {
+            // generating the text borrows the tasks, so leave this cell alone
+            drop(inner);
+            let text = (self.generate)();
+            inner = self.inner.exclusive_access();
+            inner.text.get_or_insert(text);
+        }
+        let inner = &mut *inner;
+        let rest = &inner.text.as_ref().unwrap()[inner.offset..];
+        let mut read_size = 0;
+        for (byte, value) in buf.into_iter().zip(rest) {
+            unsafe {
+                *byte = *value;
+            }
+            read_size += 1;
+        }
+        inner.offset += read_size;
+        read_size
+    }
+    fn write(&self, _buf: UserBuffer) -> usize {
+        0
+    }
+    fn stat(&self) -> Stat {
+        Stat::new(StatMode::FILE)
+    }
+}
+
+/// Open the file at `path` under `/proc`, only for reading.
+pub fn open_proc(path: &str, flags: OpenFlags) -> Option<Arc<dyn File + Send + Sync>> {
+    if flags != OpenFlags::RDONLY {
+        return None;
+    }
+    match path.strip_prefix(PROC_PREFIX)? {
+        "tasks" => Some(Arc::new(ProcFile::new(task_list_text))),
+        name => match name.split_once('/')?.1 {
+            "maps" => {
+                let pid = proc_pid(path).filter(|&pid| pid2process(pid).is_some())?;
+                Some(Arc::new(ProcFile::new(move || maps_text(pid))))
+            }
+            _ => None,
+        },
+    }
+}
@@ -0,0 +1,95 @@
+use super::File;
+use crate::mm::UserBuffer;
+use crate::sync::UPIntrFreeCell;
+use crate::task::{current_process, suspend_current_and_run_next, SignalFlags};
+
+pub const SFD_NONBLOCK: usize = 0o4000;
+/// every read returns one record of this size, of which only the leading
+/// `u32` signal number is filled in
+pub const SIGNALFD_SIGINFO_SIZE: usize = 128;
+
+/// Pending signals of the reading process that are in the mask are read
+/// from a signalfd, and are not acted upon while it is open.
+pub struct SignalFd {
+    nonblock: bool,
+    mask: UPIntrFreeCell<SignalFlags>,
+}
+
+impl SignalFd {
+    pub fn new(mask: SignalFlags, nonblock: bool) -> Self {
+        Self {
+            nonblock,
+            mask: unsafe { UPIntrFreeCell::new(mask) },
+        }
+    }
+
+    pub fn mask(&self) -> SignalFlags {
+        *self.mask.exclusive_access()
+    }
+
+    pub fn set_mask(&self, mask: SignalFlags) {
+        *self.mask.exclusive_access() = mask;
+    }
+
+    /// Take one pending signal in the mask, waiting for it unless nonblocking.
+    fn dequeue(&self) -> Option<u32> {
+        loop {
+            let process = current_process();
+            let mut inner = process.inner_exclusive_access();
+            let pending = inner.signals & self.mask();
+            if let Some(signo) = pending.lowest_signo() {
+                inner
+                    .signals
+                    .remove(SignalFlags::from_bits_truncate(1 << signo));
+                return Some(signo);
+            }
+            if self.nonblock {
+                return None;
+            }
+            drop(inner);
+            drop(process);
+            suspend_current_and_run_next();
+        }
+    }
+}
+
+impl File for SignalFd {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, buf: UserBuffer) -> usize {
+        if buf.len() < SIGNALFD_SIGINFO_SIZE {
+            return 0;
+        }
+        match self.dequeue() {
+            Some(signo) => {
+                let mut record = [0u8; SIGNALFD_SIGINFO_SIZE];
+                record[..4].copy_from_slice(&signo.to_ne_bytes());
+                for (byte, value) in buf.into_iter().zip(record) {
+                    unsafe {
+                        *byte = value;
+                    }
+                }
+                SIGNALFD_SIGINFO_SIZE
+            }
+            None => 0,
+        }
+    }
+    fn write(&self, _buf: UserBuffer) -> usize {
+        0
+    }
+    fn min_read_len(&self) -> usize {
+        SIGNALFD_SIGINFO_SIZE
+    }
+    fn would_block(&self) -> bool {
+        let process = current_process();
+        let inner = process.inner_exclusive_access();
+        self.nonblock && (inner.signals & self.mask()).is_empty()
+    }
+    fn as_signalfd(&self) -> Option<&SignalFd> {
+        Some(self)
+    }
+}
@@ -0,0 +1,128 @@
+use super::{File, Stat, StatMode};
+use crate::mm::UserBuffer;
+use crate::sync::UPIntrFreeCell;
+use crate::task::suspend_current_and_run_next;
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use lazy_static::*;
+
+pub const AF_UNIX: usize = 1;
+pub const SOCK_DGRAM: usize = 2;
+
+/// datagrams queued in a socket before senders have to wait
+const SOCKET_QUEUE_SIZE: usize = 16;
+/// longer datagrams are refused by send
+pub const MAX_DATAGRAM_SIZE: usize = 4096;
+
+type DatagramQueue = UPIntrFreeCell<VecDeque<Vec<u8>>>;
+
+lazy_static! {
+    /// bound name -> receive queue of the socket
+    static ref SOCKET_NAMES: UPIntrFreeCell<BTreeMap<String, Weak<DatagramQueue>>> =
+        unsafe { UPIntrFreeCell::new(BTreeMap::new()) };
+}
+
+/// A local datagram socket. Every datagram is received as a whole by
+/// exactly one read, so message boundaries are preserved.
+pub struct Socket {
+    queue: Arc<DatagramQueue>,
+    name: UPIntrFreeCell<Option<String>>,
+}
+
+impl Socket {
+    pub fn new() -> Self {
+        Self {
+            queue: Arc::new(unsafe { UPIntrFreeCell::new(VecDeque::new()) }),
+            name: unsafe { UPIntrFreeCell::new(None) },
+        }
+    }
+    /// Fail if this socket is already bound or the name is taken.
+    pub fn bind(&self, name: String) -> bool {
+        let mut names = SOCKET_NAMES.exclusive_access();
+        let mut bound = self.name.exclusive_access();
+        if bound.is_some() || names.get(&name).and_then(Weak::upgrade).is_some() {
+            return false;
+        }
+        names.insert(name.clone(), Arc::downgrade(&self.queue));
+        *bound = Some(name);
+        true
+    }
+    /// Queue a datagram on the socket bound to `name`, waiting while its
+    /// queue is full. Fail if nobody is bound to `name`.
+    pub fn send_to(name: &str, datagram: Vec<u8>) -> bool {
+        let queue = match SOCKET_NAMES
+            .exclusive_access()
+            .get(name)
+            .and_then(Weak::upgrade)
+        {
+            Some(queue) => queue,
+            None => return false,
+        };
+        loop {
+            let mut queue = queue.exclusive_access();
+            if queue.len() < SOCKET_QUEUE_SIZE {
+                queue.push_back(datagram);
+                return true;
+            }
+            drop(queue);
+            suspend_current_and_run_next();
+        }
+    }
+    /// Wait for the next datagram. The part which does not fit into
+    /// `buf` is discarded.
+    pub fn recv(&self, buf: UserBuffer) -> usize {
+        let datagram = loop {
+            let mut queue = self.queue.exclusive_access();
+            if let Some(datagram) = queue.pop_front() {
+                break datagram;
+            }
+            drop(queue);
+            suspend_current_and_run_next();
+        };
+        let mut len = 0;
+        for (byte_ref, byte) in buf.into_iter().zip(datagram.iter()) {
+            unsafe {
+                *byte_ref = *byte;
+            }
+            len += 1;
+        }
+        len
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        if let Some(name) = self.name.exclusive_access().take() {
+            let mut names = SOCKET_NAMES.exclusive_access();
+            if let Some(queue) = names.get(&name) {
+                if Weak::ptr_eq(queue, &Arc::downgrade(&self.queue)) {
+                    names.remove(&name);
+                }
+            }
+        }
+    }
+}
+
+impl File for Socket {
+    fn readable(&self) -> bool {
+        true
+    }
+    /// datagrams need a destination, see `sys_sendmsg`
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, buf: UserBuffer) -> usize {
+        self.recv(buf)
+    }
+    fn write(&self, _buf: UserBuffer) -> usize {
+        0
+    }
+    fn stat(&self) -> Stat {
+        Stat::new(StatMode::SOCK)
+    }
+    fn as_socket(&self) -> Option<&Socket> {
+        Some(self)
+    }
+}
@@ -1,7 +1,9 @@
-use super::File;
+use super::{File, Stat, StatMode};
+use crate::console::{print_bytes, read_line};
 use crate::drivers::chardev::CharDevice;
 use crate::drivers::chardev::UART;
 use crate::mm::UserBuffer;
+use alloc::vec;
 
 pub struct Stdin;
 pub struct Stdout;
@@ -13,18 +15,32 @@ impl File for Stdin {
     fn writable(&self) -> bool {
         false
     }
+    /// A single byte is read raw for programs doing their own editing,
+    /// longer reads get a whole line through the line discipline.
     fn read(&self, mut user_buf: UserBuffer) -> usize {
-        assert_eq!(user_buf.len(), 1);
-        //println!("before UART.read() in Stdin::read()");
-        let ch = UART.read();
-        unsafe {
-            user_buf.buffers[0].as_mut_ptr().write_volatile(ch);
+        if user_buf.len() == 1 {
+            //println!("before UART.read() in Stdin::read()");
+            let ch = UART.read();
+            unsafe {
+                user_buf.buffers[0].as_mut_ptr().write_volatile(ch);
+            }
+            return 1;
         }
-        1
+        let mut line = vec![0u8; user_buf.len()];
+        let len = read_line(&mut line);
+        for (byte, ptr) in line[..len].iter().zip(user_buf.into_iter()) {
+            unsafe {
+                *ptr = *byte;
+            }
+        }
+        len
     }
     fn write(&self, _user_buf: UserBuffer) -> usize {
         panic!("Cannot write to stdin!");
     }
+    fn stat(&self) -> Stat {
+        Stat::new(StatMode::CHR)
+    }
 }
 
 impl File for Stdout {
@@ -38,9 +54,14 @@ impl File for Stdout {
         panic!("Cannot read from stdout!");
     }
     fn write(&self, user_buf: UserBuffer) -> usize {
+        // a character may be split between two pages, the bytes go out as
+        // they are
         for buffer in user_buf.buffers.iter() {
-            print!("{}", core::str::from_utf8(*buffer).unwrap());
+            print_bytes(buffer);
         }
         user_buf.len()
     }
+    fn stat(&self) -> Stat {
+        Stat::new(StatMode::CHR)
+    }
 }
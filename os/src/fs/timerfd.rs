@@ -0,0 +1,150 @@
+use super::File;
+use crate::mm::UserBuffer;
+use crate::sync::UPIntrFreeCell;
+use crate::task::{block_current_and_run_next, current_task, suspend_current_and_run_next};
+use crate::timer::{add_timer, get_time_ms};
+use core::mem::size_of;
+
+pub const CLOCK_MONOTONIC: usize = 1;
+pub const TFD_NONBLOCK: usize = 0o4000;
+
+/// Setting of a timerfd in milliseconds. A zero `value_ms` disarms the timer,
+/// a zero `interval_ms` makes it expire only once.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ITimerSpec {
+    pub interval_ms: usize,
+    pub value_ms: usize,
+}
+
+struct TimerFdInner {
+    next_expire_ms: Option<usize>,
+    interval_ms: usize,
+}
+
+impl TimerFdInner {
+    /// Count the expirations up to `now` and move on to the next one.
+    fn take_expirations(&mut self, now: usize) -> u64 {
+        match self.next_expire_ms {
+            Some(expire_ms) if expire_ms <= now => {
+                if self.interval_ms == 0 {
+                    self.next_expire_ms = None;
+                    1
+                } else {
+                    let count = (now - expire_ms) / self.interval_ms + 1;
+                    self.next_expire_ms = Some(expire_ms + count * self.interval_ms);
+                    count as u64
+                }
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// A timer that reads as the number of times it expired since the last read.
+/// Expirations are counted when it is read, readers waiting for one sleep on
+/// the kernel timers until the next expiration.
+pub struct TimerFd {
+    nonblock: bool,
+    inner: UPIntrFreeCell<TimerFdInner>,
+}
+
+impl TimerFd {
+    pub fn new(nonblock: bool) -> Self {
+        Self {
+            nonblock,
+            inner: unsafe {
+                UPIntrFreeCell::new(TimerFdInner {
+                    next_expire_ms: None,
+                    interval_ms: 0,
+                })
+            },
+        }
+    }
+
+    /// Arm or disarm the timer, dropping expirations not read yet.
+    /// Return the previous setting with the time left until its next expiration.
+    pub fn settime(&self, new: ITimerSpec) -> ITimerSpec {
+        let now = get_time_ms();
+        let mut inner = self.inner.exclusive_access();
+        let old = ITimerSpec {
+            interval_ms: inner.interval_ms,
+            value_ms: inner
+                .next_expire_ms
+                .map_or(0, |expire_ms| expire_ms.saturating_sub(now).max(1)),
+        };
+        inner.interval_ms = new.interval_ms;
+        inner.next_expire_ms = if new.value_ms == 0 {
+            None
+        } else {
+            Some(now + new.value_ms)
+        };
+        old
+    }
+
+    /// Wait for at least one expiration and return how many there were.
+    /// Return None instead of waiting if the timerfd is nonblocking.
+    pub fn read_expirations(&self) -> Option<u64> {
+        loop {
+            let mut inner = self.inner.exclusive_access();
+            let count = inner.take_expirations(get_time_ms());
+            if count > 0 {
+                return Some(count);
+            }
+            if self.nonblock {
+                return None;
+            }
+            let next_expire_ms = inner.next_expire_ms;
+            drop(inner);
+            match next_expire_ms {
+                Some(expire_ms) => {
+                    add_timer(expire_ms, current_task().unwrap());
+                    block_current_and_run_next();
+                }
+                // a disarmed timer may be armed by another thread later
+                None => suspend_current_and_run_next(),
+            }
+        }
+    }
+}
+
+impl File for TimerFd {
+    fn readable(&self) -> bool {
+        true
+    }
+    fn writable(&self) -> bool {
+        false
+    }
+    fn read(&self, buf: UserBuffer) -> usize {
+        if buf.len() < size_of::<u64>() {
+            return 0;
+        }
+        match self.read_expirations() {
+            Some(count) => {
+                for (byte, value) in buf.into_iter().zip(count.to_ne_bytes()) {
+                    unsafe {
+                        *byte = value;
+                    }
+                }
+                size_of::<u64>()
+            }
+            None => 0,
+        }
+    }
+    fn write(&self, _buf: UserBuffer) -> usize {
+        0
+    }
+    fn min_read_len(&self) -> usize {
+        size_of::<u64>()
+    }
+    fn would_block(&self) -> bool {
+        let inner = self.inner.exclusive_access();
+        self.nonblock
+            && inner
+                .next_expire_ms
+                .map_or(true, |expire_ms| expire_ms > get_time_ms())
+    }
+    fn as_timerfd(&self) -> Option<&TimerFd> {
+        Some(self)
+    }
+}
@@ -0,0 +1,59 @@
+//! Chain-load another kernel image over the running one.
+
+use crate::board::device_teardown;
+use crate::config::{KEXEC_LOAD_ADDR, KEXEC_MAX_SIZE};
+use alloc::vec::Vec;
+use core::arch::global_asm;
+use riscv::register::{sie, sstatus};
+
+global_asm!(include_str!("kexec.S"));
+
+extern "C" {
+    fn __kexec(image: *const u8, len: usize, load_addr: usize, entry: usize) -> !;
+    fn ekernel();
+}
+
+/// Copy `image` to `KEXEC_LOAD_ADDR` and jump to the physical address `entry`
+/// with paging and interrupts off, and the devices torn down.
+///
+/// The image is staged in the kernel heap beforehand since frames of the load
+/// area may still back the user memory it came from.
+pub fn kexec(image: Vec<u8>, entry: usize) -> ! {
+    assert!(ekernel as usize <= KEXEC_LOAD_ADDR);
+    assert!(image.len() <= KEXEC_MAX_SIZE);
+    assert!((KEXEC_LOAD_ADDR..KEXEC_LOAD_ADDR + image.len()).contains(&entry));
+    unsafe {
+        sstatus::clear_sie();
+        sie::clear_stimer();
+        sie::clear_ssoft();
+    }
+    device_teardown();
+    unsafe { __kexec(image.as_ptr(), image.len(), KEXEC_LOAD_ADDR, entry) }
+}
+
+/// A position independent image equivalent to
+/// `QEMU_EXIT_HANDLE.exit_success()`:
+///     lui t0, 0x100           # VIRT_TEST
+///     lui t1, 0x5
+///     addi t1, t1, 0x555      # EXIT_SUCCESS
+///     sw t1, 0(t0)
+///     j .
+const EXIT_SUCCESS_IMAGE: [u32; 5] = [
+    0x0010_02b7,
+    0x0000_5337,
+    0x5553_0313,
+    0x0062_a023,
+    0x0000_006f,
+];
+
+/// Chain-load an image that exits QEMU successfully, with the devices live as
+/// they are once booted. QEMU keeps running if the handoff goes wrong, see
+/// `make kexec-test`.
+pub fn kexec_test() {
+    println!("[kernel] kexec into an image exiting QEMU");
+    let image = EXIT_SUCCESS_IMAGE
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect();
+    kexec(image, KEXEC_LOAD_ADDR)
+}
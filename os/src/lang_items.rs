@@ -1,8 +1,13 @@
 use crate::sbi::shutdown;
-use crate::task::current_kstack_top;
+use crate::task::kernel_stack_containing;
+use crate::trap::overflow_stack_range;
 use core::arch::asm;
+use core::ops::Range;
 use core::panic::PanicInfo;
 
+/// the walk stops here in case the fp chain is corrupted
+const MAX_BACKTRACE_DEPTH: usize = 64;
+
 #[panic_handler]
 fn panic(info: &PanicInfo) -> ! {
     if let Some(location) = info.location() {
@@ -21,17 +26,56 @@ fn panic(info: &PanicInfo) -> ! {
     shutdown(255)
 }
 
+/// The stack fp points into: the boot stack, a kernel stack or the stack
+/// kernel stack overflows are handled on. The top is included since the
+/// outermost frame points there.
+fn stack_containing(fp: usize) -> Option<Range<usize>> {
+    extern "C" {
+        fn boot_stack_lower_bound();
+        fn boot_stack_top();
+    }
+    let overflow_stack = overflow_stack_range();
+    if (boot_stack_lower_bound as usize..=boot_stack_top as usize).contains(&fp) {
+        Some(boot_stack_lower_bound as usize..boot_stack_top as usize)
+    } else if (overflow_stack.start..=overflow_stack.end).contains(&fp) {
+        Some(overflow_stack)
+    } else {
+        kernel_stack_containing(fp).map(|(bottom, top)| bottom..top)
+    }
+}
+
+/// Follow the saved fp of each frame, the kernel is built with frame
+/// pointers. Every fp must stay in the stack the walk starts on and grow
+/// towards its top, so a corrupted chain ends the walk instead of faulting
+/// or looping.
 unsafe fn backtrace() {
+    extern "C" {
+        fn stext();
+        fn etext();
+    }
     let mut fp: usize;
-    let stop = current_kstack_top();
     asm!("mv {}, s0", out(reg) fp);
     println!("---START BACKTRACE---");
-    for i in 0..10 {
-        if fp == stop {
-            break;
+    if let Some(stack) = stack_containing(fp) {
+        for i in 0..MAX_BACKTRACE_DEPTH {
+            // the outermost frame has been reached
+            if fp == stack.end || fp < stack.start + 16 || fp > stack.end || fp % 8 != 0 {
+                break;
+            }
+            let ra = *((fp - 8) as *const usize);
+            if (stext as usize..etext as usize).contains(&ra) {
+                println!("#{}:ra={:#x} (stext+{:#x})", i, ra, ra - stext as usize);
+            } else {
+                println!("#{}:ra={:#x} (not in .text)", i, ra);
+            }
+            let caller_fp = *((fp - 16) as *const usize);
+            if caller_fp <= fp {
+                break;
+            }
+            fp = caller_fp;
         }
-        println!("#{}:ra={:#x}", i, *((fp - 8) as *const usize));
-        fp = *((fp - 16) as *const usize);
+    } else {
+        println!("fp {:#x} is not on a kernel stack", fp);
     }
     println!("---END   BACKTRACE---");
 }
@@ -2,6 +2,7 @@
 #![no_main]
 #![feature(panic_info_message)]
 #![feature(alloc_error_handler)]
+#![feature(asm_const)]
 
 //use crate::drivers::{GPU_DEVICE, KEYBOARD_DEVICE, MOUSE_DEVICE, INPUT_CONDVAR};
 use crate::drivers::{GPU_DEVICE, KEYBOARD_DEVICE, MOUSE_DEVICE};
@@ -10,14 +11,15 @@ extern crate alloc;
 #[macro_use]
 extern crate bitflags;
 
-#[path = "boards/qemu.rs"]
-mod board;
-
 #[macro_use]
 mod console;
+#[path = "boards/qemu.rs"]
+mod board;
 mod config;
 mod drivers;
+mod fault_inject;
 mod fs;
+mod kexec;
 mod lang_items;
 mod mm;
 mod sbi;
@@ -27,10 +29,38 @@ mod task;
 mod timer;
 mod trap;
 
-use crate::drivers::chardev::CharDevice;
-use crate::drivers::chardev::UART;
+core::arch::global_asm!(
+    include_str!("entry.asm"),
+    boot_stack_size = const config::BOOT_STACK_SIZE,
+    max_harts = const config::MAX_HARTS,
+);
 
-core::arch::global_asm!(include_str!("entry.asm"));
+/// The boot stacks have the configured size, and the one in use is the
+/// first, which hart 0 boots on.
+fn boot_stack_test() {
+    extern "C" {
+        fn boot_stack_lower_bound();
+        fn boot_stack_top();
+    }
+    let (lower, top) = (boot_stack_lower_bound as usize, boot_stack_top as usize);
+    let sp: usize;
+    unsafe {
+        core::arch::asm!("mv {}, sp", out(reg) sp);
+    }
+    println!(
+        "[kernel] boot stacks [{:#x}, {:#x}), {:#x} bytes for each of {} harts",
+        lower,
+        top,
+        config::BOOT_STACK_SIZE,
+        config::MAX_HARTS
+    );
+    assert_eq!(top - lower, config::BOOT_STACK_SIZE * config::MAX_HARTS);
+    // every hart starts at an aligned sp
+    assert_eq!(lower % 16, 0);
+    assert_eq!(config::BOOT_STACK_SIZE % 16, 0);
+    assert!((lower..lower + config::BOOT_STACK_SIZE).contains(&sp));
+    println!("boot_stack_test passed!");
+}
 
 fn clear_bss() {
     extern "C" {
@@ -55,7 +85,12 @@ lazy_static! {
 pub fn rust_main() -> ! {
     clear_bss();
     mm::init();
-    UART.init();
+    console::init();
+    console::log_test();
+    console::uart_burst_test();
+    drivers::chardev::rx_ring_test();
+    drivers::chardev::uart_rx_test();
+    boot_stack_test();
     println!("KERN: init gpu");
     let _gpu = GPU_DEVICE.clone();
     println!("KERN: init keyboard");
@@ -64,10 +99,28 @@ pub fn rust_main() -> ! {
     let _mouse = MOUSE_DEVICE.clone();
     println!("KERN: init trap");
     trap::init();
+    sync::up_cell_test();
+    mm::remap_test();
+    trap::copy_bytes_test();
+    mm::huge_page_test();
+    mm::address_test();
+    mm::frame_poison_test();
+    mm::contiguous_alloc_test();
     trap::enable_timer_interrupt();
-    timer::set_next_trigger();
+    task::idle_test();
     board::device_init();
+    board::irq_test();
     fs::list_apps();
+    mm::describe_test();
+    mm::pie_test();
+    syscall::validate_test();
+    if cfg!(debug_assertions) {
+        fault_inject::fault_inject_test();
+        syscall::copy_from_user_test();
+    }
+    if config::KEXEC_TEST {
+        kexec::kexec_test();
+    }
     task::add_initproc();
     *DEV_NON_BLOCKING_ACCESS.exclusive_access() = true;
     task::run_tasks();
@@ -1,6 +1,7 @@
-use super::PageTableEntry;
+use super::{alloc_contiguous, dealloc_contiguous, PageTableEntry};
 use crate::config::{PAGE_SIZE, PAGE_SIZE_BITS};
 use core::fmt::{self, Debug, Formatter};
+use core::ops::{Add, Sub};
 
 const PA_WIDTH_SV39: usize = 56;
 const VA_WIDTH_SV39: usize = 39;
@@ -96,7 +97,34 @@ impl From<VirtPageNum> for usize {
     }
 }
 
+/// T + usize and T - usize wrap around like usize does, then the result is
+/// truncated by `From<usize>` again, a VirtAddr is sign-extended first
+macro_rules! impl_add_sub {
+    ($($t: ty),*) => {
+        $(
+            impl Add<usize> for $t {
+                type Output = Self;
+                fn add(self, rhs: usize) -> Self {
+                    usize::from(self).wrapping_add(rhs).into()
+                }
+            }
+            impl Sub<usize> for $t {
+                type Output = Self;
+                fn sub(self, rhs: usize) -> Self {
+                    usize::from(self).wrapping_sub(rhs).into()
+                }
+            }
+        )*
+    };
+}
+impl_add_sub!(PhysAddr, VirtAddr, PhysPageNum, VirtPageNum);
+
 impl VirtAddr {
+    /// Sv39 requires bits 63..39 to be copies of bit 38
+    pub fn is_canonical(v: usize) -> bool {
+        let high = v >> (VA_WIDTH_SV39 - 1);
+        high == 0 || high == usize::MAX >> (VA_WIDTH_SV39 - 1)
+    }
     pub fn floor(&self) -> VirtPageNum {
         VirtPageNum(self.0 / PAGE_SIZE)
     }
@@ -157,6 +185,17 @@ impl VirtPageNum {
         }
         idx
     }
+    /// The inverse of indexes, None if some index does not fit in 9 bits.
+    pub fn try_from_indexes(idx: [usize; 3]) -> Option<Self> {
+        idx.iter()
+            .try_fold(0, |vpn, &i| (i < 512).then_some(vpn << 9 | i))
+            .map(Self)
+    }
+    /// Panic if some index does not fit in 9 bits.
+    pub fn from_indexes(idx: [usize; 3]) -> Self {
+        Self::try_from_indexes(idx)
+            .unwrap_or_else(|| panic!("page table indexes {:?} out of range!", idx))
+    }
 }
 
 impl PhysAddr {
@@ -218,6 +257,25 @@ where
     pub fn get_end(&self) -> T {
         self.r
     }
+    pub fn contains(&self, value: T) -> bool {
+        self.l <= value && value < self.r
+    }
+    pub fn is_empty(&self) -> bool {
+        self.l == self.r
+    }
+    /// Whether a value is in both ranges. Ranges only touching at an end do
+    /// not overlap, nor does an empty range with anything.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.is_empty() && !other.is_empty() && self.l < other.r && other.l < self.r
+    }
+}
+impl<T> SimpleRange<T>
+where
+    T: StepByOne + Copy + PartialEq + PartialOrd + Debug + Into<usize>,
+{
+    pub fn len(&self) -> usize {
+        self.r.into() - self.l.into()
+    }
 }
 impl<T> IntoIterator for SimpleRange<T>
 where
@@ -260,3 +318,77 @@ where
     }
 }
 pub type VPNRange = SimpleRange<VirtPageNum>;
+pub type PPNRange = SimpleRange<PhysPageNum>;
+
+impl PPNRange {
+    /// the bytes of all the frames, which must be contiguous in the kernel
+    /// space as well, like those from alloc_contiguous
+    pub fn get_bytes_array(&self) -> &'static mut [u8] {
+        let pa: PhysAddr = self.l.into();
+        unsafe { core::slice::from_raw_parts_mut(pa.0 as *mut u8, self.len() * PAGE_SIZE) }
+    }
+}
+
+/// Arithmetic wraps at the width of each type, and the Sv39 sign extension
+/// of a VirtAddr survives it.
+pub fn address_test() {
+    let top = VirtAddr::from(usize::MAX);
+    assert_eq!(top, VirtAddr((1 << VA_WIDTH_SV39) - 1));
+    assert_eq!(top + 1, VirtAddr(0));
+    assert_eq!(VirtAddr(0) - 1, top);
+    assert_eq!(
+        usize::from(VirtAddr(0) - PAGE_SIZE),
+        PAGE_SIZE.wrapping_neg()
+    );
+    // from the highest address of the lower half to the lowest of the upper
+    let lower_end = VirtAddr((1 << (VA_WIDTH_SV39 - 1)) - 1);
+    assert_eq!(
+        usize::from(lower_end + 1),
+        usize::MAX << (VA_WIDTH_SV39 - 1)
+    );
+    assert_eq!(lower_end + 1 - 1, lower_end);
+    assert_eq!(VirtPageNum((1 << VPN_WIDTH_SV39) - 1) + 1, VirtPageNum(0));
+    assert_eq!(VirtPageNum(0) - 1, VirtPageNum((1 << VPN_WIDTH_SV39) - 1));
+    assert_eq!(PhysAddr((1 << PA_WIDTH_SV39) - 1) + 1, PhysAddr(0));
+    assert_eq!(PhysPageNum(0x80000) + 0x10 - 0x8, PhysPageNum(0x80008));
+    let range = VPNRange::new(VirtPageNum(0x10), VirtPageNum(0x10) + 3);
+    assert_eq!(range.len(), 3);
+    assert!(!range.is_empty());
+    assert_eq!(range.into_iter().count(), range.len());
+    assert!(VPNRange::new(VirtPageNum(0x10), VirtPageNum(0x10)).is_empty());
+    let range = PPNRange::new(PhysPageNum(0x80000), PhysPageNum(0x80004));
+    let ppns: alloc::vec::Vec<PhysPageNum> = range.into_iter().collect();
+    assert_eq!(ppns.len(), 4);
+    assert_eq!(ppns[0], range.get_start());
+    assert_eq!(ppns[3] + 1, range.get_end());
+    let empty = PPNRange::new(PhysPageNum(0x80000), PhysPageNum(0x80000));
+    assert_eq!(empty.into_iter().count(), 0);
+    assert_eq!(empty.get_bytes_array().len(), 0);
+    // zero frames as a whole
+    let pages = 3;
+    let base = alloc_contiguous(pages).unwrap();
+    let frames = PPNRange::new(base, base + pages);
+    for ppn in frames {
+        ppn.get_bytes_array().fill(0xa5);
+    }
+    frames.get_bytes_array().fill(0);
+    assert_eq!(frames.get_bytes_array().len(), pages * PAGE_SIZE);
+    assert!(frames
+        .into_iter()
+        .all(|ppn| ppn.get_bytes_array().iter().all(|&byte| byte == 0)));
+    dealloc_contiguous(base, pages);
+    // every index takes each of its values
+    let mut vpn = VirtPageNum(0);
+    while vpn.0 < 1 << VPN_WIDTH_SV39 {
+        assert_eq!(VirtPageNum::from_indexes(vpn.indexes()), vpn);
+        vpn.0 += 0x40201;
+    }
+    assert_eq!(
+        VirtPageNum::from_indexes([1, 2, 3]),
+        VirtPageNum(1 << 18 | 2 << 9 | 3)
+    );
+    for idx in [[512, 0, 0], [0, 512, 0], [0, 0, usize::MAX]] {
+        assert!(VirtPageNum::try_from_indexes(idx).is_none());
+    }
+    println!("address_test passed!");
+}
@@ -0,0 +1,106 @@
+//! Address space identifiers, which tag the cached translations of each
+//! space so that switching spaces flushes nothing.
+//!
+//! ASID 0 belongs to the kernel space for good. The others are handed out in
+//! turn to the user spaces as they are switched to. When they run out, a new
+//! generation begins: the whole TLB is flushed, which recycles the ASIDs of
+//! the spaces gone since, and every space takes a new ASID the next time it
+//! is switched to.
+
+use super::flush_tlb;
+use crate::sync::UPIntrFreeCell;
+use core::cell::Cell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use lazy_static::*;
+use riscv::register::satp;
+
+/// where the ASID field of satp begins
+pub const ASID_SHIFT: usize = 44;
+const ASID_MASK: usize = 0xffff;
+
+#[derive(Clone, Copy)]
+pub struct Asid {
+    generation: usize,
+    value: usize,
+}
+
+impl Asid {
+    /// kept by the kernel space whatever the generation
+    pub const KERNEL: Self = Self {
+        generation: usize::MAX,
+        value: 0,
+    };
+    /// of a user space not switched to yet
+    pub const NONE: Self = Self {
+        generation: 0,
+        value: 0,
+    };
+}
+
+struct AsidAllocator {
+    /// number of ASIDs the hart supports, the kernel one included
+    count: usize,
+    generation: usize,
+    next: usize,
+}
+
+impl AsidAllocator {
+    fn alloc(&mut self) -> Asid {
+        if self.next == self.count {
+            self.generation += 1;
+            self.next = 1;
+            ASID_ROLLOVERS.fetch_add(1, Ordering::Relaxed);
+            flush_tlb(None);
+        }
+        self.next += 1;
+        Asid {
+            generation: self.generation,
+            value: self.next - 1,
+        }
+    }
+}
+
+lazy_static! {
+    static ref ASID_ALLOCATOR: UPIntrFreeCell<AsidAllocator> = unsafe {
+        UPIntrFreeCell::new(AsidAllocator {
+            count: 0,
+            generation: 1,
+            next: 1,
+        })
+    };
+}
+
+/// generations begun after the first one
+pub static ASID_ROLLOVERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Find out how many ASIDs the hart supports, by writing all ones to the
+/// ASID field of satp and reading back the bits that stuck. Paging must be
+/// on, with the kernel space.
+pub fn init() {
+    let token = satp::read().bits();
+    unsafe {
+        satp::write(token | ASID_MASK << ASID_SHIFT);
+    }
+    let count = (satp::read().bits() >> ASID_SHIFT & ASID_MASK) + 1;
+    unsafe {
+        satp::write(token);
+    }
+    flush_tlb(None);
+    // without ASIDs every switch of spaces would have to flush
+    assert!(count > 1, "ASIDs are not supported");
+    ASID_ALLOCATOR.exclusive_access().count = count;
+}
+
+/// The ASID of the space tagged with `asid` in the current generation,
+/// handing it a new one if it has none.
+pub fn asid_of(asid: &Cell<Asid>) -> usize {
+    let current = asid.get();
+    if current.generation == Asid::KERNEL.generation {
+        return current.value;
+    }
+    let mut allocator = ASID_ALLOCATOR.exclusive_access();
+    if current.generation != allocator.generation {
+        asid.set(allocator.alloc());
+    }
+    asid.get().value
+}
@@ -1,8 +1,11 @@
-use super::{PhysAddr, PhysPageNum};
-use crate::config::MEMORY_END;
+use super::{PPNRange, PhysAddr, PhysPageNum};
+use crate::config::{FRAME_POISON, MEMORY_END, MEMORY_NODES};
+use crate::fault_inject::{should_fail, FaultKind};
 use crate::sync::UPIntrFreeCell;
+use alloc::collections::BTreeSet;
 use alloc::vec::Vec;
 use core::fmt::{self, Debug, Formatter};
+use core::sync::atomic::{AtomicUsize, Ordering};
 use lazy_static::*;
 
 pub struct FrameTracker {
@@ -38,49 +41,162 @@ trait FrameAllocator {
     fn dealloc(&mut self, ppn: PhysPageNum);
 }
 
-pub struct StackFrameAllocator {
-    current: usize,
+/// Blocks of up to 2^MAX_ORDER frames are handed out.
+pub const MAX_ORDER: usize = 10;
+
+/// Frames are handed out in blocks of 2^order frames, each aligned to its
+/// size. A free block is merged with its buddy, the other half of the
+/// block of the next order, as soon as both are free.
+pub struct BuddyFrameAllocator {
+    start: usize,
     end: usize,
-    recycled: Vec<usize>,
+    /// first frames of the free blocks of each order
+    free_lists: [BTreeSet<usize>; MAX_ORDER + 1],
 }
 
-impl StackFrameAllocator {
+impl BuddyFrameAllocator {
     pub fn init(&mut self, l: PhysPageNum, r: PhysPageNum) {
-        self.current = l.0;
+        self.start = l.0;
         self.end = r.0;
-        // println!("last {} Physical Frames.", self.end - self.current);
+        let mut ppn = l.0;
+        while ppn < r.0 {
+            let order = (0..=MAX_ORDER)
+                .rev()
+                .find(|&order| ppn % (1 << order) == 0 && ppn + (1 << order) <= r.0)
+                .unwrap();
+            self.free_lists[order].insert(ppn);
+            ppn += 1 << order;
+        }
+    }
+    /// The smallest order holding `pages` frames.
+    pub fn order_of(pages: usize) -> usize {
+        pages.max(1).next_power_of_two().trailing_zeros() as usize
+    }
+    pub fn alloc_order(&mut self, order: usize) -> Option<PhysPageNum> {
+        if order > MAX_ORDER {
+            return None;
+        }
+        // split the smallest free block that is large enough
+        let found = (order..=MAX_ORDER).find(|&o| !self.free_lists[o].is_empty())?;
+        let ppn = *self.free_lists[found].iter().next().unwrap();
+        self.free_lists[found].remove(&ppn);
+        for o in (order..found).rev() {
+            self.free_lists[o].insert(ppn + (1 << o));
+        }
+        Some(ppn.into())
+    }
+    pub fn dealloc_order(&mut self, ppn: PhysPageNum, order: usize) {
+        let mut ppn = ppn.0;
+        if !self.is_allocated(ppn, order) {
+            panic!(
+                "Frames ppn={:#x} of order {} have not been allocated!",
+                ppn, order
+            );
+        }
+        // merge with the buddies that are free
+        let mut order = order;
+        while order < MAX_ORDER && self.free_lists[order].remove(&(ppn ^ (1 << order))) {
+            ppn &= !(1 << order);
+            order += 1;
+        }
+        self.free_lists[order].insert(ppn);
+    }
+    /// The block may have been handed out, none of it is free.
+    fn is_allocated(&self, ppn: usize, order: usize) -> bool {
+        order <= MAX_ORDER
+            && ppn % (1 << order) == 0
+            && ppn >= self.start
+            && ppn + (1 << order) <= self.end
+            && !self.overlaps_free(ppn, order)
+    }
+    /// Some frame of the block is free already.
+    fn overlaps_free(&self, ppn: usize, order: usize) -> bool {
+        self.free_lists.iter().enumerate().any(|(o, list)| {
+            if o >= order {
+                // a larger free block would contain the whole one
+                list.contains(&(ppn & !((1 << o) - 1)))
+            } else {
+                list.range(ppn..ppn + (1 << order)).next().is_some()
+            }
+        })
+    }
+    /// Free blocks are aligned, in range and do not overlap, and no two
+    /// buddies are free without being merged.
+    pub fn check(&self) -> bool {
+        let mut blocks: Vec<(usize, usize)> = Vec::new();
+        for (order, list) in self.free_lists.iter().enumerate() {
+            for &ppn in list {
+                if ppn % (1 << order) != 0 || ppn < self.start || ppn + (1 << order) > self.end {
+                    return false;
+                }
+                if order < MAX_ORDER && list.contains(&(ppn ^ (1 << order))) {
+                    return false;
+                }
+                blocks.push((ppn, ppn + (1 << order)));
+            }
+        }
+        blocks.sort_unstable();
+        blocks.windows(2).all(|pair| pair[0].1 <= pair[1].0)
+    }
+    /// Number of free frames.
+    pub fn free_frames(&self) -> usize {
+        self.free_lists
+            .iter()
+            .enumerate()
+            .map(|(order, list)| list.len() << order)
+            .sum()
     }
 }
-impl FrameAllocator for StackFrameAllocator {
+impl FrameAllocator for BuddyFrameAllocator {
     fn new() -> Self {
         Self {
-            current: 0,
+            start: 0,
             end: 0,
-            recycled: Vec::new(),
+            free_lists: Default::default(),
         }
     }
     fn alloc(&mut self) -> Option<PhysPageNum> {
-        if let Some(ppn) = self.recycled.pop() {
-            Some(ppn.into())
-        } else if self.current == self.end {
-            None
-        } else {
-            self.current += 1;
-            Some((self.current - 1).into())
-        }
+        self.alloc_order(0)
     }
     fn dealloc(&mut self, ppn: PhysPageNum) {
-        let ppn = ppn.0;
-        // validity check
-        if ppn >= self.current || self.recycled.iter().any(|&v| v == ppn) {
-            panic!("Frame ppn={:#x} has not been allocated!", ppn);
-        }
-        // recycle
-        self.recycled.push(ppn);
+        self.dealloc_order(ppn, 0);
     }
 }
 
-type FrameAllocatorImpl = StackFrameAllocator;
+type FrameAllocatorImpl = BuddyFrameAllocator;
+
+/// frames handed out and not freed yet
+static FRAMES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+pub fn frames_allocated() -> usize {
+    FRAMES_ALLOCATED.load(Ordering::Relaxed)
+}
+
+/// Free frames are filled with it if FRAME_POISON is on, so that stale data
+/// never looks valid. A frame handed out must still be full of it, or it
+/// was written after being freed.
+const POISON: u8 = 0xaa;
+
+fn block_of(ppn: PhysPageNum, order: usize) -> PPNRange {
+    PPNRange::new(ppn, ppn + (1 << order))
+}
+
+fn poison(frames: PPNRange) {
+    if FRAME_POISON {
+        frames.get_bytes_array().fill(POISON);
+    }
+}
+
+fn check_poison(frames: PPNRange) {
+    if FRAME_POISON {
+        assert!(
+            frames.get_bytes_array().iter().all(|&byte| byte == POISON),
+            "frames {:?}..{:?} were written after being freed!",
+            frames.get_start(),
+            frames.get_end()
+        );
+    }
+}
 
 lazy_static! {
     pub static ref FRAME_ALLOCATOR: UPIntrFreeCell<FrameAllocatorImpl> =
@@ -91,21 +207,70 @@ pub fn init_frame_allocator() {
     extern "C" {
         fn ekernel();
     }
-    FRAME_ALLOCATOR.exclusive_access().init(
+    let frames = PPNRange::new(
         PhysAddr::from(ekernel as usize).ceil(),
         PhysAddr::from(MEMORY_END).floor(),
     );
+    poison(frames);
+    FRAME_ALLOCATOR
+        .exclusive_access()
+        .init(frames.get_start(), frames.get_end());
 }
 
 pub fn frame_alloc() -> Option<FrameTracker> {
-    FRAME_ALLOCATOR
-        .exclusive_access()
-        .alloc()
-        .map(FrameTracker::new)
+    frame_alloc_hinted(0)
+}
+
+/// Allocate a frame, preferably from memory node `node`.
+/// There is only one node for now, so the hint does not change anything.
+pub fn frame_alloc_hinted(node: usize) -> Option<FrameTracker> {
+    debug_assert!(node < MEMORY_NODES, "no memory node {}", node);
+    if should_fail(FaultKind::FrameAlloc) {
+        return None;
+    }
+    let ppn = FRAME_ALLOCATOR.exclusive_access().alloc()?;
+    check_poison(block_of(ppn, 0));
+    FRAMES_ALLOCATED.fetch_add(1, Ordering::Relaxed);
+    Some(FrameTracker::new(ppn))
 }
 
 pub fn frame_dealloc(ppn: PhysPageNum) {
-    FRAME_ALLOCATOR.exclusive_access().dealloc(ppn);
+    let mut allocator = FRAME_ALLOCATOR.exclusive_access();
+    // dealloc panics on frames never handed out, leave them untouched
+    if allocator.is_allocated(ppn.0, 0) {
+        poison(block_of(ppn, 0));
+    }
+    allocator.dealloc(ppn);
+    FRAMES_ALLOCATED.fetch_sub(1, Ordering::Relaxed);
+}
+
+/// Allocate `pages` physically contiguous frames, rounded up to a power of
+/// two and aligned to that size, return the first one. Fail if that is
+/// more than 2^MAX_ORDER frames. The frames are not cleared.
+pub fn alloc_contiguous(pages: usize) -> Option<PhysPageNum> {
+    if should_fail(FaultKind::FrameAlloc) {
+        return None;
+    }
+    let order = BuddyFrameAllocator::order_of(pages);
+    let ppn = FRAME_ALLOCATOR.exclusive_access().alloc_order(order)?;
+    check_poison(block_of(ppn, order));
+    FRAMES_ALLOCATED.fetch_add(1 << order, Ordering::Relaxed);
+    Some(ppn)
+}
+
+/// Free frames from `alloc_contiguous`, `pages` is the count asked for.
+pub fn dealloc_contiguous(ppn: PhysPageNum, pages: usize) {
+    let order = BuddyFrameAllocator::order_of(pages);
+    let mut allocator = FRAME_ALLOCATOR.exclusive_access();
+    if allocator.is_allocated(ppn.0, order) {
+        poison(block_of(ppn, order));
+    }
+    allocator.dealloc_order(ppn, order);
+    FRAMES_ALLOCATED.fetch_sub(1 << order, Ordering::Relaxed);
+}
+
+pub fn frame_allocator_check() -> bool {
+    FRAME_ALLOCATOR.exclusive_access().check()
 }
 
 #[allow(unused)]
@@ -125,3 +290,42 @@ pub fn frame_allocator_test() {
     drop(v);
     println!("frame_allocator_test passed!");
 }
+
+pub fn contiguous_alloc_test() {
+    let free = FRAME_ALLOCATOR.exclusive_access().free_frames();
+    let mut blocks: Vec<(PhysPageNum, usize)> = Vec::new();
+    for pages in [1, 2, 3, 8, 1, 64, 5, 1 << MAX_ORDER] {
+        let ppn = alloc_contiguous(pages).unwrap();
+        let order = BuddyFrameAllocator::order_of(pages);
+        assert_eq!(ppn.0 % (1 << order), 0);
+        assert!(blocks
+            .iter()
+            .all(|&(other, other_pages)| ppn.0 + (1 << order) <= other.0
+                || other.0 + other_pages.next_power_of_two() <= ppn.0));
+        blocks.push((ppn, pages));
+    }
+    assert!(alloc_contiguous((1 << MAX_ORDER) + 1).is_none());
+    assert!(FRAME_ALLOCATOR.exclusive_access().check());
+    // free in another order than allocated, the buddies must merge again
+    for (ppn, pages) in blocks.into_iter().rev() {
+        dealloc_contiguous(ppn, pages);
+        assert!(FRAME_ALLOCATOR.exclusive_access().check());
+    }
+    assert_eq!(FRAME_ALLOCATOR.exclusive_access().free_frames(), free);
+    println!("contiguous_alloc_test passed!");
+}
+
+/// A freed frame is full of poison until it is handed out again, zeroed.
+pub fn frame_poison_test() {
+    let frame = frame_alloc().unwrap();
+    let ppn = frame.ppn;
+    ppn.get_bytes_array().fill(0x5a);
+    drop(frame);
+    if FRAME_POISON {
+        assert!(ppn.get_bytes_array().iter().all(|&byte| byte == POISON));
+    }
+    let frame = frame_alloc().unwrap();
+    assert!(frame.ppn.get_bytes_array().iter().all(|&byte| byte == 0));
+    drop(frame);
+    println!("frame_poison_test passed!");
+}
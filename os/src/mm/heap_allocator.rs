@@ -1,8 +1,63 @@
+use super::frames_allocated;
 use crate::config::KERNEL_HEAP_SIZE;
 use buddy_system_allocator::LockedHeap;
+use core::alloc::{GlobalAlloc, Layout};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Usage of the kernel heap and of the frames, as reported by sys_meminfo.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapStats {
+    /// bytes allocated now
+    pub allocated: usize,
+    /// most bytes ever allocated at once
+    pub peak: usize,
+    /// successful allocations so far
+    pub allocs: usize,
+    /// allocations that found no room
+    pub failed: usize,
+    /// frames handed out now, to the kernel space as well
+    pub frames: usize,
+}
+
+/// The heap, counting what goes through it. The counters are atomic since
+/// allocations can happen in interrupt handlers.
+struct CountingHeap {
+    heap: LockedHeap,
+    allocated: AtomicUsize,
+    peak: AtomicUsize,
+    allocs: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+unsafe impl GlobalAlloc for CountingHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.heap.alloc(layout);
+        if ptr.is_null() {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        } else {
+            let allocated = self.allocated.fetch_add(layout.size(), Ordering::Relaxed);
+            self.peak
+                .fetch_max(allocated + layout.size(), Ordering::Relaxed);
+            self.allocs.fetch_add(1, Ordering::Relaxed);
+        }
+        ptr
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.heap.dealloc(ptr, layout);
+        // every byte freed was counted when allocated, zero-sized ones too
+        self.allocated.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
 
 #[global_allocator]
-static HEAP_ALLOCATOR: LockedHeap = LockedHeap::empty();
+static HEAP_ALLOCATOR: CountingHeap = CountingHeap {
+    heap: LockedHeap::empty(),
+    allocated: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+    allocs: AtomicUsize::new(0),
+    failed: AtomicUsize::new(0),
+};
 
 #[alloc_error_handler]
 pub fn handle_alloc_error(layout: core::alloc::Layout) -> ! {
@@ -14,11 +69,22 @@ static mut HEAP_SPACE: [u8; KERNEL_HEAP_SIZE] = [0; KERNEL_HEAP_SIZE];
 pub fn init_heap() {
     unsafe {
         HEAP_ALLOCATOR
+            .heap
             .lock()
             .init(HEAP_SPACE.as_ptr() as usize, KERNEL_HEAP_SIZE);
     }
 }
 
+pub fn heap_stats() -> HeapStats {
+    HeapStats {
+        allocated: HEAP_ALLOCATOR.allocated.load(Ordering::Relaxed),
+        peak: HEAP_ALLOCATOR.peak.load(Ordering::Relaxed),
+        allocs: HEAP_ALLOCATOR.allocs.load(Ordering::Relaxed),
+        failed: HEAP_ALLOCATOR.failed.load(Ordering::Relaxed),
+        frames: frames_allocated(),
+    }
+}
+
 #[allow(unused)]
 pub fn heap_test() {
     use alloc::boxed::Box;
@@ -1,15 +1,24 @@
+use super::asid::{asid_of, Asid, ASID_SHIFT};
+use super::shm::shm_release;
+use super::{flush_tlb, StepByOne, VPNRange};
 use super::{frame_alloc, FrameTracker};
+use super::{translated_byte_buffer, PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
 use super::{PTEFlags, PageTable, PageTableEntry};
-use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
-use super::{StepByOne, VPNRange};
-use crate::config::{MEMORY_END, MMIO, PAGE_SIZE, TRAMPOLINE};
+use crate::config::{
+    HUGE_PAGE_SIZE, MEMORY_END, MMIO, PAGE_SIZE, PIE_BASE, TRAMPOLINE, USER_HEAP_SIZE,
+};
+use crate::fault_inject::{should_fail, FaultKind};
+use crate::fs::{open_file, OpenFlags};
 use crate::sync::UPIntrFreeCell;
-use alloc::collections::BTreeMap;
+use crate::trap::copy_bytes;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::sync::Arc;
+use alloc::vec;
 use alloc::vec::Vec;
-use core::arch::asm;
+use core::cell::Cell;
 use lazy_static::*;
 use riscv::register::satp;
+use xmas_elf::ElfFile;
 
 extern "C" {
     fn stext();
@@ -36,6 +45,8 @@ pub fn kernel_token() -> usize {
 pub struct MemorySet {
     page_table: PageTable,
     areas: Vec<MapArea>,
+    /// tags the cached translations of this space, see `asid.rs`
+    asid: Cell<Asid>,
 }
 
 impl MemorySet {
@@ -43,10 +54,13 @@ impl MemorySet {
         Self {
             page_table: PageTable::new(),
             areas: Vec::new(),
+            asid: Cell::new(Asid::NONE),
         }
     }
+    /// The satp value switching to this space, whose ASID may change from
+    /// one call to another.
     pub fn token(&self) -> usize {
-        self.page_table.token()
+        self.page_table.token() | asid_of(&self.asid) << ASID_SHIFT
     }
     /// Assume that no conflicts.
     pub fn insert_framed_area(
@@ -60,6 +74,91 @@ impl MemorySet {
             None,
         );
     }
+    /// Like `insert_framed_area`, but the pages get their frames on the first
+    /// access, see `handle_lazy_fault`.
+    pub fn insert_lazy_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) {
+        let mut area = MapArea::new(start_va, end_va, MapType::Framed, permission);
+        area.lazy = true;
+        self.push(area, None);
+    }
+    /// Like `insert_lazy_area`, but the pages stay shared with the spaces
+    /// forked from this one, see `MapArea::shared`. They must all have their
+    /// frames by then, or the spaces would fault in frames of their own.
+    pub fn insert_shared_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) {
+        let mut area = MapArea::new(start_va, end_va, MapType::Framed, permission);
+        area.lazy = true;
+        area.shared = true;
+        self.push(area, None);
+    }
+    /// Map the frames of shared memory segment `id` from `start` on. They
+    /// stay shared with the other spaces mapping them, by fork as well.
+    pub fn insert_shm_area(
+        &mut self,
+        start: VirtPageNum,
+        frames: Vec<Arc<FrameTracker>>,
+        permission: MapPermission,
+        id: usize,
+    ) {
+        let end = start + frames.len();
+        let mut area = MapArea::new(start.into(), end.into(), MapType::Framed, permission);
+        area.shm = Some(id);
+        let flags = PTEFlags::from_bits(permission.bits as u16).unwrap();
+        for (vpn, frame) in VPNRange::new(start, end).into_iter().zip(frames) {
+            self.page_table.map(vpn, frame.ppn, flags);
+            area.data_frames.insert(vpn, frame);
+        }
+        self.areas.push(area);
+    }
+    /// Unmap every attachment of shared memory segment `id`, return false
+    /// if there is none.
+    pub fn remove_shm_areas(&mut self, id: usize) -> bool {
+        let page_table = &mut self.page_table;
+        let mut found = false;
+        self.areas.retain_mut(|area| {
+            let attached = area.shm == Some(id);
+            if attached {
+                area.unmap(page_table);
+                found = true;
+            }
+            !attached
+        });
+        flush_tlb(None);
+        found
+    }
+    /// Bytes of user space covered by the areas, mapped yet or not.
+    pub fn mapped_size(&self) -> usize {
+        self.areas
+            .iter()
+            .map(|area| (area.vpn_range.get_end().0 - area.vpn_range.get_start().0) * PAGE_SIZE)
+            .sum()
+    }
+    /// The highest `pages` pages below `end` in no area, if there are any.
+    pub fn find_free_range(&self, pages: usize, end: VirtPageNum) -> Option<VirtPageNum> {
+        let mut ranges: Vec<(VirtPageNum, VirtPageNum)> = self
+            .areas
+            .iter()
+            .map(|area| (area.vpn_range.get_start(), area.vpn_range.get_end()))
+            .collect();
+        ranges.sort_by(|a, b| b.cmp(a));
+        let mut end = end;
+        for (area_start, area_end) in ranges {
+            if area_end <= end && end.0 - area_end.0 >= pages {
+                break;
+            }
+            end = end.min(area_start);
+        }
+        (end.0 >= pages).then(|| end - pages)
+    }
     pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
         if let Some((idx, area)) = self
             .areas
@@ -69,15 +168,135 @@ impl MemorySet {
         {
             area.unmap(&mut self.page_table);
             self.areas.remove(idx);
+            flush_tlb(None);
         }
     }
+    /// Unmap the user area covering exactly `[start, end)` and free its
+    /// frames, return false if there is no such area.
+    pub fn remove_user_area(&mut self, start: VirtPageNum, end: VirtPageNum) -> bool {
+        match self.areas.iter().position(|area| {
+            area.map_perm.contains(MapPermission::U)
+                && area.vpn_range.get_start() == start
+                && area.vpn_range.get_end() == end
+        }) {
+            Some(idx) => {
+                self.areas[idx].unmap(&mut self.page_table);
+                self.areas.remove(idx);
+                flush_tlb(None);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Unmap every area inside `[start, end)`, which may have been split up
+    /// by `protect`.
+    pub fn remove_areas_within(&mut self, start: VirtPageNum, end: VirtPageNum) {
+        let page_table = &mut self.page_table;
+        self.areas.retain_mut(|area| {
+            let inside = start <= area.vpn_range.get_start() && area.vpn_range.get_end() <= end;
+            if inside {
+                area.unmap(page_table);
+            }
+            !inside
+        });
+        flush_tlb(None);
+    }
+    /// Change the permission of the user pages in `[start, end)`, splitting
+    /// the areas the range begins or ends in. Return false and change
+    /// nothing if some page of the range is not in a user area.
+    pub fn protect(&mut self, start: VirtPageNum, end: VirtPageNum, perm: MapPermission) -> bool {
+        let mut vpn = start;
+        while vpn < end {
+            match self.areas.iter().find(|area| {
+                area.map_perm.contains(MapPermission::U) && area.vpn_range.contains(vpn)
+            }) {
+                Some(area) => vpn = area.vpn_range.get_end(),
+                None => return false,
+            }
+        }
+        self.split_area_at(start);
+        self.split_area_at(end);
+        for area in self.areas.iter_mut() {
+            if start <= area.vpn_range.get_start() && area.vpn_range.get_end() <= end {
+                area.protect(&mut self.page_table, perm);
+            }
+        }
+        flush_tlb(None);
+        true
+    }
+    /// Split the area containing `at` into two if it does not begin there.
+    fn split_area_at(&mut self, at: VirtPageNum) {
+        if let Some(area) = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range.contains(at) && area.vpn_range.get_start() != at)
+        {
+            let tail = area.split_off(at);
+            self.areas.push(tail);
+        }
+    }
+    /// Whether a page of `range` is in an area.
+    fn overlaps_area(&self, range: VPNRange) -> bool {
+        self.areas
+            .iter()
+            .any(|area| area.vpn_range.overlaps(&range))
+    }
+    /// No page of `[start, end)` is mapped, or belongs to a lazy area.
+    pub fn is_unused(&self, start: VirtPageNum, end: VirtPageNum) -> bool {
+        !self.overlaps_area(VPNRange::new(start, end))
+            && VPNRange::new(start, end).into_iter().all(|vpn| {
+                self.page_table
+                    .translate(vpn)
+                    .map_or(true, |pte| !pte.is_valid())
+            })
+    }
+    /// Move the end of the area starting at `start` down to `new_end`,
+    /// return false if there is no such area.
+    pub fn shrink_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        match self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range.get_start() == start.floor())
+        {
+            Some(area) => {
+                area.shrink_to(&mut self.page_table, new_end.ceil());
+                flush_tlb(None);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Move the end of the area starting at `start` up to `new_end`,
+    /// return false if there is no such area or it would grow into another.
+    pub fn append_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        let idx = match self
+            .areas
+            .iter()
+            .position(|area| area.vpn_range.get_start() == start.floor())
+        {
+            Some(idx) => idx,
+            None => return false,
+        };
+        let old_end = self.areas[idx].vpn_range.get_end();
+        if old_end < new_end.ceil() && self.overlaps_area(VPNRange::new(old_end, new_end.ceil())) {
+            return false;
+        }
+        self.areas[idx].append_to(&mut self.page_table, new_end.ceil());
+        true
+    }
     /// Add a new MapArea into this MemorySet.
-    /// Assuming that there are no conflicts in the virtual address
-    /// space.
+    /// The callers make sure that it overlaps no other area, an overlap would
+    /// map pages twice and is a bug.
     pub fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
+        assert!(
+            !self.overlaps_area(map_area.vpn_range),
+            "area {:?}..{:?} overlaps another!",
+            map_area.vpn_range.get_start(),
+            map_area.vpn_range.get_end()
+        );
         map_area.map(&mut self.page_table);
         if let Some(data) = data {
-            map_area.copy_data(&mut self.page_table, data);
+            map_area.copy_data(&mut self.page_table, 0, data);
         }
         self.areas.push(map_area);
     }
@@ -92,6 +311,7 @@ impl MemorySet {
     /// Without kernel stacks.
     pub fn new_kernel() -> Self {
         let mut memory_set = Self::new_bare();
+        memory_set.asid.set(Asid::KERNEL);
         // map trampoline
         memory_set.map_trampoline();
         // map kernel sections
@@ -143,15 +363,14 @@ impl MemorySet {
             None,
         );
         // println!("mapping physical memory");
-        memory_set.push(
-            MapArea::new(
-                (ekernel as usize).into(),
-                MEMORY_END.into(),
-                MapType::Identical,
-                MapPermission::R | MapPermission::W,
-            ),
-            None,
+        let mut memory = MapArea::new(
+            (ekernel as usize).into(),
+            MEMORY_END.into(),
+            MapType::Identical,
+            MapPermission::R | MapPermission::W,
         );
+        memory.huge = true;
+        memory_set.push(memory, None);
         //println!("mapping memory-mapped registers");
         for pair in MMIO {
             memory_set.push(
@@ -166,9 +385,12 @@ impl MemorySet {
         }
         memory_set
     }
-    /// Include sections in elf and trampoline,
-    /// also returns user_sp_base and entry point.
-    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
+    /// Include sections in elf, trampoline and an empty heap,
+    /// also returns heap_bottom, user_sp_base and entry point.
+    /// The bss of a segment, from its file size to its memory size, is left
+    /// to the frames of the area, which are zeroed when allocated. An ET_DYN
+    /// image is loaded at PIE_BASE and its relative relocations applied.
+    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize, usize) {
         let mut memory_set = Self::new_bare();
         // map trampoline
         memory_set.map_trampoline();
@@ -177,13 +399,19 @@ impl MemorySet {
         let elf_header = elf.header;
         let magic = elf_header.pt1.magic;
         assert_eq!(magic, [0x7f, 0x45, 0x4c, 0x46], "invalid elf!");
+        let relocations = relative_relocations(&elf).expect("unsupported relocations!");
+        let base = if elf_header.pt2.type_().as_type() == xmas_elf::header::Type::SharedObject {
+            PIE_BASE
+        } else {
+            0
+        };
         let ph_count = elf_header.pt2.ph_count();
         let mut max_end_vpn = VirtPageNum(0);
         for i in 0..ph_count {
             let ph = elf.program_header(i).unwrap();
             if ph.get_type().unwrap() == xmas_elf::program::Type::Load {
-                let start_va: VirtAddr = (ph.virtual_addr() as usize).into();
-                let end_va: VirtAddr = ((ph.virtual_addr() + ph.mem_size()) as usize).into();
+                let start_va: VirtAddr = (base + ph.virtual_addr() as usize).into();
+                let end_va: VirtAddr = (base + (ph.virtual_addr() + ph.mem_size()) as usize).into();
                 let mut map_perm = MapPermission::U;
                 let ph_flags = ph.flags();
                 if ph_flags.is_read() {
@@ -197,50 +425,317 @@ impl MemorySet {
                 }
                 let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
                 max_end_vpn = map_area.vpn_range.get_end();
-                memory_set.push(
-                    map_area,
-                    Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
+                memory_set.push(map_area, None);
+                // the segment need not start at a page boundary
+                memory_set.areas.last_mut().unwrap().copy_data(
+                    &mut memory_set.page_table,
+                    start_va.page_offset(),
+                    &elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize],
                 );
             }
         }
+        let token = memory_set.token();
+        for (offset, addend) in relocations {
+            let value = base.wrapping_add(addend).to_ne_bytes();
+            let buffers = translated_byte_buffer(token, (base + offset) as *const u8, value.len());
+            let mut start = 0;
+            for buffer in buffers {
+                buffer.copy_from_slice(&value[start..start + buffer.len()]);
+                start += buffer.len();
+            }
+        }
+        // the heap grows from the end of the app, see `append_to`
         let max_end_va: VirtAddr = max_end_vpn.into();
-        let mut user_stack_base: usize = max_end_va.into();
-        user_stack_base += PAGE_SIZE;
+        let heap_bottom: usize = max_end_va.into();
+        memory_set.insert_lazy_area(
+            heap_bottom.into(),
+            heap_bottom.into(),
+            MapPermission::R | MapPermission::W | MapPermission::U,
+        );
+        let user_stack_base = heap_bottom + USER_HEAP_SIZE + PAGE_SIZE;
         (
             memory_set,
+            heap_bottom,
             user_stack_base,
-            elf.header.pt2.entry_point() as usize,
+            base + elf.header.pt2.entry_point() as usize,
         )
     }
+    /// Whether `from_elf` would accept this image. Used by exec to fail
+    /// before the old address space is thrown away. Only executables and
+    /// position-independent ones without other relocations than relative
+    /// ones can be loaded, there is no dynamic linker.
+    pub fn elf_is_valid(elf_data: &[u8]) -> bool {
+        if should_fail(FaultKind::ElfParse) {
+            return false;
+        }
+        match xmas_elf::ElfFile::new(elf_data) {
+            Ok(elf) => {
+                elf.header.pt1.magic == [0x7f, 0x45, 0x4c, 0x46]
+                    && matches!(
+                        elf.header.pt2.type_().as_type(),
+                        xmas_elf::header::Type::Executable | xmas_elf::header::Type::SharedObject
+                    )
+                    && relative_relocations(&elf).is_some()
+            }
+            Err(_) => false,
+        }
+    }
     pub fn from_existed_user(user_space: &MemorySet) -> MemorySet {
         let mut memory_set = Self::new_bare();
         // map trampoline
         memory_set.map_trampoline();
         // copy data sections/trap_context/user_stack
         for area in user_space.areas.iter() {
-            let new_area = MapArea::from_another(area);
-            memory_set.push(new_area, None);
-            // copy data from another space
-            for vpn in area.vpn_range {
-                let src_ppn = user_space.translate(vpn).unwrap().ppn();
-                let dst_ppn = memory_set.translate(vpn).unwrap().ppn();
-                dst_ppn
-                    .get_bytes_array()
-                    .copy_from_slice(src_ppn.get_bytes_array());
+            if area.is_shared() {
+                memory_set.push_shared_of(area);
+            } else {
+                memory_set.push_copy_of(area, &user_space.page_table);
+            }
+        }
+        memory_set
+    }
+    /// Map the frames of a shared memory area to the same pages.
+    fn push_shared_of(&mut self, area: &MapArea) {
+        let mut new_area = MapArea::from_another(area);
+        let flags = PTEFlags::from_bits(area.map_perm.bits as u16).unwrap();
+        for (&vpn, frame) in area.data_frames.iter() {
+            self.page_table.map(vpn, frame.ppn, flags);
+            new_area.data_frames.insert(vpn, Arc::clone(frame));
+        }
+        self.areas.push(new_area);
+    }
+    /// Map a copy of `area`, whose pages are mapped by `page_table`.
+    fn push_copy_of(&mut self, area: &MapArea, page_table: &PageTable) {
+        self.push(MapArea::from_another(area), None);
+        // copy data from another space
+        for vpn in area.vpn_range {
+            let src_ppn = match page_table.translate(vpn) {
+                Some(pte) if pte.is_valid() => pte.ppn(),
+                // not touched yet in a lazy area
+                _ => continue,
+            };
+            if area.lazy {
+                self.areas
+                    .last_mut()
+                    .unwrap()
+                    .map_one(&mut self.page_table, vpn);
+            }
+            let dst_ppn = self.translate(vpn).unwrap().ppn();
+            dst_ppn
+                .get_bytes_array()
+                .copy_from_slice(src_ppn.get_bytes_array());
+        }
+    }
+    /// Share the user pages with the new space instead of copying them.
+    /// Writable pages become read-only in both spaces and are copied on the
+    /// first write, see `handle_cow_fault`. Trap contexts are not user pages,
+    /// they are written by the kernel directly and always copied. Shared
+    /// memory and shared mappings stay writable in both, and never fault.
+    pub fn from_existed_user_cow(user_space: &mut MemorySet) -> MemorySet {
+        let mut memory_set = Self::new_bare();
+        // map trampoline
+        memory_set.map_trampoline();
+        for area in user_space.areas.iter() {
+            if area.is_shared() {
+                memory_set.push_shared_of(area);
+                continue;
+            }
+            if area.map_type != MapType::Framed || !area.map_perm.contains(MapPermission::U) {
+                memory_set.push_copy_of(area, &user_space.page_table);
+                continue;
+            }
+            let mut new_area = MapArea::from_another(area);
+            let mut flags = PTEFlags::from_bits(area.map_perm.bits as u16).unwrap();
+            if flags.contains(PTEFlags::W) {
+                flags = (flags - PTEFlags::W) | PTEFlags::COW;
             }
+            for (&vpn, frame) in area.data_frames.iter() {
+                user_space.page_table.remap(vpn, frame.ppn, flags);
+                memory_set.page_table.map(vpn, frame.ppn, flags);
+                new_area.data_frames.insert(vpn, Arc::clone(frame));
+            }
+            memory_set.areas.push(new_area);
         }
+        // the writable pages of this space are read-only now
+        flush_tlb(None);
         memory_set
     }
+    /// Give this space its own copy of a copy-on-write page and make it
+    /// writable, the last space sharing the frame keeps it.
+    /// Return false if the page is not copy-on-write or no frame is left.
+    pub fn handle_cow_fault(&mut self, vpn: VirtPageNum) -> bool {
+        let pte = match self.page_table.translate(vpn) {
+            Some(pte) if pte.is_valid() && pte.is_cow() => pte,
+            _ => return false,
+        };
+        let area = self
+            .areas
+            .iter_mut()
+            .find(|area| area.vpn_range.contains(vpn))
+            .unwrap();
+        let frame = area.data_frames.get_mut(&vpn).unwrap();
+        if Arc::strong_count(frame) > 1 {
+            let copy = match frame_alloc() {
+                Some(copy) => copy,
+                None => return false,
+            };
+            copy.ppn
+                .get_bytes_array()
+                .copy_from_slice(frame.ppn.get_bytes_array());
+            *frame = Arc::new(copy);
+        }
+        let flags = (pte.flags() - PTEFlags::COW) | PTEFlags::W;
+        self.page_table.remap(vpn, frame.ppn, flags);
+        flush_tlb(Some(vpn));
+        true
+    }
+    /// Allocate the frame of a page in a lazy area on its first access.
+    /// Return false if the page is not in a lazy area, is mapped already or
+    /// no frame is left.
+    pub fn handle_lazy_fault(&mut self, vpn: VirtPageNum) -> bool {
+        let area = match self
+            .areas
+            .iter_mut()
+            .find(|area| area.lazy && area.vpn_range.contains(vpn))
+        {
+            Some(area) if !area.data_frames.contains_key(&vpn) => area,
+            _ => return false,
+        };
+        let frame = match frame_alloc() {
+            Some(frame) => frame,
+            None => return false,
+        };
+        let flags = PTEFlags::from_bits(area.map_perm.bits as u16).unwrap();
+        self.page_table.map(vpn, frame.ppn, flags);
+        area.data_frames.insert(vpn, Arc::new(frame));
+        // the faulting access may have cached the invalid entry
+        flush_tlb(Some(vpn));
+        true
+    }
+    /// Allocate the frames of the lazy pages in `[start_va, end_va)` now,
+    /// for the kernel to write to them. Return false, leaving the pages
+    /// allocated so far mapped, once no frame is left.
+    pub fn populate(&mut self, start_va: VirtAddr, end_va: VirtAddr) -> bool {
+        VPNRange::new(start_va.floor(), end_va.ceil())
+            .into_iter()
+            .all(|vpn| {
+                self.handle_lazy_fault(vpn)
+                    || self.translate(vpn).map_or(false, |pte| pte.is_valid())
+            })
+    }
+    /// Give every page of the user area covering exactly `[start, end)` its
+    /// frame now. Return false, with the whole area removed, once no frame
+    /// is left.
+    pub fn populate_area(&mut self, start: VirtPageNum, end: VirtPageNum) -> bool {
+        if self.populate(start.into(), end.into()) {
+            return true;
+        }
+        self.remove_user_area(start, end);
+        false
+    }
+    /// Whether the page has its frame, `None` if it is in no user area.
+    pub fn is_resident(&self, vpn: VirtPageNum) -> Option<bool> {
+        self.areas.iter().find(|area| {
+            area.map_perm.contains(MapPermission::U) && area.vpn_range.contains(vpn)
+        })?;
+        Some(self.translate(vpn).map_or(false, |pte| pte.is_valid()))
+    }
     pub fn activate(&self) {
-        let satp = self.page_table.token();
+        let satp = self.token();
         unsafe {
             satp::write(satp);
-            asm!("sfence.vma");
         }
+        flush_tlb(None);
     }
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
         self.page_table.translate(vpn)
     }
+    /// Areas must not overlap, and each page of an area must be mapped
+    /// with the permission of the area, to its own frame if it is framed.
+    pub fn check_invariants(&self) -> bool {
+        let perm_mask = PTEFlags::R | PTEFlags::W | PTEFlags::X | PTEFlags::U;
+        self.areas.iter().enumerate().all(|(i, area)| {
+            let disjoint = self.areas[i + 1..]
+                .iter()
+                .all(|other| !other.vpn_range.overlaps(&area.vpn_range));
+            disjoint
+                && area
+                    .vpn_range
+                    .into_iter()
+                    .all(|vpn| match self.page_table.translate(vpn) {
+                        Some(pte) if pte.is_valid() => {
+                            // a copy-on-write page gets its write permission back
+                            // once copied
+                            let flags = if pte.is_cow() {
+                                pte.flags() | PTEFlags::W
+                            } else {
+                                pte.flags()
+                            };
+                            (flags & perm_mask).bits() == area.map_perm.bits() as u16
+                                && (!pte.is_cow() || area.map_perm.contains(MapPermission::W))
+                                && match area.map_type {
+                                    MapType::Framed => area
+                                        .data_frames
+                                        .get(&vpn)
+                                        .map_or(false, |frame| frame.ppn == pte.ppn()),
+                                    _ => true,
+                                }
+                        }
+                        // not touched yet
+                        _ => area.lazy && !area.data_frames.contains_key(&vpn),
+                    })
+        })
+    }
+    /// Add the frames held by this space, those of the page table included.
+    /// Frames shared copy-on-write are added once.
+    pub fn collect_frames(&self, frames: &mut BTreeSet<PhysPageNum>) {
+        self.page_table.collect_frames(frames);
+        for area in self.areas.iter() {
+            frames.extend(area.data_frames.values().map(|frame| frame.ppn));
+        }
+    }
+    /// Every area and the trampoline, which is not one, sorted by start.
+    pub fn describe(&self) -> Vec<AreaInfo> {
+        let mut areas: Vec<AreaInfo> = self
+            .areas
+            .iter()
+            .map(|area| AreaInfo {
+                start: area.vpn_range.get_start(),
+                end: area.vpn_range.get_end(),
+                map_type: area.map_type,
+                perm: area.map_perm,
+                lazy: area.lazy,
+            })
+            .collect();
+        let trampoline: VirtPageNum = VirtAddr::from(TRAMPOLINE).into();
+        if let Some(pte) = self.translate(trampoline).filter(|pte| pte.is_valid()) {
+            areas.push(AreaInfo {
+                start: trampoline,
+                end: trampoline + 1,
+                map_type: MapType::Linear(pte.ppn().0 as isize - trampoline.0 as isize),
+                perm: MapPermission::from_bits_truncate(pte.flags().bits() as u8),
+                lazy: false,
+            });
+        }
+        areas.sort_by_key(|area| area.start);
+        areas
+    }
+    /// Log the areas at the debug level.
+    pub fn dump(&self) {
+        for area in self.describe() {
+            debug!(
+                "{:?}..{:?} {:?} {:?}{}",
+                area.start,
+                area.end,
+                area.map_type,
+                area.perm,
+                if area.lazy { " lazy" } else { "" }
+            );
+        }
+    }
+    pub fn validate_page_table(&self) -> bool {
+        self.page_table.validate()
+    }
     pub fn recycle_data_pages(&mut self) {
         //*self = Self::new_bare();
         self.areas.clear();
@@ -249,9 +744,31 @@ impl MemorySet {
 
 pub struct MapArea {
     vpn_range: VPNRange,
-    data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    /// frames shared copy-on-write are held by every space mapping them
+    data_frames: BTreeMap<VirtPageNum, Arc<FrameTracker>>,
     map_type: MapType,
     map_perm: MapPermission,
+    /// framed pages are mapped on their first access instead of up front
+    lazy: bool,
+    /// map the aligned huge pages of an identical or linear area with a leaf
+    /// each, the pages around them are mapped one by one. Such areas are
+    /// never split or resized
+    huge: bool,
+    /// id of the shared memory segment whose frames are mapped, those are
+    /// never copied
+    shm: Option<usize>,
+    /// the pages stay mapped to the same frames, writable, in the spaces
+    /// forked from this one instead of being copied on write (MAP_SHARED)
+    shared: bool,
+}
+
+impl Drop for MapArea {
+    fn drop(&mut self) {
+        if let Some(id) = self.shm {
+            self.data_frames.clear();
+            shm_release(id);
+        }
+    }
 }
 
 impl MapArea {
@@ -268,6 +785,10 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type,
             map_perm,
+            lazy: false,
+            huge: false,
+            shm: None,
+            shared: false,
         }
     }
     pub fn from_another(another: &MapArea) -> Self {
@@ -276,68 +797,248 @@ impl MapArea {
             data_frames: BTreeMap::new(),
             map_type: another.map_type,
             map_perm: another.map_perm,
+            lazy: another.lazy,
+            huge: another.huge,
+            shm: another.shm,
+            shared: another.shared,
         }
     }
-    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
-        let ppn: PhysPageNum;
+    /// The frame `vpn` is mapped to, if it does not get a frame of its own.
+    fn fixed_ppn(&self, vpn: VirtPageNum) -> Option<PhysPageNum> {
         match self.map_type {
-            MapType::Identical => {
-                ppn = PhysPageNum(vpn.0);
-            }
-            MapType::Framed => {
-                let frame = frame_alloc().unwrap();
-                ppn = frame.ppn;
-                self.data_frames.insert(vpn, frame);
-            }
+            MapType::Identical => Some(PhysPageNum(vpn.0)),
+            MapType::Framed => None,
             MapType::Linear(pn_offset) => {
                 // check for sv39
                 assert!(vpn.0 < (1usize << 27));
-                ppn = PhysPageNum((vpn.0 as isize + pn_offset) as usize);
+                Some(PhysPageNum((vpn.0 as isize + pn_offset) as usize))
             }
         }
-        let pte_flags = PTEFlags::from_bits(self.map_perm.bits).unwrap();
+    }
+    /// `vpn` begins a huge page of this area that can be mapped with one leaf.
+    fn is_huge_at(&self, vpn: VirtPageNum) -> bool {
+        let pages = HUGE_PAGE_SIZE / PAGE_SIZE;
+        self.huge
+            && vpn.0 % pages == 0
+            && vpn + pages <= self.vpn_range.get_end()
+            && self.fixed_ppn(vpn).map_or(false, |ppn| ppn.0 % pages == 0)
+    }
+    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        let ppn = match self.fixed_ppn(vpn) {
+            Some(ppn) => ppn,
+            None => {
+                let frame = frame_alloc().unwrap();
+                let ppn = frame.ppn;
+                self.data_frames.insert(vpn, Arc::new(frame));
+                ppn
+            }
+        };
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits as u16).unwrap();
         page_table.map(vpn, ppn, pte_flags);
     }
     pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
-        if self.map_type == MapType::Framed {
-            self.data_frames.remove(&vpn);
+        if self.map_type == MapType::Framed && self.data_frames.remove(&vpn).is_none() {
+            // never touched in a lazy area
+            return;
         }
         page_table.unmap(vpn);
     }
     pub fn map(&mut self, page_table: &mut PageTable) {
-        for vpn in self.vpn_range {
-            self.map_one(page_table, vpn);
+        if self.lazy {
+            return;
+        }
+        let mut vpn = self.vpn_range.get_start();
+        while vpn < self.vpn_range.get_end() {
+            if self.is_huge_at(vpn) {
+                let pte_flags = PTEFlags::from_bits(self.map_perm.bits as u16).unwrap();
+                page_table.map_huge(vpn, self.fixed_ppn(vpn).unwrap(), pte_flags);
+                vpn = vpn + HUGE_PAGE_SIZE / PAGE_SIZE;
+            } else {
+                self.map_one(page_table, vpn);
+                vpn.step();
+            }
         }
     }
     pub fn unmap(&mut self, page_table: &mut PageTable) {
-        for vpn in self.vpn_range {
+        let mut vpn = self.vpn_range.get_start();
+        while vpn < self.vpn_range.get_end() {
+            if self.is_huge_at(vpn) {
+                page_table.unmap_huge(vpn);
+                vpn = vpn + HUGE_PAGE_SIZE / PAGE_SIZE;
+            } else {
+                self.unmap_one(page_table, vpn);
+                vpn.step();
+            }
+        }
+    }
+    pub fn shrink_to(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) {
+        for vpn in VPNRange::new(new_end, self.vpn_range.get_end()) {
             self.unmap_one(page_table, vpn);
         }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+    }
+    pub fn append_to(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) {
+        if !self.lazy {
+            for vpn in VPNRange::new(self.vpn_range.get_end(), new_end) {
+                self.map_one(page_table, vpn);
+            }
+        }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+    }
+    /// Move the pages from `at` on into a new area of the same kind.
+    pub fn split_off(&mut self, at: VirtPageNum) -> Self {
+        let tail = Self {
+            vpn_range: VPNRange::new(at, self.vpn_range.get_end()),
+            data_frames: self.data_frames.split_off(&at),
+            map_type: self.map_type,
+            map_perm: self.map_perm,
+            lazy: self.lazy,
+            huge: self.huge,
+            shm: self.shm,
+            shared: self.shared,
+        };
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), at);
+        tail
+    }
+    /// Its frames are shared with the spaces forked from this one, never
+    /// copied.
+    fn is_shared(&self) -> bool {
+        self.shm.is_some() || self.shared
+    }
+    /// Change the permission of the area and remap its pages with it. A
+    /// frame still shared with another space stays copy-on-write, unless it
+    /// is shared memory or a shared mapping.
+    pub fn protect(&mut self, page_table: &mut PageTable, perm: MapPermission) {
+        self.map_perm = perm;
+        let flags = PTEFlags::from_bits(perm.bits as u16).unwrap();
+        for (&vpn, frame) in self.data_frames.iter() {
+            let flags =
+                if flags.contains(PTEFlags::W) && !self.is_shared() && Arc::strong_count(frame) > 1
+                {
+                    (flags - PTEFlags::W) | PTEFlags::COW
+                } else {
+                    flags
+                };
+            page_table.remap(vpn, frame.ppn, flags);
+        }
     }
     /// data: start-aligned but maybe with shorter length
     /// assume that all frames were cleared before
-    pub fn copy_data(&mut self, page_table: &mut PageTable, data: &[u8]) {
+    /// Copy data to the area from `offset` bytes into its first page on.
+    pub fn copy_data(&mut self, page_table: &mut PageTable, offset: usize, data: &[u8]) {
         assert_eq!(self.map_type, MapType::Framed);
-        let mut start: usize = 0;
         let mut current_vpn = self.vpn_range.get_start();
-        let len = data.len();
-        loop {
-            let src = &data[start..len.min(start + PAGE_SIZE)];
+        let mut page_offset = offset;
+        let mut data = data;
+        while !data.is_empty() {
+            let len = data.len().min(PAGE_SIZE - page_offset);
             let dst = &mut page_table
                 .translate(current_vpn)
                 .unwrap()
                 .ppn()
-                .get_bytes_array()[..src.len()];
-            dst.copy_from_slice(src);
-            start += PAGE_SIZE;
-            if start >= len {
-                break;
-            }
+                .get_bytes_array()[page_offset..page_offset + len];
+            dst.copy_from_slice(&data[..len]);
+            data = &data[len..];
+            page_offset = 0;
             current_vpn.step();
         }
     }
 }
 
+/// tags of the dynamic segment
+const DT_NULL: u64 = 0;
+const DT_RELA: u64 = 7;
+const DT_RELASZ: u64 = 8;
+const DT_RELAENT: u64 = 9;
+/// types of relocations
+const R_RISCV_NONE: u64 = 0;
+const R_RISCV_RELATIVE: u64 = 3;
+/// bytes of an Elf64_Rela
+const RELA_SIZE: usize = 24;
+
+/// Where the `len` bytes from the link address `va` of the image are in its
+/// file. None if they are outside the load segments, Some(None) if they are
+/// in one but not in the file, as its bss is.
+fn file_offset_of(elf: &ElfFile, va: usize, len: usize) -> Option<Option<usize>> {
+    let ph = (0..elf.header.pt2.ph_count())
+        .filter_map(|i| elf.program_header(i).ok())
+        .filter(|ph| ph.get_type() == Ok(xmas_elf::program::Type::Load))
+        .find(|ph| {
+            let start = ph.virtual_addr() as usize;
+            start <= va && va + len <= start + ph.mem_size() as usize
+        })?;
+    let offset = va - ph.virtual_addr() as usize;
+    let in_file = offset + len <= ph.file_size() as usize;
+    Some(in_file.then(|| ph.offset() as usize + offset))
+}
+
+/// The u64 at the link address `va` of the image, read from its file.
+fn read_u64(elf: &ElfFile, va: usize) -> Option<u64> {
+    let offset = file_offset_of(elf, va, 8)??;
+    let bytes = elf.input.get(offset..offset + 8)?;
+    Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+/// The offsets and addends of the R_RISCV_RELATIVE relocations of the
+/// image, to which the load base is added, from the RELA table of its
+/// dynamic segment. None if the table is not in the file, or a relocation
+/// is of another type or outside the load segments.
+fn relative_relocations(elf: &ElfFile) -> Option<Vec<(usize, usize)>> {
+    let dynamic = match (0..elf.header.pt2.ph_count())
+        .filter_map(|i| elf.program_header(i).ok())
+        .find(|ph| ph.get_type() == Ok(xmas_elf::program::Type::Dynamic))
+    {
+        Some(dynamic) => dynamic,
+        None => return Some(Vec::new()),
+    };
+    let (mut rela, mut rela_size, mut rela_entry) = (0, 0, RELA_SIZE);
+    for i in 0..dynamic.file_size() as usize / 16 {
+        let entry = dynamic.offset() as usize + i * 16;
+        let word = |at: usize| -> Option<u64> {
+            let bytes = elf.input.get(at..at + 8)?;
+            Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+        };
+        let value = word(entry + 8)? as usize;
+        match word(entry)? {
+            DT_NULL => break,
+            DT_RELA => rela = value,
+            DT_RELASZ => rela_size = value,
+            DT_RELAENT => rela_entry = value,
+            _ => {}
+        }
+    }
+    if rela_entry != RELA_SIZE {
+        return None;
+    }
+    let mut relocations = Vec::new();
+    for i in 0..rela_size / RELA_SIZE {
+        let entry = rela + i * RELA_SIZE;
+        let offset = read_u64(elf, entry)? as usize;
+        let info = read_u64(elf, entry + 8)?;
+        let addend = read_u64(elf, entry + 16)? as usize;
+        match info & 0xffff_ffff {
+            R_RISCV_NONE => {}
+            R_RISCV_RELATIVE => {
+                file_offset_of(elf, offset, 8)?;
+                relocations.push((offset, addend));
+            }
+            _ => return None,
+        }
+    }
+    Some(relocations)
+}
+
+/// One mapping of a MemorySet, see `describe`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AreaInfo {
+    pub start: VirtPageNum,
+    pub end: VirtPageNum,
+    pub map_type: MapType,
+    pub perm: MapPermission,
+    /// framed pages are mapped on their first access
+    pub lazy: bool,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum MapType {
     Identical,
@@ -355,9 +1056,10 @@ bitflags! {
     }
 }
 
-#[allow(unused)]
+/// Check the permissions of the kernel sections, needs the kernel trap entry
+/// to recover from the stores.
 pub fn remap_test() {
-    let mut kernel_space = KERNEL_SPACE.exclusive_access();
+    let kernel_space = KERNEL_SPACE.exclusive_access();
     let mid_text: VirtAddr = ((stext as usize + etext as usize) / 2).into();
     let mid_rodata: VirtAddr = ((srodata as usize + erodata as usize) / 2).into();
     let mid_data: VirtAddr = ((sdata as usize + edata as usize) / 2).into();
@@ -376,5 +1078,164 @@ pub fn remap_test() {
         .translate(mid_data.floor())
         .unwrap()
         .executable(),);
+    drop(kernel_space);
+    // a store really faults, copying a byte onto itself keeps it intact if
+    // the mapping were wrong
+    for va in [mid_text, mid_rodata] {
+        let ptr = va.0 as *mut u8;
+        assert_ne!(unsafe { copy_bytes(ptr, ptr, 1) }, 0);
+    }
     println!("remap_test passed!");
 }
+
+/// The areas described for an app are its load segments, the empty heap
+/// after them and the trampoline, in this order.
+pub fn describe_test() {
+    let elf_data = open_file("initproc", OpenFlags::RDONLY).unwrap().read_all();
+    let (memory_set, heap_bottom, _, _) = MemorySet::from_elf(&elf_data);
+    let elf = xmas_elf::ElfFile::new(&elf_data).unwrap();
+    let mut segments: Vec<(VirtPageNum, VirtPageNum)> = (0..elf.header.pt2.ph_count())
+        .map(|i| elf.program_header(i).unwrap())
+        .filter(|ph| ph.get_type().unwrap() == xmas_elf::program::Type::Load)
+        .map(|ph| {
+            let start = VirtAddr::from(ph.virtual_addr() as usize).floor();
+            let end = VirtAddr::from((ph.virtual_addr() + ph.mem_size()) as usize).ceil();
+            (start, end)
+        })
+        .collect();
+    segments.sort();
+    let areas = memory_set.describe();
+    assert_eq!(areas.len(), segments.len() + 2);
+    for (area, &(start, end)) in areas.iter().zip(segments.iter()) {
+        assert_eq!((area.start, area.end), (start, end));
+        assert_eq!(area.map_type, MapType::Framed);
+        assert!(area.perm.contains(MapPermission::U));
+        assert!(!area.lazy);
+    }
+    let heap = areas[segments.len()];
+    let heap_bottom = VirtAddr::from(heap_bottom).floor();
+    assert_eq!((heap.start, heap.end), (heap_bottom, heap_bottom));
+    assert!(heap.lazy);
+    let trampoline = areas[segments.len() + 1];
+    assert_eq!(trampoline.start, VirtAddr::from(TRAMPOLINE).floor());
+    assert_eq!(trampoline.end, trampoline.start + 1);
+    assert_eq!(trampoline.perm, MapPermission::R | MapPermission::X);
+    assert_eq!(
+        trampoline.map_type,
+        MapType::Linear(
+            strampoline as usize as isize / PAGE_SIZE as isize - trampoline.start.0 as isize
+        )
+    );
+    println!("describe_test passed!");
+}
+
+/// Read `len` bytes at `va` of `memory_set`, which need not be active.
+fn read_bytes(memory_set: &MemorySet, va: usize, len: usize) -> Vec<u8> {
+    translated_byte_buffer(memory_set.token(), va as *const u8, len).concat()
+}
+
+/// A position-independent image is loaded at PIE_BASE with its relative
+/// relocations applied, a segment starting in the middle of a page is
+/// loaded there, and its bss running over page boundaries is mapped and
+/// zero. A relocation the kernel cannot apply makes the image invalid.
+pub fn pie_test() {
+    let put = |image: &mut Vec<u8>, at: usize, bytes: &[u8]| {
+        image[at..at + bytes.len()].copy_from_slice(bytes);
+    };
+    let mut image = vec![0u8; 0x178];
+    // ELF header: ET_DYN for RISC-V, entry 0x10, 3 program headers at 64
+    put(&mut image, 0, &[0x7f, b'E', b'L', b'F', 2, 1, 1]);
+    put(&mut image, 16, &3u16.to_le_bytes());
+    put(&mut image, 18, &0xf3u16.to_le_bytes());
+    put(&mut image, 20, &1u32.to_le_bytes());
+    put(&mut image, 24, &0x10u64.to_le_bytes());
+    put(&mut image, 32, &64u64.to_le_bytes());
+    for (at, half) in [(52, 64u16), (54, 56), (56, 3), (58, 64)] {
+        put(&mut image, at, &half.to_le_bytes());
+    }
+    // (type, flags, offset, vaddr, filesz, memsz): the text with the headers,
+    // the data at 0x1100 with its bss up to 0x3010, and the dynamic segment
+    // at the start of the data
+    let segments = [
+        (1u32, 5u32, 0u64, 0u64, 0x100u64, 0x100u64),
+        (1, 6, 0x100, 0x1100, 0x78, 0x1f10),
+        (2, 6, 0x100, 0x1100, 0x40, 0x40),
+    ];
+    for (i, (type_, flags, offset, vaddr, file_size, mem_size)) in segments.iter().enumerate() {
+        let at = 64 + i * 56;
+        put(&mut image, at, &type_.to_le_bytes());
+        put(&mut image, at + 4, &flags.to_le_bytes());
+        for (field, value) in [offset, vaddr, vaddr, file_size, mem_size]
+            .iter()
+            .enumerate()
+        {
+            put(&mut image, at + 8 + field * 8, &value.to_le_bytes());
+        }
+    }
+    // the dynamic entries, a RELA table with a relative relocation of the
+    // word at 0x1170 and a none one, then that word
+    let dynamic = [
+        (DT_RELA, 0x1140),
+        (DT_RELASZ, 48),
+        (DT_RELAENT, 24),
+        (DT_NULL, 0),
+    ];
+    let relas = [(0x1170, R_RISCV_RELATIVE, 0x1234), (0, R_RISCV_NONE, 0)];
+    let words = dynamic.iter().flat_map(|&(tag, value)| [tag, value]).chain(
+        relas
+            .iter()
+            .flat_map(|&(offset, info, addend)| [offset, info, addend]),
+    );
+    for (i, word) in words.enumerate() {
+        put(&mut image, 0x100 + i * 8, &word.to_le_bytes());
+    }
+    assert!(MemorySet::elf_is_valid(&image));
+    let (memory_set, heap_bottom, _, entry) = MemorySet::from_elf(&image);
+    assert_eq!(entry, PIE_BASE + 0x10);
+    assert_eq!(heap_bottom, PIE_BASE + 0x4000);
+    let word = |va: usize| u64::from_le_bytes(read_bytes(&memory_set, va, 8).try_into().unwrap());
+    assert_eq!(word(PIE_BASE + 0x1100), DT_RELA);
+    assert_eq!(word(PIE_BASE + 0x1170), (PIE_BASE + 0x1234) as u64);
+    let bss = read_bytes(&memory_set, PIE_BASE + 0x1178, 0x3010 - 0x1178);
+    assert!(bss.iter().all(|&byte| byte == 0));
+    drop(memory_set);
+    // R_RISCV_64 needs a symbol
+    put(&mut image, 0x148, &2u64.to_le_bytes());
+    assert!(!MemorySet::elf_is_valid(&image));
+    println!("pie_test passed!");
+}
+
+/// A 4 MiB identical area with an unaligned page before and after it is
+/// mapped by two huge leaves in the middle level and four normal pages.
+pub fn huge_page_test() {
+    let pages = HUGE_PAGE_SIZE / PAGE_SIZE;
+    let mut memory_set = MemorySet::new_bare();
+    let base = VirtAddr::from(0x9000_0000).floor();
+    let mut area = MapArea::new(
+        (base - 2).into(),
+        (base + 2 * pages + 2).into(),
+        MapType::Identical,
+        MapPermission::R | MapPermission::W,
+    );
+    area.huge = true;
+    memory_set.push(area, None);
+    for vpn in VPNRange::new(base - 2, base + 2 * pages + 2) {
+        let pte = memory_set.translate(vpn).unwrap();
+        assert!(pte.is_valid() && pte.readable() && pte.writable());
+        assert_eq!(pte.ppn().0, vpn.0);
+        let level = memory_set.page_table.leaf_level(vpn).unwrap();
+        let huge = base <= vpn && vpn < base + 2 * pages;
+        assert_eq!(level, if huge { 1 } else { 2 });
+    }
+    // the root, a middle table and the two tables of the normal pages
+    let mut frames = BTreeSet::new();
+    memory_set.page_table.collect_frames(&mut frames);
+    assert_eq!(frames.len(), 4);
+    assert!(memory_set.validate_page_table());
+    assert!(memory_set.check_invariants());
+    memory_set.remove_area_with_start_vpn(base - 2);
+    for vpn in VPNRange::new(base - 2, base + 2 * pages + 2) {
+        assert!(memory_set.page_table.leaf_level(vpn).is_none());
+    }
+    println!("huge_page_test passed!");
+}
@@ -1,22 +1,35 @@
 mod address;
+mod asid;
 mod frame_allocator;
 mod heap_allocator;
 mod memory_set;
 mod page_table;
+mod shm;
+mod tlb;
 
-pub use address::VPNRange;
+pub use address::{address_test, PPNRange, VPNRange};
 pub use address::{PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
-pub use frame_allocator::{frame_alloc, frame_dealloc, FrameTracker};
-pub use memory_set::remap_test;
-pub use memory_set::{kernel_token, MapArea, MapPermission, MapType, MemorySet, KERNEL_SPACE};
+pub use frame_allocator::{
+    alloc_contiguous, contiguous_alloc_test, dealloc_contiguous, frame_alloc, frame_alloc_hinted,
+    frame_allocator_check, frame_dealloc, frame_poison_test, frames_allocated, FrameTracker,
+    MAX_ORDER,
+};
+pub use heap_allocator::{heap_stats, HeapStats};
+pub use memory_set::{describe_test, huge_page_test, pie_test, remap_test};
+pub use memory_set::{
+    kernel_token, AreaInfo, MapArea, MapPermission, MapType, MemorySet, KERNEL_SPACE,
+};
 use page_table::PTEFlags;
 pub use page_table::{
-    translated_byte_buffer, translated_ref, translated_refmut, translated_str, PageTable,
-    PageTableEntry, UserBuffer, UserBufferIterator,
+    translated_byte_buffer, translated_refmut, PageTable, PageTableEntry, UserBuffer,
+    UserBufferIterator,
 };
+pub use shm::{shm_collect_frames, shm_frames, shm_get, IPC_PRIVATE};
+pub use tlb::{flush_tlb, tlb_stats, TlbStats};
 
 pub fn init() {
     heap_allocator::init_heap();
     frame_allocator::init_frame_allocator();
     KERNEL_SPACE.exclusive_access().activate();
+    asid::init();
 }
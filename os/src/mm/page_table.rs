@@ -1,11 +1,12 @@
 use super::{frame_alloc, FrameTracker, PhysAddr, PhysPageNum, StepByOne, VirtAddr, VirtPageNum};
-use alloc::string::String;
+use crate::config::{HUGE_PAGE_SIZE, PAGE_SIZE};
+use alloc::collections::BTreeSet;
 use alloc::vec;
 use alloc::vec::Vec;
 use bitflags::*;
 
 bitflags! {
-    pub struct PTEFlags: u8 {
+    pub struct PTEFlags: u16 {
         const V = 1 << 0;
         const R = 1 << 1;
         const W = 1 << 2;
@@ -14,6 +15,8 @@ bitflags! {
         const G = 1 << 5;
         const A = 1 << 6;
         const D = 1 << 7;
+        /// reserved for software, the page is shared by fork until written
+        const COW = 1 << 8;
     }
 }
 
@@ -36,7 +39,7 @@ impl PageTableEntry {
         (self.bits >> 10 & ((1usize << 44) - 1)).into()
     }
     pub fn flags(&self) -> PTEFlags {
-        PTEFlags::from_bits(self.bits as u8).unwrap()
+        PTEFlags::from_bits_truncate(self.bits as u16)
     }
     pub fn is_valid(&self) -> bool {
         (self.flags() & PTEFlags::V) != PTEFlags::empty()
@@ -50,6 +53,16 @@ impl PageTableEntry {
     pub fn executable(&self) -> bool {
         (self.flags() & PTEFlags::X) != PTEFlags::empty()
     }
+    pub fn is_user(&self) -> bool {
+        (self.flags() & PTEFlags::U) != PTEFlags::empty()
+    }
+    pub fn is_cow(&self) -> bool {
+        (self.flags() & PTEFlags::COW) != PTEFlags::empty()
+    }
+    /// Maps a page instead of pointing to the next level of the table.
+    pub fn is_leaf(&self) -> bool {
+        self.is_valid() && (self.readable() || self.writable() || self.executable())
+    }
 }
 
 pub struct PageTable {
@@ -73,16 +86,18 @@ impl PageTable {
             frames: Vec::new(),
         }
     }
-    fn find_pte_create(&mut self, vpn: VirtPageNum) -> Option<&mut PageTableEntry> {
+    /// The entry for `vpn` at `level`, 1 for a huge page and 2 otherwise.
+    fn find_pte_create(&mut self, vpn: VirtPageNum, level: usize) -> Option<&mut PageTableEntry> {
         let idxs = vpn.indexes();
         let mut ppn = self.root_ppn;
         let mut result: Option<&mut PageTableEntry> = None;
         for (i, idx) in idxs.iter().enumerate() {
             let pte = &mut ppn.get_pte_array()[*idx];
-            if i == 2 {
+            if i == level {
                 result = Some(pte);
                 break;
             }
+            assert!(!pte.is_leaf(), "vpn {:?} is in a huge page", vpn);
             if !pte.is_valid() {
                 let frame = frame_alloc().unwrap();
                 *pte = PageTableEntry::new(frame.ppn, PTEFlags::V);
@@ -102,39 +117,126 @@ impl PageTable {
                 result = Some(pte);
                 break;
             }
-            if !pte.is_valid() {
+            // a huge page has no entry of its own for vpn
+            if !pte.is_valid() || pte.is_leaf() {
                 return None;
             }
             ppn = pte.ppn();
         }
         result
     }
+    /// The leaf mapping `vpn` and its level, 1 for a huge page and 2
+    /// otherwise, or the invalid entry met on the way down.
+    fn find_leaf(&self, vpn: VirtPageNum) -> Option<(&mut PageTableEntry, usize)> {
+        let idxs = vpn.indexes();
+        let mut ppn = self.root_ppn;
+        for (i, idx) in idxs.iter().enumerate() {
+            let pte = &mut ppn.get_pte_array()[*idx];
+            if i == 2 || pte.is_leaf() {
+                return Some((pte, i));
+            }
+            if !pte.is_valid() {
+                return None;
+            }
+            ppn = pte.ppn();
+        }
+        None
+    }
     #[allow(unused)]
     pub fn map(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
-        let pte = self.find_pte_create(vpn).unwrap();
+        let pte = self.find_pte_create(vpn, 2).unwrap();
         assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
         *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
     }
+    /// Map the pages of the huge page from `vpn` to the frames from `ppn`
+    /// with a single leaf, both must be aligned to a huge page.
+    pub fn map_huge(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
+        let pages = HUGE_PAGE_SIZE / PAGE_SIZE;
+        assert!(
+            vpn.0 % pages == 0 && ppn.0 % pages == 0,
+            "huge page {:?} -> {:?} is not aligned",
+            vpn,
+            ppn
+        );
+        let pte = self.find_pte_create(vpn, 1).unwrap();
+        assert!(!pte.is_valid(), "vpn {:?} is mapped before mapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+    }
+    /// Unmap the huge page beginning at `vpn`.
+    pub fn unmap_huge(&mut self, vpn: VirtPageNum) {
+        match self.find_leaf(vpn) {
+            Some((pte, 1)) if vpn.indexes()[2] == 0 => {
+                *pte = PageTableEntry::empty();
+            }
+            _ => panic!("vpn {:?} is not a huge page before unmapping", vpn),
+        }
+    }
+    /// Change the frame and flags of a mapped page.
+    pub fn remap(&mut self, vpn: VirtPageNum, ppn: PhysPageNum, flags: PTEFlags) {
+        let pte = self.find_pte(vpn).unwrap();
+        assert!(pte.is_valid(), "vpn {:?} is invalid before remapping", vpn);
+        *pte = PageTableEntry::new(ppn, flags | PTEFlags::V);
+    }
     #[allow(unused)]
     pub fn unmap(&mut self, vpn: VirtPageNum) {
         let pte = self.find_pte(vpn).unwrap();
         assert!(pte.is_valid(), "vpn {:?} is invalid before unmapping", vpn);
         *pte = PageTableEntry::empty();
     }
+    /// A page in a huge page is given an entry of its own, pointing to its
+    /// frame within the huge one.
     pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-        self.find_pte(vpn).map(|pte| *pte)
+        self.find_leaf(vpn).map(|(pte, level)| match level {
+            1 => PageTableEntry::new(pte.ppn() + vpn.indexes()[2], pte.flags()),
+            _ => *pte,
+        })
+    }
+    /// Level of the leaf mapping `vpn`, 1 for a huge page and 2 otherwise.
+    pub fn leaf_level(&self, vpn: VirtPageNum) -> Option<usize> {
+        self.find_leaf(vpn)
+            .filter(|(pte, _)| pte.is_valid())
+            .map(|(_, level)| level)
     }
     pub fn translate_va(&self, va: VirtAddr) -> Option<PhysAddr> {
-        self.find_pte(va.clone().floor()).map(|pte| {
+        self.translate(va.clone().floor()).map(|pte| {
             let aligned_pa: PhysAddr = pte.ppn().into();
             let offset = va.page_offset();
             let aligned_pa_usize: usize = aligned_pa.into();
             (aligned_pa_usize + offset).into()
         })
     }
+    /// Add the frames holding the page table.
+    pub fn collect_frames(&self, frames: &mut BTreeSet<PhysPageNum>) {
+        frames.extend(self.frames.iter().map(|frame| frame.ppn));
+    }
+    /// satp value of this table, the ASID is left for `MemorySet::token`
     pub fn token(&self) -> usize {
         8usize << 60 | self.root_ppn.0
     }
+    /// Every valid non-leaf entry must point to a page table frame owned
+    /// by this table, no leaf may be writable without being readable and
+    /// the leaves of huge pages must be aligned.
+    pub fn validate(&self) -> bool {
+        self.validate_level(self.root_ppn, 0)
+    }
+    fn validate_level(&self, ppn: PhysPageNum, level: usize) -> bool {
+        ppn.get_pte_array()
+            .iter()
+            .filter(|pte| pte.is_valid())
+            .all(|pte| {
+                if level == 2 {
+                    pte.readable() || !pte.writable()
+                } else if pte.is_leaf() {
+                    level == 1 && pte.readable() && pte.ppn().0 % (HUGE_PAGE_SIZE / PAGE_SIZE) == 0
+                } else {
+                    !pte.readable()
+                        && !pte.writable()
+                        && !pte.executable()
+                        && self.frames.iter().any(|frame| frame.ppn == pte.ppn())
+                        && self.validate_level(pte.ppn(), level + 1)
+                }
+            })
+    }
 }
 
 pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&'static mut [u8]> {
@@ -159,33 +261,6 @@ pub fn translated_byte_buffer(token: usize, ptr: *const u8, len: usize) -> Vec<&
     v
 }
 
-/// Load a string from other address spaces into kernel space without an end `\0`.
-pub fn translated_str(token: usize, ptr: *const u8) -> String {
-    let page_table = PageTable::from_token(token);
-    let mut string = String::new();
-    let mut va = ptr as usize;
-    loop {
-        let ch: u8 = *(page_table
-            .translate_va(VirtAddr::from(va))
-            .unwrap()
-            .get_mut());
-        if ch == 0 {
-            break;
-        }
-        string.push(ch as char);
-        va += 1;
-    }
-    string
-}
-
-pub fn translated_ref<T>(token: usize, ptr: *const T) -> &'static T {
-    let page_table = PageTable::from_token(token);
-    page_table
-        .translate_va(VirtAddr::from(ptr as usize))
-        .unwrap()
-        .get_ref()
-}
-
 pub fn translated_refmut<T>(token: usize, ptr: *mut T) -> &'static mut T {
     let page_table = PageTable::from_token(token);
     let va = ptr as usize;
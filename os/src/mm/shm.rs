@@ -0,0 +1,87 @@
+//! Shared memory segments. The frames of a segment are mapped into every
+//! process attaching it, at an address of its own, and are freed once the
+//! last attachment goes away.
+
+use super::{frame_alloc, FrameTracker, PhysPageNum};
+use crate::config::PAGE_SIZE;
+use crate::sync::UPIntrFreeCell;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+
+/// key of shm_get creating a new segment every time
+pub const IPC_PRIVATE: usize = 0;
+
+struct ShmSegment {
+    key: usize,
+    /// held by the area of every attachment as well
+    frames: Vec<Arc<FrameTracker>>,
+}
+
+struct ShmRegistry {
+    segments: BTreeMap<usize, ShmSegment>,
+    next_id: usize,
+}
+
+lazy_static! {
+    static ref SHM: UPIntrFreeCell<ShmRegistry> = unsafe {
+        UPIntrFreeCell::new(ShmRegistry {
+            segments: BTreeMap::new(),
+            next_id: 1,
+        })
+    };
+}
+
+/// The id of the segment of `key`, which is created with `size` bytes
+/// rounded up to pages if there is none or key is IPC_PRIVATE. None if size
+/// is 0 or larger than the segment there is, or no frame is left.
+pub fn shm_get(key: usize, size: usize) -> Option<usize> {
+    if size == 0 {
+        return None;
+    }
+    let pages = (size - 1) / PAGE_SIZE + 1;
+    let mut shm = SHM.exclusive_access();
+    if key != IPC_PRIVATE {
+        if let Some((&id, segment)) = shm.segments.iter().find(|(_, seg)| seg.key == key) {
+            return (pages <= segment.frames.len()).then_some(id);
+        }
+    }
+    let frames = (0..pages)
+        .map(|_| frame_alloc().map(Arc::new))
+        .collect::<Option<Vec<_>>>()?;
+    let id = shm.next_id;
+    shm.next_id += 1;
+    shm.segments.insert(id, ShmSegment { key, frames });
+    Some(id)
+}
+
+/// The frames of segment `id`, for an attachment to map.
+pub fn shm_frames(id: usize) -> Option<Vec<Arc<FrameTracker>>> {
+    SHM.exclusive_access()
+        .segments
+        .get(&id)
+        .map(|segment| segment.frames.clone())
+}
+
+/// An attachment of segment `id` is gone, free the segment if it was the
+/// last one holding its frames.
+pub fn shm_release(id: usize) {
+    let mut shm = SHM.exclusive_access();
+    let unused = shm.segments.get(&id).map_or(false, |segment| {
+        segment
+            .frames
+            .iter()
+            .all(|frame| Arc::strong_count(frame) == 1)
+    });
+    if unused {
+        shm.segments.remove(&id);
+    }
+}
+
+/// Add the frames of every segment, attached or not.
+pub fn shm_collect_frames(frames: &mut BTreeSet<PhysPageNum>) {
+    for segment in SHM.exclusive_access().segments.values() {
+        frames.extend(segment.frames.iter().map(|frame| frame.ppn));
+    }
+}
@@ -0,0 +1,55 @@
+//! Keeping the cached address translations in sync with the page tables.
+//!
+//! A hart may keep using a translation after its page table entry changed
+//! until it is flushed, so whoever unmaps a page, or takes a permission away
+//! from it, must flush it before the page is reused or the change is relied
+//! on.
+
+use super::asid::ASID_ROLLOVERS;
+use super::{VirtAddr, VirtPageNum};
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// Flushes so far, as reported by sys_tlb_stats.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TlbStats {
+    /// flushes of every page
+    pub full_flushes: usize,
+    /// flushes of a single page
+    pub page_flushes: usize,
+    /// times the ASIDs ran out, each one flushing every page
+    pub asid_rollovers: usize,
+}
+
+static FULL_FLUSHES: AtomicUsize = AtomicUsize::new(0);
+static PAGE_FLUSHES: AtomicUsize = AtomicUsize::new(0);
+
+pub fn tlb_stats() -> TlbStats {
+    TlbStats {
+        full_flushes: FULL_FLUSHES.load(Ordering::Relaxed),
+        page_flushes: PAGE_FLUSHES.load(Ordering::Relaxed),
+        asid_rollovers: ASID_ROLLOVERS.load(Ordering::Relaxed),
+    }
+}
+
+/// Drop the cached translations of `vpn`, or of every page if None, in every
+/// space and on every hart that may have cached them. There is only one hart
+/// yet, with more of them the others would be sent an IPI to flush their own
+/// here.
+pub fn flush_tlb(vpn: Option<VirtPageNum>) {
+    match vpn {
+        Some(_) => PAGE_FLUSHES.fetch_add(1, Ordering::Relaxed),
+        None => FULL_FLUSHES.fetch_add(1, Ordering::Relaxed),
+    };
+    local_flush_tlb(vpn);
+}
+
+fn local_flush_tlb(vpn: Option<VirtPageNum>) {
+    unsafe {
+        match vpn {
+            Some(vpn) => asm!("sfence.vma {}, zero", in(reg) VirtAddr::from(vpn).0),
+            None => asm!("sfence.vma"),
+        }
+    }
+}
@@ -47,6 +47,8 @@ impl Condvar {
         block_current_task()
     }
 
+    /// Releasing the mutex and queueing the caller are atomic as the kernel
+    /// is never preempted, so a signal in between cannot be missed.
     pub fn wait_with_mutex(&self, mutex: Arc<dyn Mutex>) {
         mutex.unlock();
         self.inner.exclusive_session(|inner| {
@@ -0,0 +1,62 @@
+//! Wait queues of futexes, keyed by the physical address of the futex word
+//! so that processes sharing the page meet in the same queue.
+
+use crate::sync::UPIntrFreeCell;
+use crate::task::{add_task, block_current_and_run_next, current_task, TaskControlBlock};
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::sync::Arc;
+use lazy_static::*;
+
+pub const FUTEX_WAIT: u32 = 0;
+pub const FUTEX_WAKE: u32 = 1;
+
+lazy_static! {
+    static ref FUTEX_QUEUES: UPIntrFreeCell<BTreeMap<usize, VecDeque<Arc<TaskControlBlock>>>> =
+        unsafe { UPIntrFreeCell::new(BTreeMap::new()) };
+}
+
+/// Block the current task on `word` if it still holds `val`, return whether
+/// it was blocked. The word is compared while the queues are borrowed, which
+/// masks interrupts, so a wake can not slip in between the compare and the
+/// enqueue.
+pub fn futex_wait(word: &u32, val: u32) -> bool {
+    let key = word as *const u32 as usize;
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    if unsafe { core::ptr::read_volatile(word) } != val {
+        return false;
+    }
+    queues
+        .entry(key)
+        .or_default()
+        .push_back(current_task().unwrap());
+    drop(queues);
+    block_current_and_run_next();
+    true
+}
+
+/// Wake up to `count` tasks blocked on the word at physical address `key`,
+/// return the number woken.
+pub fn futex_wake(key: usize, count: usize) -> usize {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    let queue = match queues.get_mut(&key) {
+        Some(queue) => queue,
+        None => return 0,
+    };
+    let n = count.min(queue.len());
+    for task in queue.drain(..n) {
+        add_task(task);
+    }
+    if queue.is_empty() {
+        queues.remove(&key);
+    }
+    n
+}
+
+/// Forget a task killed while blocked on some futex.
+pub fn remove_futex_waiter(task: &Arc<TaskControlBlock>) {
+    let mut queues = FUTEX_QUEUES.exclusive_access();
+    queues.retain(|_, queue| {
+        queue.retain(|t| !Arc::ptr_eq(t, task));
+        !queue.is_empty()
+    });
+}
@@ -64,6 +64,9 @@ impl MutexBlocking {
     }
 }
 
+/// The kernel is never preempted, so nothing can unlock between queueing
+/// the caller and blocking it. Unlock hands the mutex over to the first
+/// waiter without releasing it, so a wakeup is never lost or stolen.
 impl Mutex for MutexBlocking {
     fn lock(&self) {
         let mut mutex_inner = self.inner.exclusive_access();
@@ -1,5 +1,7 @@
-use core::cell::{RefCell, RefMut, UnsafeCell};
+use core::any::type_name;
+use core::cell::{Cell, RefCell, RefMut, UnsafeCell};
 use core::ops::{Deref, DerefMut};
+use core::panic::Location;
 use lazy_static::*;
 use riscv::register::sstatus;
 
@@ -92,6 +94,9 @@ impl IntrMaskingInfo {
 pub struct UPIntrFreeCell<T> {
     /// inner data
     inner: RefCell<T>,
+    /// where the data was borrowed last, reported when it is borrowed again
+    /// before that borrow is dropped
+    borrowed_at: Cell<Option<&'static Location<'static>>>,
 }
 
 unsafe impl<T> Sync for UPIntrFreeCell<T> {}
@@ -102,15 +107,30 @@ impl<T> UPIntrFreeCell<T> {
     pub unsafe fn new(value: T) -> Self {
         Self {
             inner: RefCell::new(value),
+            borrowed_at: Cell::new(None),
         }
     }
 
-    /// Panic if the data has been borrowed.
+    /// Panic if the data has been borrowed, naming its type and where the
+    /// borrow still held was taken.
+    #[track_caller]
     pub fn exclusive_access(&self) -> UPIntrRefMut<'_, T> {
         INTR_MASKING_INFO.get_mut().enter();
-        UPIntrRefMut(Some(self.inner.borrow_mut()))
+        match self.inner.try_borrow_mut() {
+            Ok(inner) => {
+                self.borrowed_at.set(Some(Location::caller()));
+                UPIntrRefMut(Some(inner))
+            }
+            Err(_) => panic!(
+                "{} is borrowed at {} while borrowed at {}",
+                type_name::<T>(),
+                Location::caller(),
+                self.borrowed_at.get().unwrap()
+            ),
+        }
     }
 
+    #[track_caller]
     pub fn exclusive_session<F, V>(&self, f: F) -> V
     where
         F: FnOnce(&mut T) -> V,
@@ -138,3 +158,16 @@ impl<'a, T> DerefMut for UPIntrRefMut<'a, T> {
         self.0.as_mut().unwrap().deref_mut()
     }
 }
+
+/// A cell can be borrowed again once the borrow before is dropped, and
+/// remembers the caller that borrowed it last.
+pub fn up_cell_test() {
+    let cell = unsafe { UPIntrFreeCell::new(0usize) };
+    *cell.exclusive_access() += 1;
+    let line = line!() - 1;
+    let at = cell.borrowed_at.get().unwrap();
+    assert_eq!((at.file(), at.line()), (file!(), line));
+    assert_eq!(cell.exclusive_session(|value| *value), 1);
+    assert_eq!(cell.borrowed_at.get().unwrap().line(), line!() - 1);
+    println!("up_cell_test passed!");
+}
@@ -0,0 +1,48 @@
+use super::util::{check_user_ptr, copy_from_user, EINVAL};
+use super::{syscall, SYSCALL_CLONE, SYSCALL_EXEC, SYSCALL_SUBMIT_BATCH};
+
+/// at most this many ops are taken by one `sys_submit_batch`
+const MAX_BATCH_OPS: usize = 64;
+
+/// stop the batch after this op if it returns a negative value
+const OP_STOP_ON_ERROR: usize = 1;
+
+/// One syscall of a batch, `result` is filled in by the kernel.
+/// Only syscalls taking up to 3 arguments can be batched.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SyscallOp {
+    pub nr: usize,
+    pub args: [usize; 3],
+    pub flags: usize,
+    pub result: isize,
+}
+
+/// run the ops in order within a single trap and return how many were run
+/// n is 0 or larger than MAX_BATCH_OPS, return -EINVAL
+/// ops is not readable, or the result of an op is not writable, return -EFAULT
+/// clone/exec/nested batches would resume user code on their own, so these
+/// ops fail with -EINVAL
+pub fn sys_submit_batch(ops: *mut SyscallOp, n: usize) -> isize {
+    if n == 0 || n > MAX_BATCH_OPS {
+        return -EINVAL;
+    }
+    for i in 0..n {
+        let op_ptr = ops.wrapping_add(i);
+        let op = unwrap_or_errno!(copy_from_user(op_ptr));
+        let result = if op.flags & !OP_STOP_ON_ERROR != 0
+            || matches!(op.nr, SYSCALL_CLONE | SYSCALL_EXEC | SYSCALL_SUBMIT_BATCH)
+        {
+            -EINVAL
+        } else {
+            let [a0, a1, a2] = op.args;
+            syscall(op.nr, [a0, a1, a2, 0])
+        };
+        let result_ptr = unsafe { core::ptr::addr_of_mut!((*op_ptr).result) };
+        *unwrap_or_errno!(check_user_ptr(result_ptr)) = result;
+        if result < 0 && op.flags & OP_STOP_ON_ERROR != 0 {
+            return (i + 1) as isize;
+        }
+    }
+    n as isize
+}
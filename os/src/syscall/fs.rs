@@ -1,99 +1,394 @@
-use crate::fs::{make_pipe, open_file, OpenFlags};
-use crate::mm::{translated_byte_buffer, translated_refmut, translated_str, UserBuffer};
-use crate::task::{current_process, current_user_token};
+use super::util::{
+    check_fd, check_user_ptr, check_user_slice, check_user_str, copy_from_user, copy_to_user,
+    EAGAIN, EBADF, EINVAL, EMFILE, EPERM, ESPIPE,
+};
+use crate::config::PAGE_SIZE;
+use crate::fs::{
+    absolute_path, create_dir, find_inode, make_pipe, open_file, open_proc, proc_pid, unlink, File,
+    ITimerSpec, OpenFlags, SignalFd, Stat, TimerFd, CLOCK_MONOTONIC, PROC_PREFIX, SFD_NONBLOCK,
+    TFD_NONBLOCK,
+};
+use crate::mm::UserBuffer;
+use crate::task::{current_may_read_memory, current_process, pid2process, SignalFlags};
+use alloc::string::String;
 use alloc::sync::Arc;
+use alloc::vec;
+use easy_fs::BLOCK_SZ;
 
+/// write buf to the file fd, return the number of bytes written
+/// fd is not opened or not writable, return -EBADF
+/// buf is not mapped readable, return -EFAULT
 pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
-    let token = current_user_token();
-    let process = current_process();
-    let inner = process.inner_exclusive_access();
-    if fd >= inner.fd_table.len() {
-        return -1;
-    }
-    if let Some(file) = &inner.fd_table[fd] {
-        if !file.writable() {
-            return -1;
-        }
-        let file = file.clone();
-        // release current task TCB manually to avoid multi-borrow
-        drop(inner);
-        file.write(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
-    } else {
-        -1
+    let file = unwrap_or_errno!(check_fd(fd));
+    if !file.writable() {
+        return -EBADF;
     }
+    let buf = unwrap_or_errno!(check_user_slice(buf as usize, len, false));
+    file.write(buf) as isize
 }
 
+/// read into buf from the file fd, return the number of bytes read, which is
+/// 0 at the end of the file. Reading stdin blocks until a byte is typed, or a
+/// whole line if buf holds more than one byte.
+/// fd is not opened or not readable, return -EBADF
+/// buf is not mapped writable, return -EFAULT
+/// len is shorter than the records of the file, return -EINVAL
+/// nothing can be read now and the file is nonblocking, return -EAGAIN
 pub fn sys_read(fd: usize, buf: *const u8, len: usize) -> isize {
-    let token = current_user_token();
+    let file = unwrap_or_errno!(check_fd(fd));
+    if !file.readable() {
+        return -EBADF;
+    }
+    let buf = unwrap_or_errno!(check_user_slice(buf as usize, len, true));
+    if len < file.min_read_len() {
+        return -EINVAL;
+    }
+    if file.would_block() {
+        return -EAGAIN;
+    }
+    file.read(buf) as isize
+}
+
+/// The absolute path of `path` taken from the working directory.
+fn path_of_current(path: &str) -> String {
     let process = current_process();
     let inner = process.inner_exclusive_access();
-    if fd >= inner.fd_table.len() {
-        return -1;
-    }
-    if let Some(file) = &inner.fd_table[fd] {
-        let file = file.clone();
-        if !file.readable() {
-            return -1;
+    absolute_path(&inner.cwd, path)
+}
+
+/// open the file at path, relative to the working directory, return -1 if
+/// it does not exist and is not created, or is a directory opened writable.
+/// Files under /proc are generated by the kernel and only opened read-only.
+/// flags has unknown bits, return -EINVAL
+/// the fd would reach RLIMIT_NOFILE, return -EMFILE
+/// a file under /proc/<pid> of a process whose memory the caller may not
+/// read (see sys_process_vm_readv), return -EPERM
+pub fn sys_open(path: *const u8, flags: u32) -> isize {
+    let path = unwrap_or_errno!(check_user_str(path));
+    let flags = match OpenFlags::from_bits(flags) {
+        Some(flags) => flags,
+        None => return -EINVAL,
+    };
+    let path = path_of_current(&path);
+    let file: Option<Arc<dyn File + Send + Sync>> = if path.starts_with(PROC_PREFIX) {
+        if let Some(process) = proc_pid(&path).and_then(pid2process) {
+            if !current_may_read_memory(&process) {
+                return -EPERM;
+            }
         }
-        // release current task TCB manually to avoid multi-borrow
-        drop(inner);
-        file.read(UserBuffer::new(translated_byte_buffer(token, buf, len))) as isize
+        open_proc(&path, flags)
+    } else {
+        open_file(&path, flags).map(|inode| inode as _)
+    };
+    let process = current_process();
+    if let Some(file) = file {
+        let inner = process.inner_exclusive_access();
+        let fd = unwrap_or_errno!(inner.alloc_fd().ok_or(-EMFILE));
+        inner.fd_table()[fd] = Some(file);
+        fd as isize
     } else {
         -1
     }
 }
 
-pub fn sys_open(path: *const u8, flags: u32) -> isize {
-    let process = current_process();
-    let token = current_user_token();
-    let path = translated_str(token, path);
-    if let Some(inode) = open_file(path.as_str(), OpenFlags::from_bits(flags).unwrap()) {
-        let mut inner = process.inner_exclusive_access();
-        let fd = inner.alloc_fd();
-        inner.fd_table[fd] = Some(inode);
-        fd as isize
+/// create a directory at path, relative to the working directory, return -1
+/// if something is there already or its parent directory does not exist
+pub fn sys_mkdir(path: *const u8) -> isize {
+    let path = unwrap_or_errno!(check_user_str(path));
+    match create_dir(&path_of_current(&path)) {
+        Some(_) => 0,
+        None => -1,
+    }
+}
+
+/// remove the file or empty directory at path, relative to the working
+/// directory. A file still open is freed once the last fd on it is closed
+/// and can be used until then. Return -1 if nothing is there or it is a
+/// directory that is not empty
+pub fn sys_unlink(path: *const u8) -> isize {
+    let path = unwrap_or_errno!(check_user_str(path));
+    if unlink(&path_of_current(&path)) {
+        0
     } else {
         -1
     }
 }
 
-pub fn sys_close(fd: usize) -> isize {
-    let process = current_process();
-    let mut inner = process.inner_exclusive_access();
-    if fd >= inner.fd_table.len() {
-        return -1;
+/// change the working directory to path, relative to the current one,
+/// return -1 if it is not a directory
+pub fn sys_chdir(path: *const u8) -> isize {
+    let path = unwrap_or_errno!(check_user_str(path));
+    let path = path_of_current(&path);
+    match find_inode(&path) {
+        Some(inode) if inode.is_dir() => {
+            current_process().inner_exclusive_access().cwd = path;
+            0
+        }
+        _ => -1,
     }
-    if inner.fd_table[fd].is_none() {
+}
+
+/// copy the absolute path of the working directory, ending with `\0`, to
+/// buf and return its length without the `\0`, or -1 if buf is too short
+/// buf is not mapped writable, return -EFAULT
+pub fn sys_getcwd(buf: *mut u8, len: usize) -> isize {
+    let cwd = current_process().inner_exclusive_access().cwd.clone();
+    if len < cwd.len() + 1 {
         return -1;
     }
-    inner.fd_table[fd].take();
+    let buffer = unwrap_or_errno!(check_user_slice(buf as usize, cwd.len() + 1, true));
+    for (dst, src) in buffer.into_iter().zip(cwd.bytes().chain(Some(0))) {
+        unsafe {
+            *dst = src;
+        }
+    }
+    cwd.len() as isize
+}
+
+/// move the offset of the file fd by offset bytes from the start, the
+/// current offset or the end of the file, as whence is SEEK_SET, SEEK_CUR or
+/// SEEK_END, and return the new offset
+/// fd is not opened, return -EBADF
+/// fd has no offset, like a pipe, return -ESPIPE
+/// whence is unknown or the new offset would be negative, return -EINVAL
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    let file = unwrap_or_errno!(check_fd(fd));
+    let inode = match file.as_inode() {
+        Some(inode) => inode,
+        None => return -ESPIPE,
+    };
+    match inode.seek(offset, whence) {
+        Some(offset) => offset as isize,
+        None => -EINVAL,
+    }
+}
+
+/// copy up to count bytes from the file in_fd to the file out_fd within the
+/// kernel, a block of the block cache at a time, and return the number of
+/// bytes copied, fewer at the end of in_fd. Without offset they are read
+/// from the offset of in_fd, which moves past them. Otherwise they are read
+/// from *offset, which moves past them instead, and in_fd must be a file
+/// with an offset, like a regular file.
+/// in_fd is not readable or out_fd not writable, return -EBADF
+/// in_fd is read in whole records, like a socket, return -EINVAL
+/// offset is not mapped writable, return -EFAULT
+/// offset is given but in_fd has no offset, like a pipe, return -1
+pub fn sys_sendfile(out_fd: usize, in_fd: usize, offset: *mut usize, count: usize) -> isize {
+    let in_file = unwrap_or_errno!(check_fd(in_fd));
+    let out_file = unwrap_or_errno!(check_fd(out_fd));
+    if !in_file.readable() || !out_file.writable() {
+        return -EBADF;
+    }
+    if in_file.min_read_len() > 0 {
+        return -EINVAL;
+    }
+    let mut position = if offset.is_null() {
+        None
+    } else {
+        unwrap_or_errno!(check_user_ptr(offset));
+        if in_file.as_inode().is_none() {
+            return -1;
+        }
+        Some(unwrap_or_errno!(copy_from_user(offset)))
+    };
+    let mut buffer = vec![0u8; BLOCK_SZ];
+    let mut sent = 0;
+    while sent < count {
+        // a chunk ends at a block boundary of in_fd if it has an offset, so
+        // each is read from a single block in the cache
+        let block_offset = match (position, in_file.as_inode()) {
+            (Some(position), _) => position,
+            (None, Some(inode)) => inode.offset(),
+            (None, None) => 0,
+        } % BLOCK_SZ;
+        let len = (count - sent).min(BLOCK_SZ - block_offset);
+        // the files take user buffers, this one outlives the calls
+        let chunk = unsafe { core::slice::from_raw_parts_mut(buffer.as_mut_ptr(), len) };
+        let read = match position {
+            Some(position) => in_file.as_inode().unwrap().read_at(position, chunk),
+            None => in_file.read(UserBuffer::new(vec![chunk])),
+        };
+        if read == 0 {
+            break;
+        }
+        let chunk = unsafe { core::slice::from_raw_parts_mut(buffer.as_mut_ptr(), read) };
+        let written = out_file.write(UserBuffer::new(vec![chunk]));
+        sent += written;
+        if let Some(position) = position.as_mut() {
+            *position += written;
+        }
+        if written < read {
+            break;
+        }
+    }
+    if let Some(position) = position {
+        unwrap_or_errno!(copy_to_user(offset, &position));
+    }
+    sent as isize
+}
+
+/// copy the metadata of the file fd to st
+/// fd is not opened, return -EBADF
+/// st is not mapped writable, return -EFAULT
+pub fn sys_fstat(fd: usize, st: *mut Stat) -> isize {
+    let file = unwrap_or_errno!(check_fd(fd));
+    unwrap_or_errno!(copy_to_user(st, &file.stat()));
     0
 }
 
+pub fn sys_close(fd: usize) -> isize {
+    unwrap_or_errno!(check_fd(fd));
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let file = inner.fd_table()[fd].take();
+    // closing the last file on an unlinked inode waits for the disk
+    drop(inner);
+    drop(file);
+    0
+}
+
+/// the fds would reach RLIMIT_NOFILE, return -EMFILE
 pub fn sys_pipe(pipe: *mut usize) -> isize {
+    let read_end = unwrap_or_errno!(check_user_ptr(pipe));
+    let write_end = unwrap_or_errno!(check_user_ptr(pipe.wrapping_add(1)));
     let process = current_process();
-    let token = current_user_token();
-    let mut inner = process.inner_exclusive_access();
+    let inner = process.inner_exclusive_access();
     let (pipe_read, pipe_write) = make_pipe();
-    let read_fd = inner.alloc_fd();
-    inner.fd_table[read_fd] = Some(pipe_read);
-    let write_fd = inner.alloc_fd();
-    inner.fd_table[write_fd] = Some(pipe_write);
-    *translated_refmut(token, pipe) = read_fd;
-    *translated_refmut(token, unsafe { pipe.add(1) }) = write_fd;
+    let read_fd = unwrap_or_errno!(inner.alloc_fd().ok_or(-EMFILE));
+    inner.fd_table()[read_fd] = Some(pipe_read);
+    let write_fd = match inner.alloc_fd() {
+        Some(fd) => fd,
+        None => {
+            inner.fd_table()[read_fd] = None;
+            return -EMFILE;
+        }
+    };
+    inner.fd_table()[write_fd] = Some(pipe_write);
+    *read_end = read_fd;
+    *write_end = write_fd;
     0
 }
 
+/// the fd would reach RLIMIT_NOFILE, return -EMFILE
 pub fn sys_dup(fd: usize) -> isize {
+    let file = unwrap_or_errno!(check_fd(fd));
     let process = current_process();
-    let mut inner = process.inner_exclusive_access();
-    if fd >= inner.fd_table.len() {
-        return -1;
+    let inner = process.inner_exclusive_access();
+    let new_fd = unwrap_or_errno!(inner.alloc_fd().ok_or(-EMFILE));
+    inner.fd_table()[new_fd] = Some(file);
+    new_fd as isize
+}
+
+/// only CLOCK_MONOTONIC is supported, flags may only have TFD_NONBLOCK,
+/// otherwise return -EINVAL
+/// the fd would reach RLIMIT_NOFILE, return -EMFILE
+pub fn sys_timerfd_create(clock_id: usize, flags: usize) -> isize {
+    if clock_id != CLOCK_MONOTONIC || flags & !TFD_NONBLOCK != 0 {
+        return -EINVAL;
     }
-    if inner.fd_table[fd].is_none() {
-        return -1;
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let fd = unwrap_or_errno!(inner.alloc_fd().ok_or(-EMFILE));
+    inner.fd_table()[fd] = Some(Arc::new(TimerFd::new(flags & TFD_NONBLOCK != 0)));
+    fd as isize
+}
+
+/// arm the timerfd with `new` (relative to now), and store the previous
+/// setting to `old` unless it is null
+/// fd is not a timerfd, return -EINVAL
+pub fn sys_timerfd_settime(fd: usize, new: *const ITimerSpec, old: *mut ITimerSpec) -> isize {
+    let file = unwrap_or_errno!(check_fd(fd));
+    let timerfd = match file.as_timerfd() {
+        Some(timerfd) => timerfd,
+        None => return -EINVAL,
+    };
+    let new = unwrap_or_errno!(copy_from_user(new));
+    let old_ref = if old.is_null() {
+        None
+    } else {
+        Some(unwrap_or_errno!(check_user_ptr(old)))
+    };
+    let old = timerfd.settime(new);
+    if let Some(old_ref) = old_ref {
+        *old_ref = old;
     }
-    let new_fd = inner.alloc_fd();
-    inner.fd_table[new_fd] = Some(Arc::clone(inner.fd_table[fd].as_ref().unwrap()));
-    new_fd as isize
+    0
+}
+
+/// create a signalfd reading the signals in `mask` if fd is usize::MAX,
+/// otherwise replace the mask of the signalfd fd, return the signalfd,
+/// SIGKILL, SIGSTOP and the fault signals SIGILL, SIGBUS and SIGSEGV in the
+/// mask are ignored
+/// fd is not a signalfd, or mask or flags is unknown, return -EINVAL
+/// a new signalfd would reach RLIMIT_NOFILE, return -EMFILE
+pub fn sys_signalfd(fd: usize, mask: u32, flags: usize) -> isize {
+    let mask = match SignalFlags::from_bits(mask) {
+        Some(mask) if flags & !SFD_NONBLOCK == 0 => {
+            mask - SignalFlags::unblockable() - SignalFlags::synchronous()
+        }
+        _ => return -EINVAL,
+    };
+    if fd != usize::MAX {
+        let file = unwrap_or_errno!(check_fd(fd));
+        return match file.as_signalfd() {
+            Some(signalfd) => {
+                signalfd.set_mask(mask);
+                fd as isize
+            }
+            None => -EINVAL,
+        };
+    }
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let fd = unwrap_or_errno!(inner.alloc_fd().ok_or(-EMFILE));
+    inner.fd_table()[fd] = Some(Arc::new(SignalFd::new(mask, flags & SFD_NONBLOCK != 0)));
+    fd as isize
+}
+
+/// set `vec[i]` to 1 if the i-th page of `[offset, offset + len)` in the
+/// file is in the block cache, otherwise 0, pages past the end of file are 0
+/// fd is not a regular file, or offset is not page aligned, return -EINVAL
+pub fn sys_fincore(fd: usize, offset: usize, len: usize, vec: *mut u8) -> isize {
+    let file = unwrap_or_errno!(check_fd(fd));
+    let inode = match file.as_inode() {
+        Some(inode) => inode,
+        None => return -EINVAL,
+    };
+    if offset % PAGE_SIZE != 0 || offset.checked_add(len).is_none() {
+        return -EINVAL;
+    }
+    let pages = len / PAGE_SIZE + usize::from(len % PAGE_SIZE != 0);
+    let vec = unwrap_or_errno!(check_user_slice(vec as usize, pages, true));
+    for (i, resident) in vec.into_iter().enumerate() {
+        unsafe {
+            *resident = inode.is_resident(offset + i * PAGE_SIZE, PAGE_SIZE) as u8;
+        }
+    }
+    0
+}
+
+/// write records of the entries of the directory fd into buf, going on from
+/// where the last call stopped, return the number of bytes written, 0 once
+/// every entry is read. A record is the inode number and the length of the
+/// name as u32s, then the name without '\0', padded to 4 bytes
+/// fd is not a directory, return -1
+/// buf is too short for the next record, return -EINVAL
+/// buf is not mapped writable, return -EFAULT
+pub fn sys_getdents(fd: usize, buf: *mut u8, len: usize) -> isize {
+    let file = unwrap_or_errno!(check_fd(fd));
+    let inode = match file.as_inode() {
+        Some(inode) if inode.is_dir() => inode,
+        _ => return -1,
+    };
+    let user_buf = unwrap_or_errno!(check_user_slice(buf as usize, len, true));
+    let records = match inode.read_dirents(len) {
+        Some(records) => records,
+        None => return -EINVAL,
+    };
+    for (dst, src) in user_buf.into_iter().zip(records.iter()) {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    records.len() as isize
 }
@@ -1,18 +1,61 @@
+const SYSCALL_GETCWD: usize = 17;
 const SYSCALL_DUP: usize = 24;
+const SYSCALL_MKDIR: usize = 34;
+const SYSCALL_UNLINK: usize = 35;
+const SYSCALL_CHDIR: usize = 49;
 const SYSCALL_OPEN: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
 const SYSCALL_PIPE: usize = 59;
+const SYSCALL_GETDENTS: usize = 61;
+const SYSCALL_LSEEK: usize = 62;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_SENDFILE: usize = 71;
+const SYSCALL_SIGNALFD: usize = 74;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_TIMERFD_CREATE: usize = 85;
+const SYSCALL_TIMERFD_SETTIME: usize = 86;
+const SYSCALL_PERSONALITY: usize = 92;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_SET_TID_ADDRESS: usize = 96;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_SET_ROBUST_LIST: usize = 99;
 const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_KEXEC: usize = 104;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_KILL: usize = 129;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_SHUTDOWN: usize = 142;
+const SYSCALL_GETPGID: usize = 155;
+const SYSCALL_GETSID: usize = 156;
+const SYSCALL_SETSID: usize = 157;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
+const SYSCALL_PRCTL: usize = 167;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
-const SYSCALL_FORK: usize = 220;
+const SYSCALL_GETPPID: usize = 173;
+const SYSCALL_SHM_GET: usize = 194;
+const SYSCALL_SHM_ATTACH: usize = 196;
+const SYSCALL_SHM_DETACH: usize = 197;
+const SYSCALL_SOCKET: usize = 198;
+const SYSCALL_BIND: usize = 200;
+const SYSCALL_SENDMSG: usize = 211;
+const SYSCALL_RECVMSG: usize = 212;
+const SYSCALL_SBRK: usize = 214;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_CLONE: usize = 220;
 const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
+const SYSCALL_MINCORE: usize = 232;
+const SYSCALL_SET_MEMPOLICY: usize = 237;
 const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_PROCESS_VM_READV: usize = 270;
+const SYSCALL_SPAWN: usize = 400;
+const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_SUBMIT_BATCH: usize = 426;
+const SYSCALL_FINCORE: usize = 460;
 const SYSCALL_THREAD_CREATE: usize = 1000;
 const SYSCALL_GETTID: usize = 1001;
 const SYSCALL_WAITTID: usize = 1002;
@@ -25,42 +68,113 @@ const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
 const SYSCALL_CONDVAR_CREATE: usize = 1030;
 const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
 const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_YIELD_UNTIL: usize = 1040;
 const SYSCALL_FRAMEBUFFER: usize = 2000;
 const SYSCALL_FRAMEBUFFER_FLUSH: usize = 2001;
 const SYSCALL_EVENT_GET: usize = 3000;
 const SYSCALL_KEY_PRESSED: usize = 3001;
+const SYSCALL_VALIDATE: usize = 4000;
+const SYSCALL_FAULT_INJECT: usize = 4001;
+const SYSCALL_SCHED_LATENCY_HIST: usize = 4002;
+const SYSCALL_MEMINFO: usize = 4003;
+const SYSCALL_TLB_STATS: usize = 4004;
+const SYSCALL_GET_CYCLES: usize = 4005;
 
+#[macro_use]
+mod util;
+
+mod batch;
 mod fs;
 mod gui;
 mod input;
+mod net;
 mod process;
 mod sync;
 mod thread;
 
+use batch::*;
 use fs::*;
 use gui::*;
 use input::*;
+use net::*;
+pub use process::validate_test;
 use process::*;
 use sync::*;
 use thread::*;
+pub use util::copy_from_user_test;
+
+use crate::fs::{ITimerSpec, Stat};
+use crate::mm::{HeapStats, TlbStats};
+use crate::task::{record_syscall_of_current, RLimit, TaskInfo};
+use crate::timer::{get_time_ms, TimeVal};
 
-pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+pub fn syscall(syscall_id: usize, args: [usize; 4]) -> isize {
+    record_syscall_of_current(syscall_id, get_time_ms());
     match syscall_id {
+        SYSCALL_GETCWD => sys_getcwd(args[0] as *mut u8, args[1]),
         SYSCALL_DUP => sys_dup(args[0]),
+        SYSCALL_MKDIR => sys_mkdir(args[0] as *const u8),
+        SYSCALL_UNLINK => sys_unlink(args[0] as *const u8),
+        SYSCALL_CHDIR => sys_chdir(args[0] as *const u8),
         SYSCALL_OPEN => sys_open(args[0] as *const u8, args[1] as u32),
         SYSCALL_CLOSE => sys_close(args[0]),
         SYSCALL_PIPE => sys_pipe(args[0] as *mut usize),
+        SYSCALL_GETDENTS => sys_getdents(args[0], args[1] as *mut u8, args[2]),
+        SYSCALL_LSEEK => sys_lseek(args[0], args[1] as isize, args[2]),
         SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
         SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_SENDFILE => sys_sendfile(args[0], args[1], args[2] as *mut usize, args[3]),
+        SYSCALL_SIGNALFD => sys_signalfd(args[0], args[1] as u32, args[2]),
+        SYSCALL_FSTAT => sys_fstat(args[0], args[1] as *mut Stat),
+        SYSCALL_TIMERFD_CREATE => sys_timerfd_create(args[0], args[1]),
+        SYSCALL_TIMERFD_SETTIME => sys_timerfd_settime(
+            args[0],
+            args[1] as *const ITimerSpec,
+            args[2] as *mut ITimerSpec,
+        ),
+        SYSCALL_PERSONALITY => sys_personality(args[0] as u32),
         SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_SET_TID_ADDRESS => sys_set_tid_address(args[0]),
+        SYSCALL_FUTEX => sys_futex(args[0] as *mut u32, args[1] as u32, args[2] as u32),
+        SYSCALL_SET_ROBUST_LIST => sys_set_robust_list(args[0], args[1]),
         SYSCALL_SLEEP => sys_sleep(args[0]),
+        SYSCALL_KEXEC => sys_kexec(args[0] as *const u8, args[1], args[2]),
         SYSCALL_YIELD => sys_yield(),
         SYSCALL_KILL => sys_kill(args[0], args[1] as u32),
-        SYSCALL_GET_TIME => sys_get_time(),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_SHUTDOWN => sys_shutdown(args[0]),
+        SYSCALL_GETPGID => sys_getpgid(args[0]),
+        SYSCALL_GETSID => sys_getsid(args[0]),
+        SYSCALL_SETSID => sys_setsid(),
+        SYSCALL_GETRLIMIT => sys_getrlimit(args[0], args[1] as *mut RLimit),
+        SYSCALL_SETRLIMIT => sys_setrlimit(args[0], args[1]),
+        SYSCALL_PRCTL => sys_prctl(args[0], args[1]),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
         SYSCALL_GETPID => sys_getpid(),
-        SYSCALL_FORK => sys_fork(),
+        SYSCALL_GETPPID => sys_getppid(),
+        SYSCALL_SHM_GET => sys_shm_get(args[0], args[1]),
+        SYSCALL_SHM_ATTACH => sys_shm_attach(args[0], args[1]),
+        SYSCALL_SHM_DETACH => sys_shm_detach(args[0]),
+        SYSCALL_SOCKET => sys_socket(args[0], args[1], args[2]),
+        SYSCALL_BIND => sys_bind(args[0], args[1] as *const u8),
+        SYSCALL_SENDMSG => sys_sendmsg(args[0], args[1] as *const MsgHdr),
+        SYSCALL_RECVMSG => sys_recvmsg(args[0], args[1] as *const MsgHdr),
+        SYSCALL_SBRK => sys_sbrk(args[0] as isize),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_CLONE => sys_clone(args[0], args[1], args[2], args[3]),
         SYSCALL_EXEC => sys_exec(args[0] as *const u8, args[1] as *const usize),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2], args[3]),
+        SYSCALL_MPROTECT => sys_mprotect(args[0], args[1], args[2]),
+        SYSCALL_MINCORE => sys_mincore(args[0], args[1], args[2] as *mut u8),
+        SYSCALL_SET_MEMPOLICY => sys_set_mempolicy(args[0]),
         SYSCALL_WAITPID => sys_waitpid(args[0] as isize, args[1] as *mut i32),
+        SYSCALL_PROCESS_VM_READV => {
+            sys_process_vm_readv(args[0], args[1] as *mut u8, args[2], args[3])
+        }
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
+        SYSCALL_SUBMIT_BATCH => sys_submit_batch(args[0] as *mut SyscallOp, args[1]),
+        SYSCALL_FINCORE => sys_fincore(args[0], args[1], args[2], args[3] as *mut u8),
         SYSCALL_THREAD_CREATE => sys_thread_create(args[0], args[1]),
         SYSCALL_GETTID => sys_gettid(),
         SYSCALL_WAITTID => sys_waittid(args[0]) as isize,
@@ -73,10 +187,17 @@ pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
         SYSCALL_CONDVAR_CREATE => sys_condvar_create(args[0]),
         SYSCALL_CONDVAR_SIGNAL => sys_condvar_signal(args[0]),
         SYSCALL_CONDVAR_WAIT => sys_condvar_wait(args[0], args[1]),
+        SYSCALL_YIELD_UNTIL => sys_yield_until(args[0] as *const u32, args[1] as u32, args[2]),
         SYSCALL_FRAMEBUFFER => sys_framebuffer(),
         SYSCALL_FRAMEBUFFER_FLUSH => sys_framebuffer_flush(),
         SYSCALL_EVENT_GET => sys_event_get(),
         SYSCALL_KEY_PRESSED => sys_key_pressed(),
+        SYSCALL_VALIDATE => sys_validate(),
+        SYSCALL_FAULT_INJECT => sys_fault_inject(args[0], args[1], args[2]),
+        SYSCALL_SCHED_LATENCY_HIST => sys_sched_latency_hist(args[0] as *mut u64, args[1]),
+        SYSCALL_MEMINFO => sys_meminfo(args[0] as *mut HeapStats),
+        SYSCALL_TLB_STATS => sys_tlb_stats(args[0] as *mut TlbStats),
+        SYSCALL_GET_CYCLES => sys_get_cycles(),
         _ => panic!("Unsupported syscall_id: {}", syscall_id),
     }
 }
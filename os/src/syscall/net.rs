@@ -0,0 +1,83 @@
+use super::util::{
+    check_fd, check_user_slice, check_user_str, copy_from_user, ECONNREFUSED, EMFILE,
+};
+use crate::fs::{File, Socket, AF_UNIX, MAX_DATAGRAM_SIZE, SOCK_DGRAM};
+use crate::task::current_process;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+/// Message header of `sys_sendmsg`/`sys_recvmsg`.
+/// `name` is the nul-terminated destination name, unused by recvmsg.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct MsgHdr {
+    pub name: usize,
+    pub buf: usize,
+    pub len: usize,
+}
+
+/// only local datagram sockets are supported, otherwise return -1
+/// the fd would reach RLIMIT_NOFILE, return -EMFILE
+pub fn sys_socket(domain: usize, socket_type: usize, protocol: usize) -> isize {
+    if domain != AF_UNIX || socket_type != SOCK_DGRAM || protocol != 0 {
+        return -1;
+    }
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let fd = unwrap_or_errno!(inner.alloc_fd().ok_or(-EMFILE));
+    inner.fd_table()[fd] = Some(Arc::new(Socket::new()));
+    fd as isize
+}
+
+/// fd is not opened, return -EBADF
+/// fd is not a socket, return -1
+fn check_socket(fd: usize) -> Result<Arc<dyn File + Send + Sync>, isize> {
+    let file = check_fd(fd)?;
+    if file.as_socket().is_some() {
+        Ok(file)
+    } else {
+        Err(-1)
+    }
+}
+
+/// the socket is bound or the name is taken, return -1
+pub fn sys_bind(fd: usize, name: *const u8) -> isize {
+    let file = unwrap_or_errno!(check_socket(fd));
+    let name = unwrap_or_errno!(check_user_str(name));
+    if file.as_socket().unwrap().bind(name) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// send one datagram to the socket bound to `msg.name`
+/// the datagram is too long, return -1
+/// nobody is bound to the name, return -ECONNREFUSED
+pub fn sys_sendmsg(fd: usize, msg: *const MsgHdr) -> isize {
+    unwrap_or_errno!(check_socket(fd));
+    let msg = unwrap_or_errno!(copy_from_user(msg));
+    if msg.len > MAX_DATAGRAM_SIZE {
+        return -1;
+    }
+    let name = unwrap_or_errno!(check_user_str(msg.name as *const u8));
+    let buf = unwrap_or_errno!(check_user_slice(msg.buf, msg.len, false));
+    let mut datagram = Vec::with_capacity(msg.len);
+    for buffer in buf.buffers {
+        datagram.extend_from_slice(buffer);
+    }
+    let len = datagram.len();
+    if Socket::send_to(name.as_str(), datagram) {
+        len as isize
+    } else {
+        -ECONNREFUSED
+    }
+}
+
+/// receive exactly one datagram into `msg.buf`, waiting for it if needed
+pub fn sys_recvmsg(fd: usize, msg: *const MsgHdr) -> isize {
+    let file = unwrap_or_errno!(check_socket(fd));
+    let msg = unwrap_or_errno!(copy_from_user(msg));
+    let buf = unwrap_or_errno!(check_user_slice(msg.buf, msg.len, true));
+    file.as_socket().unwrap().recv(buf) as isize
+}
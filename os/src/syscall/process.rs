@@ -1,13 +1,30 @@
-use crate::fs::{open_file, OpenFlags};
-use crate::mm::{translated_ref, translated_refmut, translated_str};
+use super::thread::clone_thread;
+use super::util::{
+    check_nproc, check_user_ptr, check_user_slice, check_user_str, copy_from_user, copy_to_user,
+    E2BIG, EFAULT, EINVAL, ENOMEM, EPERM, ESRCH,
+};
+use crate::config::{
+    KEXEC, KEXEC_LOAD_ADDR, KEXEC_MAX_SIZE, MMAP_END, PAGE_SIZE, USER_HEAP_SIZE, USER_STACK_SIZE,
+};
+use crate::fault_inject::{arm_fault, FaultKind, ENABLED as FAULT_INJECTION_ENABLED};
+use crate::fs::{absolute_path, open_file, sync_all, OpenFlags};
+use crate::kexec::kexec;
+use crate::mm::{
+    frame_alloc, frame_allocator_check, heap_stats, shm_frames, shm_get, tlb_stats, HeapStats,
+    MapPermission, MemorySet, PageTable, TlbStats, VPNRange, VirtAddr, VirtPageNum,
+};
+use crate::sbi::shutdown;
 use crate::task::{
-    current_process, current_task, current_user_token, exit_current_and_run_next, pid2process,
-    suspend_current_and_run_next, SignalFlags,
+    copy_sched_latency, current_is_initproc, current_may_read_memory, current_process,
+    current_task, exit_current_and_run_next, pid2process, pid2process_check, report_frame_leaks,
+    suspend_current_and_run_next, Personality, RLimit, SignalFlags, TaskInfo,
+    SCHED_LATENCY_BUCKETS,
 };
-use crate::timer::get_time_ms;
+use crate::timer::{cycles_to_us, get_cycles, get_time_val, TimeVal};
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::mem::size_of;
 
 pub fn sys_exit(exit_code: i32) -> ! {
     exit_current_and_run_next(exit_code);
@@ -19,47 +36,166 @@ pub fn sys_yield() -> isize {
     0
 }
 
-pub fn sys_get_time() -> isize {
-    get_time_ms() as isize
+/// Set the priority of the current thread, which runs in proportion to it.
+/// Return the priority, or -1 if it is below 2.
+pub fn sys_set_priority(prio: isize) -> isize {
+    if prio < 2 {
+        return -1;
+    }
+    current_task().unwrap().inner_exclusive_access().priority = prio as usize;
+    prio
+}
+
+/// write the time since boot to ts, there are no time zones
+/// ts is not mapped writable, return -EFAULT
+pub fn sys_get_time(ts: *mut TimeVal, _tz: usize) -> isize {
+    unwrap_or_errno!(copy_to_user(ts, &get_time_val()));
+    0
+}
+
+/// return the clock cycles of the hart, for timing at a finer resolution
+/// than sys_get_time. The count is 64-bit and never gets negative in practice
+pub fn sys_get_cycles() -> isize {
+    get_cycles() as isize
 }
 
 pub fn sys_getpid() -> isize {
     current_task().unwrap().process.upgrade().unwrap().getpid() as isize
 }
 
-pub fn sys_fork() -> isize {
+/// An orphan has been moved under initproc by the time its parent exits,
+/// so the parent is always alive. initproc itself has none and gets 0.
+pub fn sys_getppid() -> isize {
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    inner
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(0, |parent| parent.getpid() as isize)
+}
+
+/// Grow or shrink the heap by `size` bytes, return the old program break,
+/// or -1 if the heap would end below its bottom or run into the user stacks.
+/// The pages grown get their frames on first access and are zeroed.
+pub fn sys_sbrk(size: isize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    match inner.change_program_brk(size) {
+        Some(old_brk) => old_brk as isize,
+        None => -1,
+    }
+}
+
+/// sys_clone creates a thread sharing the address space of the caller
+pub const CLONE_VM: usize = 0x100;
+/// sys_clone shares the fd table of the caller with the child
+pub const CLONE_FILES: usize = 0x400;
+
+/// Create a child of the current thread. With CLONE_VM it is a thread of
+/// the process, which shares the address space and the fd table with it.
+/// Otherwise it is a new process with a copy of the address space, which
+/// shares the fd table with CLONE_FILES and gets a copy of it without.
+/// The child starts at entry with arg in a0, or for a process returns 0 from
+/// the syscall as from fork if entry is 0. Its stack pointer is stack if it
+/// is not 0, otherwise a thread starts on a user stack of its own and a
+/// process on the stack of the caller.
+/// Return the tid of the thread or the pid of the process.
+/// unknown flags, CLONE_VM without CLONE_FILES, a thread without entry, or
+/// a process cloned by a process with more than one thread, return -EINVAL
+/// MAX_TASKS tasks are alive, or the process has reached RLIMIT_NPROC,
+/// return -EAGAIN
+pub fn sys_clone(flags: usize, stack: usize, entry: usize, arg: usize) -> isize {
+    if flags & !(CLONE_VM | CLONE_FILES) != 0 || flags == CLONE_VM {
+        return -EINVAL;
+    }
+    let is_thread = flags & CLONE_VM != 0;
+    if is_thread && entry == 0 {
+        return -EINVAL;
+    }
+    unwrap_or_errno!(check_nproc());
+    if is_thread {
+        return clone_thread(entry, arg, stack);
+    }
     let current_process = current_process();
-    let new_process = current_process.fork();
+    if current_process
+        .inner_exclusive_access()
+        .tasks
+        .iter()
+        .flatten()
+        .count()
+        > 1
+    {
+        return -EINVAL;
+    }
+    let new_process = current_process.fork(flags & CLONE_FILES != 0);
     let new_pid = new_process.getpid();
     // modify trap context of new_task, because it returns immediately after switching
     let new_process_inner = new_process.inner_exclusive_access();
     let task = new_process_inner.tasks[0].as_ref().unwrap();
     let trap_cx = task.inner_exclusive_access().get_trap_cx();
-    // we do not have to move to next instruction since we have done it before
-    // for child process, fork returns 0
-    trap_cx.x[10] = 0;
+    if entry != 0 {
+        trap_cx.sepc = entry;
+        trap_cx.x[10] = arg;
+    } else {
+        // we do not have to move to next instruction since we have done it before
+        // for child process, fork returns 0
+        trap_cx.x[10] = 0;
+    }
+    if stack != 0 {
+        trap_cx.set_sp(stack);
+    }
     new_pid as isize
 }
 
+/// A bare name is looked up in the root directory, where the apps are.
+fn app_path(path: String) -> String {
+    if path.contains('/') {
+        absolute_path(&current_process().inner_exclusive_access().cwd, &path)
+    } else {
+        path
+    }
+}
+
+/// the arguments are copied to the top of the new user stack, and leave at
+/// least a page of it to the program
+const ARG_MAX: usize = USER_STACK_SIZE - PAGE_SIZE;
+
+/// replace the image of the calling process with the app at path, passing
+/// it the nul-terminated strings in the null-terminated array args. A path
+/// with a `/` is taken from the working directory, a bare name from the root
+/// return argc, or -1 if there is no such app or it is not a valid ELF
+/// args and their pointers take more than ARG_MAX bytes, return -E2BIG
+/// no frame is left for them, return -ENOMEM with the caller left as it was
 pub fn sys_exec(path: *const u8, mut args: *const usize) -> isize {
-    let token = current_user_token();
-    let path = translated_str(token, path);
+    let path = unwrap_or_errno!(check_user_str(path));
     let mut args_vec: Vec<String> = Vec::new();
+    let mut args_size = size_of::<usize>();
     loop {
-        let arg_str_ptr = *translated_ref(token, args);
+        let arg_str_ptr = unwrap_or_errno!(copy_from_user(args));
         if arg_str_ptr == 0 {
             break;
         }
-        args_vec.push(translated_str(token, arg_str_ptr as *const u8));
+        let arg = unwrap_or_errno!(check_user_str(arg_str_ptr as *const u8));
+        args_size += size_of::<usize>() + arg.len() + 1;
+        if args_size > ARG_MAX {
+            return -E2BIG;
+        }
+        args_vec.push(arg);
         unsafe {
             args = args.add(1);
         }
     }
-    if let Some(app_inode) = open_file(path.as_str(), OpenFlags::RDONLY) {
+    if let Some(app_inode) = open_file(&app_path(path), OpenFlags::RDONLY) {
         let all_data = app_inode.read_all();
+        if !MemorySet::elf_is_valid(all_data.as_slice()) {
+            return -1;
+        }
         let process = current_process();
         let argc = args_vec.len();
-        process.exec(all_data.as_slice(), args_vec);
+        if !process.exec(all_data.as_slice(), args_vec) {
+            return -ENOMEM;
+        }
         // return argc because cx.x[10] will be covered with it later
         argc as isize
     } else {
@@ -67,9 +203,294 @@ pub fn sys_exec(path: *const u8, mut args: *const usize) -> isize {
     }
 }
 
-/// If there is not a child process whose pid is same as given, return -1.
-/// Else if there is a child process but it is still running, return -2.
+/// start the app at path in a new child process, found as by sys_exec. The
+/// child is cloned as by fork and runs the app before it returns to user
+/// mode, so it inherits what fork passes on, a copy of the fd table included
+/// return the pid of the child, or -1 if there is no such app or it is not
+/// a valid ELF
+/// the caller has more than one thread, return -EINVAL
+/// MAX_TASKS tasks are alive, or the caller has reached RLIMIT_NPROC,
+/// return -EAGAIN
+/// no frame is left for the new stack, return -ENOMEM, the child is killed
+/// before it runs and is reaped by waitpid as any other
+pub fn sys_spawn(path: *const u8) -> isize {
+    let path = unwrap_or_errno!(check_user_str(path));
+    let all_data = match open_file(&app_path(path), OpenFlags::RDONLY) {
+        Some(app_inode) => app_inode.read_all(),
+        None => return -1,
+    };
+    if !MemorySet::elf_is_valid(all_data.as_slice()) {
+        return -1;
+    }
+    let pid = sys_clone(0, 0, 0, 0);
+    if pid >= 0 {
+        let child = pid2process(pid as usize).unwrap();
+        if !child.exec(all_data.as_slice(), Vec::new()) {
+            // the child still runs a copy of the caller, which it must not
+            // return to
+            child.exit_before_running();
+            return -ENOMEM;
+        }
+    }
+    pid
+}
+
+/// prot bits of sys_mmap
+const PROT_READ: usize = 1 << 0;
+const PROT_WRITE: usize = 1 << 1;
+const PROT_EXEC: usize = 1 << 2;
+/// the pages stay shared with the children forked later
+const MAP_SHARED: usize = 0x01;
+/// the pages are copied on write in the children forked later, the default
+const MAP_PRIVATE: usize = 0x02;
+/// accepted for compatibility, every mapping is anonymous
+const MAP_ANONYMOUS: usize = 0x20;
+/// every page of the mapping gets its frame right away
+const MAP_POPULATE: usize = 0x8000;
+
+/// User permission of pages mapped with prot, writable pages are readable too.
+fn prot_to_permission(prot: usize) -> MapPermission {
+    let mut permission = MapPermission::U;
+    if prot & (PROT_READ | PROT_WRITE) != 0 {
+        permission |= MapPermission::R;
+    }
+    if prot & PROT_WRITE != 0 {
+        permission |= MapPermission::W;
+    }
+    if prot & PROT_EXEC != 0 {
+        permission |= MapPermission::X;
+    }
+    permission
+}
+
+/// The pages of `[start, start + len)`, len rounded up to whole pages.
+/// len is 0, or the range overflows or ends above MMAP_END, return -EINVAL
+fn mmap_range(start: usize, len: usize) -> Result<(VirtPageNum, VirtPageNum), isize> {
+    if len == 0 {
+        return Err(-EINVAL);
+    }
+    match start.checked_add(len) {
+        // MMAP_END is page aligned, so is the rounded up end
+        Some(end) if end <= MMAP_END => {
+            Ok((VirtAddr::from(start).floor(), VirtAddr::from(end).ceil()))
+        }
+        _ => Err(-EINVAL),
+    }
+}
+
+/// Map `len` bytes of zeroed memory at `start`, readable, writable and
+/// executable as `prot` asks, writable pages are readable too. len is
+/// rounded up to whole pages. The pages get their frames on first access,
+/// or all of them now with MAP_POPULATE in flags.
+/// With MAP_SHARED, the children forked later map the same frames, and the
+/// writes of each are seen by all. The frames are taken now, as for
+/// MAP_POPULATE. Otherwise, or with MAP_PRIVATE, the children get copies.
+/// The mapping may be in the room of the heap above the program break, which
+/// then stops growing below it. Return 0, or -1 if start is not page
+/// aligned, prot is empty or unknown, some page of the range is used
+/// already, by another mapping or by the heap, or the address space would
+/// grow beyond RLIMIT_AS.
+/// len is 0, the range overflows or ends above MMAP_END, or flags has
+/// unknown bits or both MAP_SHARED and MAP_PRIVATE, return -EINVAL
+/// no frame is left to populate the mapping, return -ENOMEM and map nothing
+pub fn sys_mmap(start: usize, len: usize, prot: usize, flags: usize) -> isize {
+    let prot_mask = PROT_READ | PROT_WRITE | PROT_EXEC;
+    if start % PAGE_SIZE != 0 || prot & !prot_mask != 0 || prot & prot_mask == 0 {
+        return -1;
+    }
+    let flags_mask = MAP_SHARED | MAP_PRIVATE | MAP_ANONYMOUS | MAP_POPULATE;
+    if flags & !flags_mask != 0 || flags & (MAP_SHARED | MAP_PRIVATE) == MAP_SHARED | MAP_PRIVATE {
+        return -EINVAL;
+    }
+    let (start_vpn, end_vpn) = unwrap_or_errno!(mmap_range(start, len));
+    let permission = prot_to_permission(prot);
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if !inner.memory_set.is_unused(start_vpn, end_vpn) {
+        return -1;
+    }
+    let pages = end_vpn.0 - start_vpn.0;
+    if inner.memory_set.mapped_size() + pages * PAGE_SIZE > inner.rlimits.address_space {
+        return -1;
+    }
+    let (start_va, end_va) = (start_vpn.into(), end_vpn.into());
+    if flags & MAP_SHARED != 0 {
+        inner
+            .memory_set
+            .insert_shared_area(start_va, end_va, permission);
+    } else {
+        inner
+            .memory_set
+            .insert_lazy_area(start_va, end_va, permission);
+    }
+    // a shared page faulted in after fork would be private to one space
+    if flags & (MAP_POPULATE | MAP_SHARED) != 0
+        && !inner.memory_set.populate_area(start_vpn, end_vpn)
+    {
+        return -ENOMEM;
+    }
+    0
+}
+
+/// set `vec[i]` to 1 if the i-th page of `[start, start + len)` has its
+/// frame, otherwise 0, len rounded up to whole pages
+/// start is not page aligned, or the range is not valid for sys_mmap,
+/// return -EINVAL
+/// some page of the range is not mapped, return -ENOMEM
+/// vec is not mapped writable, return -EFAULT
+pub fn sys_mincore(start: usize, len: usize, vec: *mut u8) -> isize {
+    if start % PAGE_SIZE != 0 {
+        return -EINVAL;
+    }
+    let (start_vpn, end_vpn) = unwrap_or_errno!(mmap_range(start, len));
+    let vec = unwrap_or_errno!(check_user_slice(
+        vec as usize,
+        end_vpn.0 - start_vpn.0,
+        true
+    ));
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let mut resident = Vec::new();
+    for vpn in VPNRange::new(start_vpn, end_vpn) {
+        match inner.memory_set.is_resident(vpn) {
+            Some(is_resident) => resident.push(is_resident as u8),
+            None => return -ENOMEM,
+        }
+    }
+    for (byte, value) in vec.into_iter().zip(resident) {
+        unsafe {
+            *byte = value;
+        }
+    }
+    0
+}
+
+/// Change the protection of the pages in `[start, start + len)` to `prot`,
+/// as sys_mmap takes it. The range may cover part of a mapping or several
+/// mappings, but every page must be in one, and not in the heap.
+/// Later accesses the new protection denies are fatal page faults. Return 0,
+/// or -1 if start is not page aligned, prot is empty or unknown, or some page
+/// of the range is not mapped.
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    let prot_mask = PROT_READ | PROT_WRITE | PROT_EXEC;
+    if start % PAGE_SIZE != 0 || len == 0 || prot & !prot_mask != 0 || prot & prot_mask == 0 {
+        return -1;
+    }
+    let end = match start.checked_add(len) {
+        Some(end) if end <= MMAP_END => end,
+        _ => return -1,
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    // the heap is a single area, moved by its start
+    let heap_end: usize = VirtAddr::from(inner.program_brk).ceil().into();
+    if end > inner.heap_bottom && start < heap_end {
+        return -1;
+    }
+    let (start_va, end_va) = (VirtAddr::from(start), VirtAddr::from(end));
+    if inner
+        .memory_set
+        .protect(start_va.floor(), end_va.ceil(), prot_to_permission(prot))
+    {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Unmap `[start, start + len)` and free its frames, len rounded up to
+/// whole pages as sys_mmap does. The range must be exactly one mapping made
+/// by sys_mmap, or another user area other than the heap, so nothing is
+/// unmapped on failure. Later accesses to the range are fatal page faults.
+/// Return 0, or -1 if start is not page aligned or there is no such mapping.
+/// len is 0, or the range overflows or ends above MMAP_END, return -EINVAL
+pub fn sys_munmap(start: usize, len: usize) -> isize {
+    if start % PAGE_SIZE != 0 {
+        return -1;
+    }
+    let (start_vpn, end_vpn) = unwrap_or_errno!(mmap_range(start, len));
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if start == inner.heap_bottom {
+        return -1;
+    }
+    if inner.memory_set.remove_user_area(start_vpn, end_vpn) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Get the shared memory segment of `key`, created with `size` bytes if
+/// there is none. Key 0 creates a new segment every time. Return the id of
+/// the segment, or -1 if size is 0 or larger than the segment of key, or no
+/// memory is left.
+pub fn sys_shm_get(key: usize, size: usize) -> isize {
+    match shm_get(key, size) {
+        Some(id) => id as isize,
+        None => -1,
+    }
+}
+
+/// Map shared memory segment `id` at an address of the kernel's choosing,
+/// accessible as `prot` asks, as sys_mmap takes it. Writes are seen by every
+/// process attaching the segment, and by the children forked since. Return
+/// the address, or -1 if prot is empty or unknown, there is no such segment
+/// or no room for it, or the address space would grow beyond RLIMIT_AS.
+pub fn sys_shm_attach(id: usize, prot: usize) -> isize {
+    let prot_mask = PROT_READ | PROT_WRITE | PROT_EXEC;
+    if prot & !prot_mask != 0 || prot & prot_mask == 0 {
+        return -1;
+    }
+    let frames = match shm_frames(id) {
+        Some(frames) => frames,
+        None => return -1,
+    };
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.memory_set.mapped_size() + frames.len() * PAGE_SIZE > inner.rlimits.address_space {
+        return -1;
+    }
+    let start = match inner
+        .memory_set
+        .find_free_range(frames.len(), VirtAddr::from(MMAP_END).floor())
+    {
+        Some(start) => start,
+        None => return -1,
+    };
+    let start_va: usize = VirtAddr::from(start).into();
+    let end = start_va + frames.len() * PAGE_SIZE;
+    if end > inner.heap_bottom && start_va < inner.heap_bottom + USER_HEAP_SIZE {
+        return -1;
+    }
+    inner
+        .memory_set
+        .insert_shm_area(start, frames, prot_to_permission(prot), id);
+    start_va as isize
+}
+
+/// Unmap every attachment of shared memory segment `id`, which is freed
+/// once no process has it attached. Return 0, or -1 if it is not attached.
+pub fn sys_shm_detach(id: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    if inner.memory_set.remove_shm_areas(id) {
+        0
+    } else {
+        -1
+    }
+}
+
+/// Reap the child pid, or any child if pid is -1, and return its pid.
+/// Return -1 if there is no such child and -2 if it has not exited yet.
+/// The exit code is not written back if exit_code_ptr is null.
+/// exit_code_ptr is not writable, return -EFAULT
 pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
+    let exit_code_ref = if exit_code_ptr.is_null() {
+        None
+    } else {
+        Some(unwrap_or_errno!(check_user_ptr(exit_code_ptr)))
+    };
     let process = current_process();
     // find a child process
 
@@ -95,7 +516,9 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
         // ++++ temporarily access child PCB exclusively
         let exit_code = child.inner_exclusive_access().exit_code;
         // ++++ release child PCB
-        *translated_refmut(inner.memory_set.token(), exit_code_ptr) = exit_code;
+        if let Some(exit_code_ref) = exit_code_ref {
+            *exit_code_ref = exit_code;
+        }
         found_pid as isize
     } else {
         -2
@@ -103,10 +526,15 @@ pub fn sys_waitpid(pid: isize, exit_code_ptr: *mut i32) -> isize {
     // ---- release current PCB automatically
 }
 
+/// SIGSTOP stops the process until SIGCONT, which is not kept pending
 pub fn sys_kill(pid: usize, signal: u32) -> isize {
     if let Some(process) = pid2process(pid) {
         if let Some(flag) = SignalFlags::from_bits(signal) {
-            process.inner_exclusive_access().signals |= flag;
+            let mut inner = process.inner_exclusive_access();
+            if flag.contains(SignalFlags::SIGCONT) {
+                inner.signals.remove(SignalFlags::SIGSTOP);
+            }
+            inner.signals |= flag - SignalFlags::SIGCONT;
             0
         } else {
             -1
@@ -115,3 +543,323 @@ pub fn sys_kill(pid: usize, signal: u32) -> isize {
         -1
     }
 }
+
+/// set the limit of `resource`, which may only be lowered, a limit below the
+/// current usage only stops further growth
+/// unknown resource, return -EINVAL
+/// limit higher than the current one, return -EPERM
+pub fn sys_setrlimit(resource: usize, limit: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let current = match inner.rlimits.get_mut(resource) {
+        Some(current) => current,
+        None => return -EINVAL,
+    };
+    if limit > *current {
+        return -EPERM;
+    }
+    *current = limit;
+    0
+}
+
+/// store the limit of `resource` to rlim
+/// unknown resource, return -EINVAL
+/// rlim is not mapped writable, return -EFAULT
+pub fn sys_getrlimit(resource: usize, rlim: *mut RLimit) -> isize {
+    let limit = match current_process()
+        .inner_exclusive_access()
+        .rlimits
+        .get(resource)
+    {
+        Some(limit) => limit,
+        None => return -EINVAL,
+    };
+    let rlimit = RLimit {
+        rlim_cur: limit,
+        rlim_max: limit,
+    };
+    unwrap_or_errno!(copy_to_user(rlim, &rlimit));
+    0
+}
+
+/// passing this persona only queries the current one
+const PERSONALITY_QUERY: u32 = 0xffff_ffff;
+
+/// set the personality of current process and return the previous one
+/// persona contains unsupported bits, return -1
+pub fn sys_personality(persona: u32) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    let old = inner.personality.bits() as isize;
+    if persona != PERSONALITY_QUERY {
+        if let Some(personality) = Personality::from_bits(persona) {
+            inner.personality = personality;
+        } else {
+            return -1;
+        }
+    }
+    old
+}
+
+const PR_GET_DUMPABLE: usize = 3;
+const PR_SET_DUMPABLE: usize = 4;
+
+/// PR_GET_DUMPABLE returns whether the memory of current process may be read
+/// by others, PR_SET_DUMPABLE sets it to arg (0 or 1), exec makes it 1 again
+/// unknown option or arg, return -EINVAL
+pub fn sys_prctl(option: usize, arg: usize) -> isize {
+    let process = current_process();
+    let mut inner = process.inner_exclusive_access();
+    match (option, arg) {
+        (PR_GET_DUMPABLE, _) => inner.dumpable as isize,
+        (PR_SET_DUMPABLE, 0 | 1) => {
+            inner.dumpable = arg == 1;
+            0
+        }
+        _ => -EINVAL,
+    }
+}
+
+/// copy len bytes at remote in the address space of process pid to local,
+/// return len. Pages of pid not touched yet are not mapped for this.
+/// local is not mapped writable, or part of the remote range is not mapped
+/// readable, return -EFAULT and copy nothing
+/// no process has pid, return -ESRCH
+/// the caller is not pid, or pid is not dumpable or the caller is neither
+/// its parent nor initproc, return -EPERM
+pub fn sys_process_vm_readv(pid: usize, local: *mut u8, remote: usize, len: usize) -> isize {
+    let process = match pid2process(pid) {
+        Some(process) => process,
+        None => return -ESRCH,
+    };
+    if !current_may_read_memory(&process) {
+        return -EPERM;
+    }
+    let buffer = unwrap_or_errno!(check_user_slice(local as usize, len, true));
+    let end = match remote.checked_add(len) {
+        Some(end) => end,
+        None => return -EFAULT,
+    };
+    let page_table = PageTable::from_token(process.inner_exclusive_access().get_user_token());
+    let mut chunks: Vec<&[u8]> = Vec::new();
+    let mut start = remote;
+    while start < end {
+        let pte = match page_table.translate(VirtAddr::from(start).floor()) {
+            Some(pte) if pte.is_valid() && pte.is_user() && pte.readable() => pte,
+            _ => return -EFAULT,
+        };
+        let offset = start % PAGE_SIZE;
+        let chunk = (PAGE_SIZE - offset).min(end - start);
+        chunks.push(&pte.ppn().get_bytes_array()[offset..offset + chunk]);
+        start += chunk;
+    }
+    for (dst, src) in buffer.into_iter().zip(chunks.into_iter().flatten()) {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    len as isize
+}
+
+/// pid 0 stands for the calling process
+/// process does not exist, return -1
+pub fn sys_getpgid(pid: usize) -> isize {
+    let process = if pid == 0 {
+        Some(current_process())
+    } else {
+        pid2process(pid)
+    };
+    match process {
+        Some(process) => process.inner_exclusive_access().pgid as isize,
+        None => -1,
+    }
+}
+
+/// pid 0 stands for the calling process
+/// process does not exist, return -1
+pub fn sys_getsid(pid: usize) -> isize {
+    let process = if pid == 0 {
+        Some(current_process())
+    } else {
+        pid2process(pid)
+    };
+    match process {
+        Some(process) => process.inner_exclusive_access().sid as isize,
+        None => -1,
+    }
+}
+
+/// make the caller the leader of a new session and process group
+/// the caller already leads a process group, return -1 (EPERM)
+pub fn sys_setsid() -> isize {
+    let process = current_process();
+    let pid = process.getpid();
+    let mut inner = process.inner_exclusive_access();
+    if inner.pgid == pid {
+        return -1;
+    }
+    inner.pgid = pid;
+    inner.sid = pid;
+    pid as isize
+}
+
+const VALIDATE_MEMORY_SET: isize = 1 << 0;
+const VALIDATE_PAGE_TABLE: isize = 1 << 1;
+const VALIDATE_FRAME_ALLOCATOR: isize = 1 << 2;
+const VALIDATE_PID_ALLOCATOR: isize = 1 << 3;
+
+/// Run the kernel self checks on `memory_set` and the global allocators,
+/// which only read the state they check. Return a bitmask of the failed ones.
+fn validate(memory_set: &MemorySet) -> isize {
+    let mut failed = 0;
+    if !memory_set.check_invariants() {
+        failed |= VALIDATE_MEMORY_SET;
+    }
+    if !memory_set.validate_page_table() {
+        failed |= VALIDATE_PAGE_TABLE;
+    }
+    if !frame_allocator_check() {
+        failed |= VALIDATE_FRAME_ALLOCATOR;
+    }
+    if !pid2process_check() {
+        failed |= VALIDATE_PID_ALLOCATOR;
+    }
+    failed
+}
+
+/// run the kernel self checks on the space of the caller
+/// return 0 if all of them pass, otherwise a bitmask of the failed ones
+/// the caller is not initproc, return -EPERM
+pub fn sys_validate() -> isize {
+    if !current_is_initproc() {
+        return -EPERM;
+    }
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    validate(&inner.memory_set)
+}
+
+/// The checks pass on a healthy space, and catch a page mapped to another
+/// frame than the one its area holds.
+pub fn validate_test() {
+    let mut memory_set = MemorySet::new_bare();
+    let start: VirtAddr = 0x10000.into();
+    memory_set.insert_framed_area(
+        start,
+        start + PAGE_SIZE,
+        MapPermission::R | MapPermission::W | MapPermission::U,
+    );
+    assert_eq!(validate(&memory_set), 0);
+    // the checks themselves must not disturb the state they check
+    assert_eq!(validate(&memory_set), 0);
+    let vpn = start.floor();
+    let pte = memory_set.translate(vpn).unwrap();
+    let stray = frame_alloc().unwrap();
+    let mut page_table = PageTable::from_token(memory_set.token());
+    page_table.remap(vpn, stray.ppn, pte.flags());
+    assert_eq!(validate(&memory_set), VALIDATE_MEMORY_SET);
+    page_table.remap(vpn, pte.ppn(), pte.flags());
+    assert_eq!(validate(&memory_set), 0);
+    println!("validate_test passed!");
+}
+
+/// make each of the next `times` operations of `kind` fail with
+/// `probability` percent, see `FaultKind` for the kinds
+/// unknown kind or probability > 100, return -1
+/// injection disabled (see `fault_inject::ENABLED`), or the caller is not
+/// initproc, return -EPERM
+pub fn sys_fault_inject(kind: usize, times: usize, probability: usize) -> isize {
+    if !FAULT_INJECTION_ENABLED || !current_is_initproc() {
+        return -EPERM;
+    }
+    if probability > 100 {
+        return -1;
+    }
+    match FaultKind::from_usize(kind) {
+        Some(kind) => {
+            arm_fault(kind, times, probability);
+            0
+        }
+        None => -1,
+    }
+}
+
+/// copy the first `nbuckets` buckets of the scheduling latency histogram to
+/// buf, see `SCHED_LATENCY_BUCKETS` for the boundaries, buckets past the real
+/// count are left untouched
+/// return the number of resumes measured, which all the buckets sum up to
+pub fn sys_sched_latency_hist(buf: *mut u64, nbuckets: usize) -> isize {
+    let n = nbuckets.min(SCHED_LATENCY_BUCKETS);
+    let buffer = unwrap_or_errno!(check_user_slice(buf as usize, n * size_of::<u64>(), true));
+    let mut buckets = [0u64; SCHED_LATENCY_BUCKETS];
+    let measured = copy_sched_latency(&mut buckets[..n]);
+    let bytes = buckets[..n].iter().flat_map(|count| count.to_ne_bytes());
+    for (dst, src) in buffer.into_iter().zip(bytes) {
+        unsafe {
+            *dst = src;
+        }
+    }
+    measured as isize
+}
+
+/// replace the running kernel with the `len` bytes at `image`, loaded at
+/// KEXEC_LOAD_ADDR and entered at the physical address `entry`
+/// kexec compiled out, a release build, or the caller is not initproc,
+/// return -EPERM
+/// len is 0 or too large, or entry lies outside the loaded image, return -EINVAL
+/// image is not readable, return -EFAULT
+pub fn sys_kexec(image: *const u8, len: usize, entry: usize) -> isize {
+    if !KEXEC || !cfg!(debug_assertions) || !current_is_initproc() {
+        return -EPERM;
+    }
+    if len == 0
+        || len > KEXEC_MAX_SIZE
+        || !(KEXEC_LOAD_ADDR..KEXEC_LOAD_ADDR + len).contains(&entry)
+    {
+        return -EINVAL;
+    }
+    let buffer = unwrap_or_errno!(check_user_slice(image as usize, len, false));
+    let mut staged = Vec::with_capacity(len);
+    for chunk in buffer.buffers {
+        staged.extend_from_slice(chunk);
+    }
+    println!("[kernel] kexec {} bytes, entry {:#x}", len, entry);
+    kexec(staged, entry)
+}
+
+/// write the block cache back to the disk and power off, reporting success
+/// to the host if exit_code is 0
+pub fn sys_shutdown(exit_code: usize) -> ! {
+    sync_all();
+    report_frame_leaks();
+    println!("[kernel] shutdown with exit_code {}", exit_code);
+    shutdown(exit_code)
+}
+
+/// copy the usage of the kernel heap to buf
+/// buf is not mapped writable, return -EFAULT
+pub fn sys_meminfo(buf: *mut HeapStats) -> isize {
+    let stats = heap_stats();
+    *unwrap_or_errno!(check_user_ptr(buf)) = stats;
+    0
+}
+
+/// copy the TLB flush counters to buf
+/// buf is not mapped writable, return -EFAULT
+pub fn sys_tlb_stats(buf: *mut TlbStats) -> isize {
+    *unwrap_or_errno!(check_user_ptr(buf)) = tlb_stats();
+    0
+}
+
+/// copy the syscall statistics and the times of the calling thread to buf,
+/// this call included, but for the time in the kernel spent on it
+/// buf is not mapped writable, return -EFAULT
+pub fn sys_task_info(buf: *mut TaskInfo) -> isize {
+    let task = current_task().unwrap();
+    // copied from where it is, it takes up a quarter of the kernel stack
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.info.user_time_us = cycles_to_us(task_inner.runtime_in_user);
+    task_inner.info.kernel_time_us = cycles_to_us(task_inner.runtime_in_kernel);
+    unwrap_or_errno!(copy_to_user(buf, &task_inner.info));
+    0
+}
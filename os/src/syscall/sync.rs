@@ -1,5 +1,11 @@
-use crate::sync::{Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore};
-use crate::task::{block_current_and_run_next, current_process, current_task};
+use super::util::{check_user_ptr, copy_from_user, EAGAIN, EINVAL, ETIMEDOUT};
+use crate::sync::{
+    futex_wait, futex_wake, Condvar, Mutex, MutexBlocking, MutexSpin, Semaphore, FUTEX_WAIT,
+    FUTEX_WAKE,
+};
+use crate::task::{
+    block_current_and_run_next, current_process, current_task, suspend_current_and_run_next,
+};
 use crate::timer::{add_timer, get_time_ms};
 use alloc::sync::Arc;
 
@@ -34,22 +40,24 @@ pub fn sys_mutex_create(blocking: bool) -> isize {
     }
 }
 
-pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+/// mutex_id is not created by the current process, return -EINVAL
+fn mutex_of_current(mutex_id: usize) -> Result<Arc<dyn Mutex>, isize> {
     let process = current_process();
     let process_inner = process.inner_exclusive_access();
-    let mutex = Arc::clone(process_inner.mutex_list[mutex_id].as_ref().unwrap());
-    drop(process_inner);
-    drop(process);
+    match process_inner.mutex_list.get(mutex_id) {
+        Some(Some(mutex)) => Ok(Arc::clone(mutex)),
+        _ => Err(-EINVAL),
+    }
+}
+
+pub fn sys_mutex_lock(mutex_id: usize) -> isize {
+    let mutex = unwrap_or_errno!(mutex_of_current(mutex_id));
     mutex.lock();
     0
 }
 
 pub fn sys_mutex_unlock(mutex_id: usize) -> isize {
-    let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    let mutex = Arc::clone(process_inner.mutex_list[mutex_id].as_ref().unwrap());
-    drop(process_inner);
-    drop(process);
+    let mutex = unwrap_or_errno!(mutex_of_current(mutex_id));
     mutex.unlock();
     0
 }
@@ -75,20 +83,24 @@ pub fn sys_semaphore_create(res_count: usize) -> isize {
     id as isize
 }
 
-pub fn sys_semaphore_up(sem_id: usize) -> isize {
+/// sem_id is not created by the current process, return -EINVAL
+fn semaphore_of_current(sem_id: usize) -> Result<Arc<Semaphore>, isize> {
     let process = current_process();
     let process_inner = process.inner_exclusive_access();
-    let sem = Arc::clone(process_inner.semaphore_list[sem_id].as_ref().unwrap());
-    drop(process_inner);
+    match process_inner.semaphore_list.get(sem_id) {
+        Some(Some(sem)) => Ok(Arc::clone(sem)),
+        _ => Err(-EINVAL),
+    }
+}
+
+pub fn sys_semaphore_up(sem_id: usize) -> isize {
+    let sem = unwrap_or_errno!(semaphore_of_current(sem_id));
     sem.up();
     0
 }
 
 pub fn sys_semaphore_down(sem_id: usize) -> isize {
-    let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    let sem = Arc::clone(process_inner.semaphore_list[sem_id].as_ref().unwrap());
-    drop(process_inner);
+    let sem = unwrap_or_errno!(semaphore_of_current(sem_id));
     sem.down();
     0
 }
@@ -114,21 +126,64 @@ pub fn sys_condvar_create(_arg: usize) -> isize {
     id as isize
 }
 
-pub fn sys_condvar_signal(condvar_id: usize) -> isize {
+/// condvar_id is not created by the current process, return -EINVAL
+fn condvar_of_current(condvar_id: usize) -> Result<Arc<Condvar>, isize> {
     let process = current_process();
     let process_inner = process.inner_exclusive_access();
-    let condvar = Arc::clone(process_inner.condvar_list[condvar_id].as_ref().unwrap());
-    drop(process_inner);
+    match process_inner.condvar_list.get(condvar_id) {
+        Some(Some(condvar)) => Ok(Arc::clone(condvar)),
+        _ => Err(-EINVAL),
+    }
+}
+
+pub fn sys_condvar_signal(condvar_id: usize) -> isize {
+    let condvar = unwrap_or_errno!(condvar_of_current(condvar_id));
     condvar.signal();
     0
 }
 
 pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
-    let process = current_process();
-    let process_inner = process.inner_exclusive_access();
-    let condvar = Arc::clone(process_inner.condvar_list[condvar_id].as_ref().unwrap());
-    let mutex = Arc::clone(process_inner.mutex_list[mutex_id].as_ref().unwrap());
-    drop(process_inner);
+    let condvar = unwrap_or_errno!(condvar_of_current(condvar_id));
+    let mutex = unwrap_or_errno!(mutex_of_current(mutex_id));
     condvar.wait_with_mutex(mutex);
     0
 }
+
+/// yield until the word at addr equals expected, re-reading it after each wakeup
+/// return 0 once it does, or -ETIMEDOUT when timeout_ms elapses first
+/// addr is misaligned, return -EINVAL; it is not readable, return -EFAULT
+pub fn sys_yield_until(addr: *const u32, expected: u32, timeout_ms: usize) -> isize {
+    if addr as usize % core::mem::size_of::<u32>() != 0 {
+        return -EINVAL;
+    }
+    let expire_ms = get_time_ms().saturating_add(timeout_ms);
+    loop {
+        if unwrap_or_errno!(copy_from_user(addr)) == expected {
+            return 0;
+        }
+        if get_time_ms() >= expire_ms {
+            return -ETIMEDOUT;
+        }
+        suspend_current_and_run_next();
+    }
+}
+
+/// FUTEX_WAIT: block until woken if *uaddr == val, return 0 once woken or
+/// -EAGAIN if the word differs
+/// FUTEX_WAKE: wake up to val tasks blocked on uaddr, return the number woken
+/// uaddr is misaligned or not mapped writable, return -EFAULT; op is unknown,
+/// return -EINVAL
+pub fn sys_futex(uaddr: *mut u32, op: u32, val: u32) -> isize {
+    let word = unwrap_or_errno!(check_user_ptr(uaddr));
+    match op {
+        FUTEX_WAIT => {
+            if futex_wait(word, val) {
+                0
+            } else {
+                -EAGAIN
+            }
+        }
+        FUTEX_WAKE => futex_wake(word as *mut u32 as usize, val as usize) as isize,
+        _ => -EINVAL,
+    }
+}
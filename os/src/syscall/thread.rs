@@ -1,11 +1,24 @@
+use super::process::{sys_clone, CLONE_FILES, CLONE_VM};
+use super::util::EINVAL;
 use crate::{
+    config::MEMORY_NODES,
     mm::kernel_token,
-    task::{add_task, current_task, TaskControlBlock},
+    task::{
+        add_task, check_user_ptr_of_current, current_task, TaskControlBlock, ROBUST_LIST_HEAD_SIZE,
+    },
     trap::{trap_handler, TrapContext},
 };
 use alloc::sync::Arc;
 
+/// sys_clone of a thread on a user stack of its own
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
+    sys_clone(CLONE_VM | CLONE_FILES, 0, entry, arg)
+}
+
+/// Create a thread of the current process starting at entry with arg in a0,
+/// on `stack` if it is not 0 and on the user stack of the thread otherwise.
+/// Return its tid.
+pub fn clone_thread(entry: usize, arg: usize, stack: usize) -> isize {
     let task = current_task().unwrap();
     let process = task.process.upgrade().unwrap();
     // create a new thread
@@ -31,13 +44,20 @@ pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     }
     tasks[new_task_tid] = Some(Arc::clone(&new_task));
     let new_task_trap_cx = new_task_inner.get_trap_cx();
+    // the thread-local storage is right above the user stack
+    let sp = if stack != 0 {
+        stack
+    } else {
+        new_task_res.tls_base()
+    };
     *new_task_trap_cx = TrapContext::app_init_context(
         entry,
-        new_task_res.ustack_top(),
+        sp,
         kernel_token(),
         new_task.kstack.get_top(),
         trap_handler as usize,
     );
+    new_task_trap_cx.set_tp(new_task_res.tls_base());
     (*new_task_trap_cx).x[10] = arg;
     new_task_tid as isize
 }
@@ -83,3 +103,39 @@ pub fn sys_waittid(tid: usize) -> i32 {
         -2
     }
 }
+
+/// register the robust futex list of the current thread, which is walked
+/// when the thread exits or its process is killed
+/// len mismatches the list head layout, return -1
+pub fn sys_set_robust_list(head: usize, len: usize) -> isize {
+    if len != ROBUST_LIST_HEAD_SIZE || !check_user_ptr_of_current(head, len) {
+        return -1;
+    }
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .robust_list_head = head;
+    0
+}
+
+/// zero the u32 at `tidptr` when the current thread exits, 0 to stop, and
+/// return the tid of the thread
+pub fn sys_set_tid_address(tidptr: usize) -> isize {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.clear_child_tid = tidptr;
+    task_inner.res.as_ref().unwrap().tid as isize
+}
+
+/// prefer memory node `node` for frames allocated on behalf of the current thread
+/// node does not exist, return -EINVAL
+pub fn sys_set_mempolicy(node: usize) -> isize {
+    if node >= MEMORY_NODES {
+        return -EINVAL;
+    }
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .mempolicy_node = node;
+    0
+}
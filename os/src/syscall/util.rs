@@ -0,0 +1,209 @@
+//! Argument validation shared by the syscalls.
+//!
+//! Every helper translates user pointers through the page table of the
+//! current process and checks the permissions of the pages before handing
+//! out kernel references. On failure the negative errno to return is given.
+
+use crate::config::{MAX_TASKS, PAGE_SIZE};
+use crate::fault_inject::{arm_fault, should_fail, FaultKind};
+use crate::fs::File;
+use crate::mm::{PageTable, PageTableEntry, UserBuffer, VirtAddr};
+use crate::task::{
+    check_user_ptr_of_current, current_process, current_user_token, handle_cow_fault_of_current,
+    handle_lazy_fault_of_current, task_count,
+};
+use crate::trap::copy_bytes;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::{align_of, size_of, MaybeUninit};
+
+pub const EPERM: isize = 1;
+pub const ESRCH: isize = 3;
+pub const E2BIG: isize = 7;
+pub const EBADF: isize = 9;
+pub const EAGAIN: isize = 11;
+pub const ENOMEM: isize = 12;
+pub const EFAULT: isize = 14;
+pub const EINVAL: isize = 22;
+pub const EMFILE: isize = 24;
+pub const ESPIPE: isize = 29;
+pub const ETIMEDOUT: isize = 110;
+pub const ECONNREFUSED: isize = 111;
+
+/// Return the errno of a failed check from the enclosing syscall.
+macro_rules! unwrap_or_errno {
+    ($result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(errno) => return errno,
+        }
+    };
+}
+
+/// fd is not opened by the current process, return -EBADF
+pub fn check_fd(fd: usize) -> Result<Arc<dyn File + Send + Sync>, isize> {
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    let file = inner.fd_table().get(fd).cloned().flatten();
+    file.ok_or(-EBADF)
+}
+
+/// MAX_TASKS tasks are alive, or the current process has reached
+/// RLIMIT_NPROC, return -EAGAIN. Checked before a task is created, since
+/// tasks only go away once reaped.
+pub fn check_nproc() -> Result<(), isize> {
+    let process = current_process();
+    let inner = process.inner_exclusive_access();
+    if task_count() >= MAX_TASKS || inner.nproc_usage() >= inner.rlimits.nproc {
+        Err(-EAGAIN)
+    } else {
+        Ok(())
+    }
+}
+
+/// The leaf entry mapping `va`, if the page is accessible from user mode.
+/// The kernel accesses the frame directly, so a page not touched yet in a
+/// lazy area is mapped first, and a page shared copy-on-write is copied
+/// first when asked for writable.
+fn user_pte(page_table: &PageTable, va: usize, writable: bool) -> Result<PageTableEntry, isize> {
+    let vpn = VirtAddr::from(va).floor();
+    match page_table.translate(vpn) {
+        Some(pte) if pte.is_valid() => {
+            if writable && pte.is_cow() {
+                handle_cow_fault_of_current(va);
+            }
+        }
+        _ => {
+            handle_lazy_fault_of_current(va);
+        }
+    }
+    page_table
+        .translate(vpn)
+        .filter(|pte| {
+            pte.is_valid() && pte.is_user() && pte.readable() && (!writable || pte.writable())
+        })
+        .ok_or(-EFAULT)
+}
+
+/// `[ptr, ptr + len)` is not mapped readable (and writable if asked) for the
+/// current process, return -EFAULT
+pub fn check_user_slice(ptr: usize, len: usize, writable: bool) -> Result<UserBuffer, isize> {
+    if len == 0 {
+        return Ok(UserBuffer::new(Vec::new()));
+    }
+    if ptr == 0 || ptr.checked_add(len).is_none() || !check_user_ptr_of_current(ptr, len) {
+        return Err(-EFAULT);
+    }
+    let page_table = PageTable::from_token(current_user_token());
+    let end = ptr + len;
+    let mut start = ptr;
+    let mut buffers = Vec::new();
+    while start < end {
+        let ppn = user_pte(&page_table, start, writable)?.ppn();
+        let offset = start % PAGE_SIZE;
+        let chunk = (PAGE_SIZE - offset).min(end - start);
+        buffers.push(&mut ppn.get_bytes_array()[offset..offset + chunk]);
+        start += chunk;
+    }
+    Ok(UserBuffer::new(buffers))
+}
+
+/// `ptr` is null, misaligned or not mapped writable, return -EFAULT
+pub fn check_user_ptr<T>(ptr: *mut T) -> Result<&'static mut T, isize> {
+    let va = ptr as usize;
+    // an aligned object smaller than its alignment never crosses a page,
+    // larger ones must not either since the reference has to be contiguous
+    if va == 0
+        || va % align_of::<T>() != 0
+        || va % PAGE_SIZE + size_of::<T>() > PAGE_SIZE
+        || !check_user_ptr_of_current(va, size_of::<T>())
+    {
+        return Err(-EFAULT);
+    }
+    let page_table = PageTable::from_token(current_user_token());
+    let ppn = user_pte(&page_table, va, true)?.ppn();
+    let object = &mut ppn.get_bytes_array()[va % PAGE_SIZE] as *mut u8 as *mut T;
+    Ok(unsafe { &mut *object })
+}
+
+/// Copy an object from user space, which may cross a page boundary.
+/// `ptr` is null or not mapped readable, or the copy faults, return -EFAULT
+pub fn copy_from_user<T: Copy>(ptr: *const T) -> Result<T, isize> {
+    let buffer = check_user_slice(ptr as usize, size_of::<T>(), false)?;
+    copy_from_buffer(buffer)
+}
+
+/// Copy an object out of the chunks of `buffer`, which add up to its size.
+/// The copy faults, return -EFAULT
+fn copy_from_buffer<T: Copy>(buffer: UserBuffer) -> Result<T, isize> {
+    let mut object = MaybeUninit::<T>::uninit();
+    let mut dst = object.as_mut_ptr() as *mut u8;
+    for chunk in buffer.buffers {
+        // null is never mapped in kernel space
+        let src = if should_fail(FaultKind::UserCopy) {
+            core::ptr::null()
+        } else {
+            chunk.as_ptr()
+        };
+        if unsafe { copy_bytes(dst, src, chunk.len()) } != 0 {
+            return Err(-EFAULT);
+        }
+        dst = unsafe { dst.add(chunk.len()) };
+    }
+    Ok(unsafe { object.assume_init() })
+}
+
+/// An object in two chunks is copied whole, and an injected fault in the
+/// middle of the copy is reported instead of taken by the kernel.
+pub fn copy_from_user_test() {
+    let mut object: u64 = 0x0123_4567_89ab_cdef;
+    let bytes = &mut object as *mut u64 as *mut u8;
+    let buffer = || {
+        UserBuffer::new(unsafe {
+            alloc::vec![
+                core::slice::from_raw_parts_mut(bytes, 3),
+                core::slice::from_raw_parts_mut(bytes.add(3), size_of::<u64>() - 3),
+            ]
+        })
+    };
+    assert_eq!(copy_from_buffer::<u64>(buffer()), Ok(0x0123_4567_89ab_cdef));
+    arm_fault(FaultKind::UserCopy, 1, 100);
+    assert_eq!(copy_from_buffer::<u64>(buffer()), Err(-EFAULT));
+    println!("copy_from_user_test passed!");
+}
+
+/// Copy an object to user space, which may cross a page boundary.
+/// `ptr` is null or not mapped writable, return -EFAULT
+pub fn copy_to_user<T: Copy>(ptr: *mut T, object: &T) -> Result<(), isize> {
+    let buffer = check_user_slice(ptr as usize, size_of::<T>(), true)?;
+    let bytes =
+        unsafe { core::slice::from_raw_parts(object as *const T as *const u8, size_of::<T>()) };
+    for (dst, src) in buffer.into_iter().zip(bytes) {
+        unsafe {
+            *dst = *src;
+        }
+    }
+    Ok(())
+}
+
+/// Load a nul-terminated string without the ending `\0`.
+/// Some byte before the `\0` is not mapped readable, return -EFAULT
+pub fn check_user_str(ptr: *const u8) -> Result<String, isize> {
+    let mut va = ptr as usize;
+    if va == 0 || !check_user_ptr_of_current(va, 0) {
+        return Err(-EFAULT);
+    }
+    let page_table = PageTable::from_token(current_user_token());
+    let mut string = String::new();
+    loop {
+        let page = user_pte(&page_table, va, false)?.ppn().get_bytes_array();
+        for &ch in &page[va % PAGE_SIZE..] {
+            if ch == 0 {
+                return Ok(string);
+            }
+            string.push(ch as char);
+        }
+        va = (va / PAGE_SIZE + 1) * PAGE_SIZE;
+    }
+}
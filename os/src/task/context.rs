@@ -1,3 +1,4 @@
+use super::exit_current_on_entry;
 use crate::trap::trap_return;
 
 #[repr(C)]
@@ -22,4 +23,13 @@ impl TaskContext {
             s: [0; 12],
         }
     }
+    /// The task exits with SIGKILL as soon as it is switched to, without
+    /// ever returning to user mode.
+    pub fn goto_exit(kstack_ptr: usize) -> Self {
+        Self {
+            ra: exit_current_on_entry as usize,
+            sp: kstack_ptr,
+            s: [0; 12],
+        }
+    }
 }
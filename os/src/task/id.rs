@@ -1,5 +1,7 @@
 use super::ProcessControlBlock;
-use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT_BASE, USER_STACK_SIZE};
+use crate::config::{
+    KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT_BASE, USER_STACK_SIZE, USER_TLS_SIZE,
+};
 use crate::mm::{MapPermission, PhysPageNum, VirtAddr, KERNEL_SPACE};
 use crate::sync::UPIntrFreeCell;
 use alloc::{
@@ -28,6 +30,21 @@ impl RecycleAllocator {
             self.current - 1
         }
     }
+    /// Every recycled id must have been allocated before, and be
+    /// recycled only once.
+    pub fn check(&self) -> bool {
+        self.recycled
+            .iter()
+            .enumerate()
+            .all(|(i, id)| *id < self.current && !self.recycled[i + 1..].contains(id))
+    }
+    /// number of ids allocated and not recycled yet
+    pub fn allocated(&self) -> usize {
+        self.current - self.recycled.len()
+    }
+    pub fn is_allocated(&self, id: usize) -> bool {
+        id < self.current && !self.recycled.contains(&id)
+    }
     pub fn dealloc(&mut self, id: usize) {
         assert!(id < self.current);
         assert!(
@@ -54,21 +71,51 @@ pub fn pid_alloc() -> PidHandle {
     PidHandle(PID_ALLOCATOR.exclusive_access().alloc())
 }
 
+/// The pid allocator must be consistent, and each of `live_pids` allocated.
+pub fn pid_allocator_check(live_pids: impl Iterator<Item = usize>) -> bool {
+    let allocator = PID_ALLOCATOR.exclusive_access();
+    allocator.check() && live_pids.into_iter().all(|pid| allocator.is_allocated(pid))
+}
+
 impl Drop for PidHandle {
     fn drop(&mut self) {
         PID_ALLOCATOR.exclusive_access().dealloc(self.0);
     }
 }
 
-/// Return (bottom, top) of a kernel stack in kernel space.
+/// Return (bottom, top) of a kernel stack in kernel space. The page below
+/// bottom is left unmapped as a guard, so an overflow faults instead of
+/// writing into the next stack, see `__alltraps_k`.
 pub fn kernel_stack_position(kstack_id: usize) -> (usize, usize) {
     let top = TRAMPOLINE - kstack_id * (KERNEL_STACK_SIZE + PAGE_SIZE);
     let bottom = top - KERNEL_STACK_SIZE;
     (bottom, top)
 }
 
+/// Return (bottom, top) of the kernel stack slot `addr` lies in, top
+/// included since it is where the outermost frame points to. The stack
+/// may not be allocated.
+pub fn kernel_stack_containing(addr: usize) -> Option<(usize, usize)> {
+    if !(TRAMPOLINE / 2..=TRAMPOLINE).contains(&addr) {
+        return None;
+    }
+    let kstack_id = (TRAMPOLINE - addr) / (KERNEL_STACK_SIZE + PAGE_SIZE);
+    let (bottom, top) = kernel_stack_position(kstack_id);
+    if addr > bottom {
+        Some((bottom, top))
+    } else {
+        None
+    }
+}
+
 pub struct KernelStack(pub usize);
 
+/// Every task holds a kernel stack from creation until it is reaped, so this
+/// is the number of tasks alive, zombies included.
+pub fn task_count() -> usize {
+    KSTACK_ALLOCATOR.exclusive_access().allocated()
+}
+
 pub fn kstack_alloc() -> KernelStack {
     let kstack_id = KSTACK_ALLOCATOR.exclusive_access().alloc();
     let (kstack_bottom, kstack_top) = kernel_stack_position(kstack_id);
@@ -144,10 +191,10 @@ impl TaskUserRes {
     pub fn alloc_user_res(&self) {
         let process = self.process.upgrade().unwrap();
         let mut process_inner = process.inner_exclusive_access();
-        // alloc user stack
+        // alloc user stack, whose pages are mapped on demand
         let ustack_bottom = ustack_bottom_from_tid(self.ustack_base, self.tid);
         let ustack_top = ustack_bottom + USER_STACK_SIZE;
-        process_inner.memory_set.insert_framed_area(
+        process_inner.memory_set.insert_lazy_area(
             ustack_bottom.into(),
             ustack_top.into(),
             MapPermission::R | MapPermission::W | MapPermission::U,
@@ -166,11 +213,12 @@ impl TaskUserRes {
         // dealloc tid
         let process = self.process.upgrade().unwrap();
         let mut process_inner = process.inner_exclusive_access();
-        // dealloc ustack manually
+        // dealloc ustack manually, mprotect may have split it
         let ustack_bottom_va: VirtAddr = ustack_bottom_from_tid(self.ustack_base, self.tid).into();
+        let ustack_top_va = ustack_bottom_va + USER_STACK_SIZE;
         process_inner
             .memory_set
-            .remove_area_with_start_vpn(ustack_bottom_va.into());
+            .remove_areas_within(ustack_bottom_va.into(), ustack_top_va.into());
         // dealloc trap_cx manually
         let trap_cx_bottom_va: VirtAddr = trap_cx_bottom_from_tid(self.tid).into();
         process_inner
@@ -215,6 +263,11 @@ impl TaskUserRes {
     pub fn ustack_top(&self) -> usize {
         ustack_bottom_from_tid(self.ustack_base, self.tid) + USER_STACK_SIZE
     }
+    /// The thread-local storage of the thread, at the top of its user stack.
+    /// The stack begins right below it.
+    pub fn tls_base(&self) -> usize {
+        self.ustack_top() - USER_TLS_SIZE
+    }
 }
 
 impl Drop for TaskUserRes {
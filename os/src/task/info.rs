@@ -0,0 +1,40 @@
+//! Syscall statistics of each thread, reported by `sys_task_info`.
+
+use crate::config::MAX_SYSCALL_NUM;
+
+/// Copied to user space as is.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TaskInfo {
+    /// calls of each syscall id, the ids from MAX_SYSCALL_NUM - 1 on (the
+    /// thread, sync and debugging syscalls) are all counted in the last slot
+    pub syscall_times: [u32; MAX_SYSCALL_NUM],
+    /// when the first syscall was dispatched in ms, 0 before any
+    pub first_time_ms: usize,
+    /// when the latest syscall was dispatched in ms, 0 before any
+    pub last_time_ms: usize,
+    /// time the thread ran in user mode and in the kernel in us, filled in
+    /// from the runtimes of the thread when copied out
+    pub user_time_us: usize,
+    pub kernel_time_us: usize,
+}
+
+impl TaskInfo {
+    pub fn new() -> Self {
+        Self {
+            syscall_times: [0; MAX_SYSCALL_NUM],
+            first_time_ms: 0,
+            last_time_ms: 0,
+            user_time_us: 0,
+            kernel_time_us: 0,
+        }
+    }
+    pub fn record(&mut self, syscall_id: usize, now_ms: usize) {
+        let slot = syscall_id.min(MAX_SYSCALL_NUM - 1);
+        self.syscall_times[slot] = self.syscall_times[slot].saturating_add(1);
+        if self.first_time_ms == 0 {
+            self.first_time_ms = now_ms;
+        }
+        self.last_time_ms = now_ms;
+    }
+}
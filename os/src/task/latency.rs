@@ -0,0 +1,50 @@
+//! Histogram of scheduling delays, measured from the time a task is put into
+//! the ready queue until it is resumed.
+
+use crate::sync::UPIntrFreeCell;
+use lazy_static::*;
+
+/// Bucket 0 counts delays of 0 cycles, bucket i (0 < i < last) counts delays
+/// in [2^(i-1), 2^i) and the last bucket everything from 2^(last-1) on.
+pub const SCHED_LATENCY_BUCKETS: usize = 24;
+
+pub struct LatencyHistogram {
+    buckets: [u64; SCHED_LATENCY_BUCKETS],
+    /// number of resumes measured, which all buckets sum up to
+    measured: u64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self {
+            buckets: [0; SCHED_LATENCY_BUCKETS],
+            measured: 0,
+        }
+    }
+    fn bucket_of(delay: usize) -> usize {
+        let log = (usize::BITS - delay.leading_zeros()) as usize;
+        log.min(SCHED_LATENCY_BUCKETS - 1)
+    }
+    pub fn record(&mut self, delay: usize) {
+        self.buckets[Self::bucket_of(delay)] += 1;
+        self.measured += 1;
+    }
+}
+
+lazy_static! {
+    pub static ref SCHED_LATENCY: UPIntrFreeCell<LatencyHistogram> =
+        unsafe { UPIntrFreeCell::new(LatencyHistogram::new()) };
+}
+
+pub fn record_sched_latency(delay: usize) {
+    SCHED_LATENCY.exclusive_access().record(delay);
+}
+
+/// Copy the first `buf.len()` buckets into buf, return the number of resumes
+/// measured so far.
+pub fn copy_sched_latency(buf: &mut [u64]) -> u64 {
+    let hist = SCHED_LATENCY.exclusive_access();
+    let n = buf.len().min(SCHED_LATENCY_BUCKETS);
+    buf[..n].copy_from_slice(&hist.buckets[..n]);
+    hist.measured
+}
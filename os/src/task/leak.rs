@@ -0,0 +1,53 @@
+//! Accounting of the frames handed out, to find those nothing holds any
+//! more at shutdown.
+
+use super::process::ProcessControlBlock;
+use super::INITPROC;
+use crate::mm::{frames_allocated, shm_collect_frames, PhysPageNum, KERNEL_SPACE};
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// frames the drivers took at boot, outside of any address space
+static DRIVER_FRAMES: AtomicUsize = AtomicUsize::new(0);
+
+fn collect_process_frames(process: &Arc<ProcessControlBlock>, frames: &mut BTreeSet<PhysPageNum>) {
+    let inner = process.inner_exclusive_access();
+    inner.memory_set.collect_frames(frames);
+    for child in inner.children.iter() {
+        collect_process_frames(child, frames);
+    }
+}
+
+/// Frames of the kernel space, kernel stacks included, of every process,
+/// zombies included, since all of them descend from initproc, and of the
+/// shared memory segments.
+fn held_frames(with_processes: bool) -> usize {
+    let mut frames = BTreeSet::new();
+    KERNEL_SPACE.exclusive_access().collect_frames(&mut frames);
+    if with_processes {
+        collect_process_frames(&INITPROC, &mut frames);
+        shm_collect_frames(&mut frames);
+    }
+    frames.len()
+}
+
+/// Called before the first process is created.
+pub fn record_driver_frames() {
+    DRIVER_FRAMES.store(frames_allocated() - held_frames(false), Ordering::Relaxed);
+}
+
+/// Log the frames still handed out that neither the kernel nor a process
+/// holds, return how many they are.
+pub fn report_frame_leaks() -> usize {
+    let allocated = frames_allocated();
+    let leaked = allocated
+        .saturating_sub(held_frames(true))
+        .saturating_sub(DRIVER_FRAMES.load(Ordering::Relaxed));
+    if leaked > 0 {
+        error!("{} of {} allocated frames leaked", leaked, allocated);
+    } else {
+        info!("no frame leaked, {} allocated", allocated);
+    }
+    leaked
+}
@@ -1,25 +1,78 @@
-use super::{ProcessControlBlock, TaskControlBlock};
+use super::id::pid_allocator_check;
+use super::latency::record_sched_latency;
+use super::{ProcessControlBlock, TaskControlBlock, TaskStatus};
 use crate::sync::UPIntrFreeCell;
+use crate::timer::{cycles_to_us, get_time};
 use alloc::collections::{BTreeMap, VecDeque};
 use alloc::sync::Arc;
+use alloc::vec::Vec;
 use lazy_static::*;
 
+/// The stride a task of priority p advances by each time it runs is
+/// BIG_STRIDE / p. Since p >= 2, the passes of ready tasks never differ by
+/// more than BIG_STRIDE / 2, far below half the range of usize, so the pass
+/// comparison stays right when they wrap around.
+pub const BIG_STRIDE: usize = 1 << 20;
+
+/// `a` is before `b`, even if one of them has wrapped around.
+fn stride_before(a: usize, b: usize) -> bool {
+    (a.wrapping_sub(b) as isize) < 0
+}
+
 pub struct TaskManager {
-    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+    /// ready tasks with the time they were added at
+    ready_queue: VecDeque<(Arc<TaskControlBlock>, usize)>,
+    /// pass of the task fetched last
+    pass: usize,
 }
 
-/// A simple FIFO scheduler.
+/// A stride scheduler, tasks with equal strides run in FIFO order. A task
+/// that yields has advanced its stride and is added at the back, so every
+/// other ready task of the same priority runs before it again.
 impl TaskManager {
     pub fn new() -> Self {
         Self {
             ready_queue: VecDeque::new(),
+            pass: 0,
         }
     }
     pub fn add(&mut self, task: Arc<TaskControlBlock>) {
-        self.ready_queue.push_back(task);
+        // a new task or one that slept for long starts from the current pass
+        // instead of taking the cpu until it catches up
+        task.inner.exclusive_session(|task_inner| {
+            if stride_before(task_inner.stride, self.pass) {
+                task_inner.stride = self.pass;
+            }
+        });
+        self.ready_queue.push_back((task, get_time()));
     }
     pub fn fetch(&mut self) -> Option<Arc<TaskControlBlock>> {
-        self.ready_queue.pop_front()
+        let mut next: Option<(usize, usize)> = None;
+        for (id, (task, _)) in self.ready_queue.iter().enumerate() {
+            let stride = task.inner_exclusive_access().stride;
+            if next.map_or(true, |(_, min)| stride_before(stride, min)) {
+                next = Some((id, stride));
+            }
+        }
+        let (task, ready_since) = self.ready_queue.remove(next?.0).unwrap();
+        record_sched_latency(get_time() - ready_since);
+        task.inner.exclusive_session(|task_inner| {
+            self.pass = task_inner.stride;
+            task_inner.stride = task_inner
+                .stride
+                .wrapping_add(BIG_STRIDE / task_inner.priority);
+        });
+        Some(task)
+    }
+    pub fn remove(&mut self, task: Arc<TaskControlBlock>) {
+        if let Some((id, _)) = self
+            .ready_queue
+            .iter()
+            .enumerate()
+            .find(|(_, (t, _))| Arc::as_ptr(t) == Arc::as_ptr(&task))
+        {
+            self.ready_queue.remove(id);
+        }
     }
 }
 
@@ -38,6 +91,10 @@ pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
     TASK_MANAGER.exclusive_access().fetch()
 }
 
+pub fn remove_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.exclusive_access().remove(task);
+}
+
 pub fn pid2process(pid: usize) -> Option<Arc<ProcessControlBlock>> {
     let map = PID2PCB.exclusive_access();
     map.get(&pid).map(Arc::clone)
@@ -47,9 +104,78 @@ pub fn insert_into_pid2process(pid: usize, process: Arc<ProcessControlBlock>) {
     PID2PCB.exclusive_access().insert(pid, process);
 }
 
+/// Every process reachable by pid must hold an allocated pid.
+pub fn pid2process_check() -> bool {
+    let map = PID2PCB.exclusive_access();
+    pid_allocator_check(map.keys().copied())
+}
+
 pub fn remove_from_pid2process(pid: usize) {
     let mut map = PID2PCB.exclusive_access();
     if map.remove(&pid).is_none() {
         panic!("cannot find pid {} in pid2task!", pid);
     }
 }
+
+/// One thread in the listing of `snapshot_tasks`.
+pub struct TaskSnapshot {
+    pub pid: usize,
+    pub tid: usize,
+    /// 0 for initproc
+    pub ppid: usize,
+    pub status: &'static str,
+    pub user_us: usize,
+    pub kernel_us: usize,
+}
+
+fn snapshot_threads(process: &ProcessControlBlock, ppid: usize, list: &mut Vec<TaskSnapshot>) {
+    let inner = process.inner_exclusive_access();
+    for (tid, task) in inner.tasks.iter().enumerate() {
+        if let Some(task) = task {
+            let task_inner = task.inner_exclusive_access();
+            let status = if inner.is_zombie || task_inner.exit_code.is_some() {
+                "Zombie"
+            } else {
+                match task_inner.task_status {
+                    TaskStatus::Ready => "Ready",
+                    TaskStatus::Running => "Running",
+                    TaskStatus::Blocking => "Blocked",
+                }
+            };
+            list.push(TaskSnapshot {
+                pid: process.getpid(),
+                tid,
+                ppid,
+                status,
+                user_us: cycles_to_us(task_inner.runtime_in_user),
+                kernel_us: cycles_to_us(task_inner.runtime_in_kernel),
+            });
+        }
+    }
+}
+
+/// Every thread of the live processes, and of the zombies not reaped yet,
+/// which are only reachable from their parents. Taken at once while the
+/// pid map is borrowed, so no process comes or goes in between.
+pub fn snapshot_tasks() -> Vec<TaskSnapshot> {
+    let map = PID2PCB.exclusive_access();
+    let mut list = Vec::new();
+    for (&pid, process) in map.iter() {
+        let ppid = process
+            .inner_exclusive_access()
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.upgrade())
+            .map_or(0, |parent| parent.getpid());
+        snapshot_threads(process, ppid, &mut list);
+        let children = process.inner_exclusive_access().children.clone();
+        for child in children
+            .iter()
+            .filter(|child| child.inner_exclusive_access().is_zombie)
+        {
+            snapshot_threads(child, pid, &mut list);
+        }
+    }
+    list.sort_by_key(|task| (task.pid, task.tid));
+    list
+}
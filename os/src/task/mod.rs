@@ -1,8 +1,14 @@
 mod context;
 mod id;
+mod info;
+mod latency;
+mod leak;
 mod manager;
+mod personality;
 mod process;
 mod processor;
+mod rlimit;
+mod robust;
 mod signal;
 mod switch;
 #[allow(clippy::module_inception)]
@@ -10,19 +16,34 @@ mod task;
 
 use self::id::TaskUserRes;
 use crate::fs::{open_file, OpenFlags};
+use crate::mm::VirtAddr;
+use crate::sync::{futex_wake, remove_futex_waiter};
+use crate::timer::remove_timer;
 use alloc::{sync::Arc, vec::Vec};
 use lazy_static::*;
-use manager::fetch_task;
-use process::ProcessControlBlock;
+use manager::{fetch_task, remove_task};
+use process::{new_fd_table, ProcessControlBlock};
+use robust::{exit_clear_child_tid, exit_robust_list};
 use switch::__switch;
 
 pub use context::TaskContext;
-pub use id::{kstack_alloc, pid_alloc, KernelStack, PidHandle, IDLE_PID};
-pub use manager::{add_task, pid2process, remove_from_pid2process};
+pub use id::{
+    kernel_stack_containing, kstack_alloc, pid_alloc, task_count, KernelStack, PidHandle, IDLE_PID,
+};
+pub use info::TaskInfo;
+pub use latency::{copy_sched_latency, SCHED_LATENCY_BUCKETS};
+pub use leak::report_frame_leaks;
+pub use manager::{
+    add_task, pid2process, pid2process_check, remove_from_pid2process, snapshot_tasks, TaskSnapshot,
+};
+pub use personality::Personality;
 pub use processor::{
-    current_kstack_top, current_process, current_task, current_trap_cx, current_trap_cx_user_va,
-    current_user_token, run_tasks, schedule, take_current_task,
+    charge_current_runtime, current_process, current_task, current_trap_cx,
+    current_trap_cx_user_va, current_user_token, idle_test, run_tasks, schedule, take_current_task,
+    tick_current_task,
 };
+pub use rlimit::{RLimit, RLimits};
+pub use robust::ROBUST_LIST_HEAD_SIZE;
 pub use signal::SignalFlags;
 pub use task::{TaskControlBlock, TaskStatus};
 
@@ -56,19 +77,56 @@ pub fn block_current_and_run_next() {
     let task_cx_ptr = block_current_task();
     schedule(task_cx_ptr);
 }
-use crate::board::QEMUExit;
 
 pub fn exit_current_and_run_next(exit_code: i32) {
+    // the files of an exiting process are closed while it is still current,
+    // since closing the last file on an unlinked inode waits for the disk.
+    // A table shared by CLONE_FILES stays open for the other processes
+    let task = current_task().unwrap();
+    if task.inner_exclusive_access().res.as_ref().unwrap().tid == 0 {
+        let process = task.process.upgrade().unwrap();
+        let fd_table = core::mem::replace(
+            &mut process.inner_exclusive_access().fd_table,
+            new_fd_table(Vec::new()),
+        );
+        drop(fd_table);
+    }
+    drop(task);
     let task = take_current_task().unwrap();
     let mut task_inner = task.inner_exclusive_access();
     let process = task.process.upgrade().unwrap();
     let tid = task_inner.res.as_ref().unwrap().tid;
+    // release the robust futexes while the user memory is still mapped
+    if task_inner.robust_list_head != 0 {
+        exit_robust_list(
+            &mut process.inner_exclusive_access().memory_set,
+            task_inner.robust_list_head,
+            tid,
+        );
+    }
+    if task_inner.clear_child_tid != 0 {
+        let word = exit_clear_child_tid(
+            &mut process.inner_exclusive_access().memory_set,
+            task_inner.clear_child_tid,
+        );
+        if let Some(word) = word {
+            futex_wake(word, 1);
+        }
+    }
     // record exit code
     task_inner.exit_code = Some(exit_code);
     task_inner.res = None;
     // here we do not remove the thread since we are still using the kstack
     // it will be deallocated when sys_waittid is called
     drop(task_inner);
+    let (user_us, kernel_us) = task.runtimes_us();
+    debug!(
+        "pid {} tid {} exited after {} us in user mode and {} us in the kernel",
+        process.getpid(),
+        tid,
+        user_us,
+        kernel_us
+    );
     drop(task);
     // however, if this is the main thread of current process
     // the process should terminate at once
@@ -79,13 +137,8 @@ pub fn exit_current_and_run_next(exit_code: i32) {
                 "[kernel] Idle process exit with exit_code {} ...",
                 exit_code
             );
-            if exit_code != 0 {
-                //crate::sbi::shutdown(255); //255 == -1 for err hint
-                crate::board::QEMU_EXIT_HANDLE.exit_failure();
-            } else {
-                //crate::sbi::shutdown(0); //0 for success hint
-                crate::board::QEMU_EXIT_HANDLE.exit_success();
-            }
+            report_frame_leaks();
+            crate::sbi::shutdown(exit_code as usize);
         }
         remove_from_pid2process(pid);
         let mut process_inner = process.inner_exclusive_access();
@@ -95,7 +148,10 @@ pub fn exit_current_and_run_next(exit_code: i32) {
         process_inner.exit_code = exit_code;
 
         {
-            // move all child processes under init process
+            // move all child processes under init process. The locks are taken
+            // as this process, initproc, then each child, which is the order
+            // waitpid uses too (parent before child). This process is never
+            // initproc, whose exit halts the machine above.
             let mut initproc_inner = INITPROC.inner_exclusive_access();
             for child in process_inner.children.iter() {
                 child.inner_exclusive_access().parent = Some(Arc::downgrade(&INITPROC));
@@ -107,13 +163,35 @@ pub fn exit_current_and_run_next(exit_code: i32) {
         // it has to be done before we dealloc the whole memory_set
         // otherwise they will be deallocated twice
         let mut recycle_res = Vec::<TaskUserRes>::new();
-        for task in process_inner.tasks.iter().filter(|t| t.is_some()) {
+        let mut tid_words = Vec::new();
+        let inner = &mut *process_inner;
+        for task in inner.tasks.iter().filter(|t| t.is_some()) {
             let task = task.as_ref().unwrap();
+            // other threads which are ready or sleeping must never be scheduled
+            // again, since their trap_cx is going to be freed right below.
+            // Mutex/Semaphore/Condvar wait queues belong to this process and
+            // are dropped together with it, futex queues are shared.
+            remove_inactive_task(Arc::clone(task));
             let mut task_inner = task.inner_exclusive_access();
             if let Some(res) = task_inner.res.take() {
+                // other threads die with the process and never reach the exit path
+                if task_inner.robust_list_head != 0 {
+                    exit_robust_list(&mut inner.memory_set, task_inner.robust_list_head, res.tid);
+                }
+                if task_inner.clear_child_tid != 0 {
+                    tid_words.extend(exit_clear_child_tid(
+                        &mut inner.memory_set,
+                        task_inner.clear_child_tid,
+                    ));
+                }
                 recycle_res.push(res);
             }
         }
+        // only wake once every thread is off the queues, so that none of
+        // them is put back into the ready queue
+        for word in tid_words {
+            futex_wake(word, 1);
+        }
         // dealloc_tid and dealloc_user_res require access to PCB inner, so we
         // need to collect those user res first, then release process_inner
         // for now to avoid deadlock/double borrow problem.
@@ -122,10 +200,14 @@ pub fn exit_current_and_run_next(exit_code: i32) {
 
         let mut process_inner = process.inner_exclusive_access();
         process_inner.children.clear();
-        // deallocate other data in user space i.e. program code/data section
+        // deallocate other data in user space i.e. program code/data section.
+        // This is the only point where user memory is torn down: no thread of
+        // this process owns a trap_cx any more, and everything the parent may
+        // read later (exit_code) has been saved into plain fields above.
+        // The page table itself goes away when the parent reaps this process.
         process_inner.memory_set.recycle_data_pages();
         // drop file descriptors
-        process_inner.fd_table.clear();
+        process_inner.fd_table().clear();
     }
     drop(process);
     // we do not have to save task context
@@ -133,6 +215,12 @@ pub fn exit_current_and_run_next(exit_code: i32) {
     schedule(&mut _unused as *mut _);
 }
 
+/// Entry of a task killed before it ever ran, see `TaskContext::goto_exit`.
+pub fn exit_current_on_entry() -> ! {
+    exit_current_and_run_next(-9);
+    panic!("Unreachable in exit_current_on_entry!");
+}
+
 lazy_static! {
     pub static ref INITPROC: Arc<ProcessControlBlock> = {
         let inode = open_file("initproc", OpenFlags::RDONLY).unwrap();
@@ -141,14 +229,52 @@ lazy_static! {
     };
 }
 
+fn remove_inactive_task(task: Arc<TaskControlBlock>) {
+    remove_task(Arc::clone(&task));
+    remove_timer(Arc::clone(&task));
+    remove_futex_waiter(&task);
+}
+
 pub fn add_initproc() {
+    leak::record_driver_frames();
     let _initproc = INITPROC.clone();
 }
 
 pub fn check_signals_of_current() -> Option<(i32, &'static str)> {
     let process = current_process();
     let process_inner = process.inner_exclusive_access();
-    process_inner.signals.check_error()
+    // signals held by a signalfd wait there to be read instead
+    (process_inner.signals - process_inner.signalfd_mask()).check_error()
+}
+
+/// The current process is initproc, the only one trusted with operations
+/// that take over the whole kernel.
+pub fn current_is_initproc() -> bool {
+    Arc::ptr_eq(&current_process(), &INITPROC)
+}
+
+/// The current process may read the memory of `process`: it is the process
+/// itself, or `process` is dumpable and the current process is its parent or
+/// initproc.
+pub fn current_may_read_memory(process: &Arc<ProcessControlBlock>) -> bool {
+    let current = current_process();
+    if Arc::ptr_eq(&current, process) {
+        return true;
+    }
+    let inner = process.inner_exclusive_access();
+    let is_parent = inner
+        .parent
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(false, |parent| Arc::ptr_eq(&parent, &current));
+    inner.dumpable && (is_parent || Arc::ptr_eq(&current, &INITPROC))
+}
+
+/// The current process got SIGSTOP and no SIGCONT since.
+pub fn is_current_stopped() -> bool {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    process_inner.signals.contains(SignalFlags::SIGSTOP)
 }
 
 pub fn current_add_signal(signal: SignalFlags) {
@@ -156,3 +282,40 @@ pub fn current_add_signal(signal: SignalFlags) {
     let mut process_inner = process.inner_exclusive_access();
     process_inner.signals |= signal;
 }
+
+/// Give the current process its own copy of the page at `va` if it is
+/// shared copy-on-write, return whether that was the case.
+pub fn handle_cow_fault_of_current(va: usize) -> bool {
+    current_process()
+        .inner_exclusive_access()
+        .memory_set
+        .handle_cow_fault(VirtAddr::from(va).floor())
+}
+
+/// Map the page at `va` if it is in a lazy area of the current process and
+/// not touched before, return whether that was the case.
+pub fn handle_lazy_fault_of_current(va: usize) -> bool {
+    current_process()
+        .inner_exclusive_access()
+        .memory_set
+        .handle_lazy_fault(VirtAddr::from(va).floor())
+}
+
+/// Non-canonical user pointers are truncated to 39 bits on translation,
+/// unless the current process has asked for `STRICT_POINTERS`.
+pub fn check_user_ptr_of_current(ptr: usize, len: usize) -> bool {
+    let process = current_process();
+    let process_inner = process.inner_exclusive_access();
+    !process_inner
+        .personality
+        .contains(Personality::STRICT_POINTERS)
+        || (VirtAddr::is_canonical(ptr) && VirtAddr::is_canonical(ptr.wrapping_add(len)))
+}
+
+/// Count a syscall of the current thread, dispatched at now_ms.
+pub fn record_syscall_of_current(syscall_id: usize, now_ms: usize) {
+    let task = current_task().unwrap();
+    task.inner_exclusive_access()
+        .info
+        .record(syscall_id, now_ms);
+}
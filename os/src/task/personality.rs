@@ -0,0 +1,14 @@
+use bitflags::*;
+
+bitflags! {
+    /// Compatibility switches of a process, see `sys_personality`.
+    /// Inherited across fork and kept across exec. All bits are clear by default.
+    pub struct Personality: u32 {
+        /// Do not randomize the address space layout. The layout is not
+        /// randomized yet, so this is accepted but always in effect.
+        const ADDR_NO_RANDOMIZE = 0x0040000;
+        /// Reject non-canonical user pointers in syscall arguments
+        /// instead of truncating them to 39 bits.
+        const STRICT_POINTERS   = 0x1000_0000;
+    }
+}
@@ -1,10 +1,11 @@
 use super::id::RecycleAllocator;
 use super::manager::insert_into_pid2process;
-use super::TaskControlBlock;
-use super::{add_task, SignalFlags};
+use super::{add_task, Personality, RLimits, SignalFlags};
 use super::{pid_alloc, PidHandle};
+use super::{TaskContext, TaskControlBlock};
+use crate::config::{COW_FORK, PAGE_SIZE, USER_HEAP_SIZE};
 use crate::fs::{File, Stdin, Stdout};
-use crate::mm::{translated_refmut, MemorySet, KERNEL_SPACE};
+use crate::mm::{translated_refmut, MemorySet, VirtAddr, KERNEL_SPACE};
 use crate::sync::{Condvar, Mutex, Semaphore, UPIntrFreeCell, UPIntrRefMut};
 use crate::trap::{trap_handler, TrapContext};
 use alloc::string::String;
@@ -12,6 +13,13 @@ use alloc::sync::{Arc, Weak};
 use alloc::vec;
 use alloc::vec::Vec;
 
+/// open files of a process, indexed by fd
+pub type FdTable = Vec<Option<Arc<dyn File + Send + Sync>>>;
+
+pub fn new_fd_table(files: FdTable) -> Arc<UPIntrFreeCell<FdTable>> {
+    Arc::new(unsafe { UPIntrFreeCell::new(files) })
+}
+
 pub struct ProcessControlBlock {
     // immutable
     pub pid: PidHandle,
@@ -25,13 +33,28 @@ pub struct ProcessControlBlockInner {
     pub parent: Option<Weak<ProcessControlBlock>>,
     pub children: Vec<Arc<ProcessControlBlock>>,
     pub exit_code: i32,
-    pub fd_table: Vec<Option<Arc<dyn File + Send + Sync>>>,
+    /// shared with the processes cloned with CLONE_FILES, which see each
+    /// other's opens and closes
+    pub fd_table: Arc<UPIntrFreeCell<FdTable>>,
+    /// absolute path of the working directory, relative paths start there
+    pub cwd: String,
     pub signals: SignalFlags,
     pub tasks: Vec<Option<Arc<TaskControlBlock>>>,
     pub task_res_allocator: RecycleAllocator,
     pub mutex_list: Vec<Option<Arc<dyn Mutex>>>,
     pub semaphore_list: Vec<Option<Arc<Semaphore>>>,
     pub condvar_list: Vec<Option<Arc<Condvar>>>,
+    pub personality: Personality,
+    /// process group id
+    pub pgid: usize,
+    /// session id
+    pub sid: usize,
+    /// whether other processes may read the memory of this one
+    pub dumpable: bool,
+    /// the heap starts at the end of the app and ends at the program break
+    pub heap_bottom: usize,
+    pub program_brk: usize,
+    pub rlimits: RLimits,
 }
 
 impl ProcessControlBlockInner {
@@ -40,13 +63,24 @@ impl ProcessControlBlockInner {
         self.memory_set.token()
     }
 
-    pub fn alloc_fd(&mut self) -> usize {
-        if let Some(fd) = (0..self.fd_table.len()).find(|fd| self.fd_table[*fd].is_none()) {
-            fd
-        } else {
-            self.fd_table.push(None);
-            self.fd_table.len() - 1
+    #[track_caller]
+    pub fn fd_table(&self) -> UPIntrRefMut<'_, FdTable> {
+        self.fd_table.exclusive_access()
+    }
+
+    /// The lowest free fd, or None if it would reach RLIMIT_NOFILE.
+    pub fn alloc_fd(&self) -> Option<usize> {
+        let mut fd_table = self.fd_table();
+        let fd = (0..fd_table.len())
+            .find(|fd| fd_table[*fd].is_none())
+            .unwrap_or(fd_table.len());
+        if fd >= self.rlimits.nofile {
+            return None;
+        }
+        if fd == fd_table.len() {
+            fd_table.push(None);
         }
+        Some(fd)
     }
 
     pub fn alloc_tid(&mut self) -> usize {
@@ -61,21 +95,76 @@ impl ProcessControlBlockInner {
         self.tasks.len()
     }
 
+    /// Tasks counted against RLIMIT_NPROC: the children, zombies not reaped
+    /// yet included, and the threads other than the main one.
+    pub fn nproc_usage(&self) -> usize {
+        self.children.len() + self.tasks.iter().flatten().count() - 1
+    }
+
     pub fn get_task(&self, tid: usize) -> Arc<TaskControlBlock> {
         self.tasks[tid].as_ref().unwrap().clone()
     }
+
+    /// Move the program break by `size` bytes and return the old one. Fail if
+    /// the heap would end below its bottom, run into the user stacks or a
+    /// mapping in its room, or grow the address space beyond RLIMIT_AS.
+    pub fn change_program_brk(&mut self, size: isize) -> Option<usize> {
+        let old_brk = self.program_brk;
+        let new_brk = (old_brk as isize).checked_add(size)?;
+        if new_brk < self.heap_bottom as isize
+            || new_brk as usize > self.heap_bottom + USER_HEAP_SIZE
+        {
+            return None;
+        }
+        let new_brk = new_brk as usize;
+        let grown = VirtAddr::from(new_brk)
+            .ceil()
+            .0
+            .saturating_sub(VirtAddr::from(old_brk).ceil().0);
+        if grown > 0
+            && self.memory_set.mapped_size() + grown * PAGE_SIZE > self.rlimits.address_space
+        {
+            return None;
+        }
+        let resized = if size < 0 {
+            self.memory_set
+                .shrink_to(self.heap_bottom.into(), new_brk.into())
+        } else {
+            self.memory_set
+                .append_to(self.heap_bottom.into(), new_brk.into())
+        };
+        if !resized {
+            return None;
+        }
+        self.program_brk = new_brk;
+        Some(old_brk)
+    }
+
+    /// signals held by the signalfds of this process
+    pub fn signalfd_mask(&self) -> SignalFlags {
+        self.fd_table()
+            .iter()
+            .flatten()
+            .filter_map(|file| file.as_signalfd())
+            .fold(SignalFlags::empty(), |mask, signalfd| {
+                mask | signalfd.mask()
+            })
+    }
 }
 
 impl ProcessControlBlock {
+    #[track_caller]
     pub fn inner_exclusive_access(&self) -> UPIntrRefMut<'_, ProcessControlBlockInner> {
         self.inner.exclusive_access()
     }
 
     pub fn new(elf_data: &[u8]) -> Arc<Self> {
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, heap_bottom, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
         // allocate a pid
         let pid_handle = pid_alloc();
+        // the first process leads its own group and session
+        let pid = pid_handle.0;
         let process = Arc::new(Self {
             pid: pid_handle,
             inner: unsafe {
@@ -85,20 +174,28 @@ impl ProcessControlBlock {
                     parent: None,
                     children: Vec::new(),
                     exit_code: 0,
-                    fd_table: vec![
+                    fd_table: new_fd_table(vec![
                         // 0 -> stdin
                         Some(Arc::new(Stdin)),
                         // 1 -> stdout
                         Some(Arc::new(Stdout)),
                         // 2 -> stderr
                         Some(Arc::new(Stdout)),
-                    ],
+                    ]),
+                    cwd: String::from("/"),
                     signals: SignalFlags::empty(),
                     tasks: Vec::new(),
                     task_res_allocator: RecycleAllocator::new(),
                     mutex_list: Vec::new(),
                     semaphore_list: Vec::new(),
                     condvar_list: Vec::new(),
+                    personality: Personality::empty(),
+                    pgid: pid,
+                    sid: pid,
+                    dumpable: true,
+                    heap_bottom,
+                    program_brk: heap_bottom,
+                    rlimits: RLimits::new(),
                 })
             },
         });
@@ -111,16 +208,17 @@ impl ProcessControlBlock {
         // prepare trap_cx of main thread
         let task_inner = task.inner_exclusive_access();
         let trap_cx = task_inner.get_trap_cx();
-        let ustack_top = task_inner.res.as_ref().unwrap().ustack_top();
+        let tls_base = task_inner.res.as_ref().unwrap().tls_base();
         let kstack_top = task.kstack.get_top();
         drop(task_inner);
         *trap_cx = TrapContext::app_init_context(
             entry_point,
-            ustack_top,
+            tls_base,
             KERNEL_SPACE.exclusive_access().token(),
             kstack_top,
             trap_handler as usize,
         );
+        trap_cx.set_tp(tls_base);
         // add main thread to the process
         let mut process_inner = process.inner_exclusive_access();
         process_inner.tasks.push(Some(Arc::clone(&task)));
@@ -131,23 +229,52 @@ impl ProcessControlBlock {
         process
     }
 
-    /// Only support processes with a single thread.
-    pub fn exec(self: &Arc<Self>, elf_data: &[u8], args: Vec<String>) {
+    /// Only support processes with a single thread. Return false, with the
+    /// old image kept, if no frame is left for the arguments.
+    pub fn exec(self: &Arc<Self>, elf_data: &[u8], args: Vec<String>) -> bool {
         assert_eq!(self.inner_exclusive_access().thread_count(), 1);
         // memory_set with elf program headers/trampoline/trap context/user stack
-        let (memory_set, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
+        let (memory_set, heap_bottom, ustack_base, entry_point) = MemorySet::from_elf(elf_data);
         let new_token = memory_set.token();
-        // substitute memory_set
-        self.inner_exclusive_access().memory_set = memory_set;
+        // substitute memory_set, the old one is kept until the arguments are
+        // in place
+        let old_memory_set =
+            core::mem::replace(&mut self.inner_exclusive_access().memory_set, memory_set);
         // then we alloc user resource for main thread again
         // since memory_set has been changed
         let task = self.inner_exclusive_access().get_task(0);
         let mut task_inner = task.inner_exclusive_access();
+        let old_ustack_base = task_inner.res.as_ref().unwrap().ustack_base;
         task_inner.res.as_mut().unwrap().ustack_base = ustack_base;
         task_inner.res.as_mut().unwrap().alloc_user_res();
+        // push arguments on user stack, below the thread-local storage
+        let tls_base = task_inner.res.as_mut().unwrap().tls_base();
+        let mut user_sp = tls_base;
+        // the stack is mapped lazily, and the arguments are written through the frames
+        let args_size = (args.len() + 1) * core::mem::size_of::<usize>()
+            + args.iter().map(|arg| arg.len() + 1).sum::<usize>();
+        if !self
+            .inner_exclusive_access()
+            .memory_set
+            .populate((user_sp - args_size).into(), user_sp.into())
+        {
+            // the resources of the main thread in the old image are still mapped
+            task_inner.res.as_mut().unwrap().ustack_base = old_ustack_base;
+            self.inner_exclusive_access().memory_set = old_memory_set;
+            return false;
+        }
+        drop(old_memory_set);
+        // the new image holds no secrets of the old one and may be read by
+        // others again
+        let mut inner = self.inner_exclusive_access();
+        inner.dumpable = true;
+        inner.heap_bottom = heap_bottom;
+        inner.program_brk = heap_bottom;
+        drop(inner);
         task_inner.trap_cx_ppn = task_inner.res.as_mut().unwrap().trap_cx_ppn();
-        // push arguments on user stack
-        let mut user_sp = task_inner.res.as_mut().unwrap().ustack_top();
+        // the robust list and the tid address lived in the old address space
+        task_inner.robust_list_head = 0;
+        task_inner.clear_child_tid = 0;
         user_sp -= (args.len() + 1) * core::mem::size_of::<usize>();
         let argv_base = user_sp;
         let mut argv: Vec<_> = (0..=args.len())
@@ -179,28 +306,41 @@ impl ProcessControlBlock {
             task.kstack.get_top(),
             trap_handler as usize,
         );
+        trap_cx.set_tp(tls_base);
         trap_cx.x[10] = args.len();
         trap_cx.x[11] = argv_base;
         *task_inner.get_trap_cx() = trap_cx;
+        true
     }
 
-    /// Only support processes with a single thread.
-    pub fn fork(self: &Arc<Self>) -> Arc<Self> {
+    /// Make the main thread, which has not run yet, exit as soon as it is
+    /// switched to instead of returning to user mode.
+    pub fn exit_before_running(&self) {
+        let task = self.inner_exclusive_access().get_task(0);
+        let kstack_top = task.kstack.get_top();
+        task.inner_exclusive_access().task_cx = TaskContext::goto_exit(kstack_top);
+    }
+
+    /// Only support processes with a single thread left. The fd table is
+    /// shared with the child if `share_files`, copied otherwise.
+    pub fn fork(self: &Arc<Self>, share_files: bool) -> Arc<Self> {
         let mut parent = self.inner_exclusive_access();
-        assert_eq!(parent.thread_count(), 1);
-        // clone parent's memory_set completely including trampoline/ustacks/trap_cxs
-        let memory_set = MemorySet::from_existed_user(&parent.memory_set);
+        assert_eq!(parent.tasks.iter().flatten().count(), 1);
+        // clone parent's memory_set including trampoline/ustacks/trap_cxs,
+        // the user pages are shared until written if COW_FORK is on
+        let memory_set = if COW_FORK {
+            MemorySet::from_existed_user_cow(&mut parent.memory_set)
+        } else {
+            MemorySet::from_existed_user(&parent.memory_set)
+        };
         // alloc a pid
         let pid = pid_alloc();
-        // copy fd table
-        let mut new_fd_table: Vec<Option<Arc<dyn File + Send + Sync>>> = Vec::new();
-        for fd in parent.fd_table.iter() {
-            if let Some(file) = fd {
-                new_fd_table.push(Some(file.clone()));
-            } else {
-                new_fd_table.push(None);
-            }
-        }
+        // share or copy fd table
+        let fd_table = if share_files {
+            Arc::clone(&parent.fd_table)
+        } else {
+            new_fd_table(parent.fd_table().clone())
+        };
         // create child process pcb
         let child = Arc::new(Self {
             pid,
@@ -211,13 +351,21 @@ impl ProcessControlBlock {
                     parent: Some(Arc::downgrade(self)),
                     children: Vec::new(),
                     exit_code: 0,
-                    fd_table: new_fd_table,
+                    fd_table,
+                    cwd: parent.cwd.clone(),
                     signals: SignalFlags::empty(),
                     tasks: Vec::new(),
                     task_res_allocator: RecycleAllocator::new(),
                     mutex_list: Vec::new(),
                     semaphore_list: Vec::new(),
                     condvar_list: Vec::new(),
+                    personality: parent.personality,
+                    pgid: parent.pgid,
+                    sid: parent.sid,
+                    dumpable: parent.dumpable,
+                    heap_bottom: parent.heap_bottom,
+                    program_brk: parent.program_brk,
+                    rlimits: parent.rlimits,
                 })
             },
         });
@@ -1,10 +1,18 @@
 use super::__switch;
 use super::{fetch_task, TaskStatus};
 use super::{ProcessControlBlock, TaskContext, TaskControlBlock};
+use crate::config::{CLOCK_FREQ, TIME_SLICE_MS};
+use crate::sbi::set_timer;
 use crate::sync::UPIntrFreeCell;
+use crate::timer::{
+    cycles_to_us, get_time, set_next_trigger, start_tick, timer_interrupts, TIME_SLICE_TICKS,
+};
 use crate::trap::TrapContext;
 use alloc::sync::Arc;
+use core::arch::asm;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use lazy_static::*;
+use riscv::register::sstatus;
 
 pub struct Processor {
     current: Option<Arc<TaskControlBlock>>,
@@ -34,36 +42,143 @@ lazy_static! {
         unsafe { UPIntrFreeCell::new(Processor::new()) };
 }
 
+/// times the hart waited for an interrupt with nothing to run
+static IDLE_WAITS: AtomicUsize = AtomicUsize::new(0);
+/// cycles of `time` spent waiting
+static IDLE_CYCLES: AtomicUsize = AtomicUsize::new(0);
+
+/// Sleep in wfi until an interrupt, the ready queue was just found empty.
+/// Interrupts are still off: were they turned on first, one making a task
+/// ready could be taken before wfi, which would then sleep through it. wfi
+/// returns once an interrupt is pending even with them off, and it is taken
+/// when they are turned on, before the ready queue is checked again.
+fn idle_wait() {
+    let start = get_time();
+    unsafe {
+        asm!("wfi");
+    }
+    IDLE_WAITS.fetch_add(1, Ordering::Relaxed);
+    IDLE_CYCLES.fetch_add(get_time() - start, Ordering::Relaxed);
+    unsafe {
+        sstatus::set_sie();
+    }
+}
+
+/// Times the hart waited for an interrupt with nothing to run, and the
+/// microseconds it spent waiting, since boot.
+fn idle_stats() -> (usize, usize) {
+    (
+        IDLE_WAITS.load(Ordering::Relaxed),
+        cycles_to_us(IDLE_CYCLES.load(Ordering::Relaxed)),
+    )
+}
+
+/// With nothing to run and the timer set to go off 50ms later, the hart
+/// waits for it in wfi instead of spinning, and is not woken every tick
+/// meanwhile. Runs at boot, before there are any tasks or device interrupts.
+pub fn idle_test() {
+    const WAIT_MS: usize = 50;
+    let (waits, idle_us) = idle_stats();
+    let interrupts = timer_interrupts();
+    let deadline = get_time() + CLOCK_FREQ / 1000 * WAIT_MS;
+    // wfi may also return without an interrupt
+    while get_time() < deadline {
+        unsafe {
+            sstatus::clear_sie();
+        }
+        set_timer(deadline);
+        idle_wait();
+    }
+    unsafe {
+        sstatus::clear_sie();
+    }
+    let waits = idle_stats().0 - waits;
+    let idle_us = idle_stats().1 - idle_us;
+    let interrupts = timer_interrupts() - interrupts;
+    println!(
+        "[kernel] idle for {} us in {} waits, {} timer interrupts",
+        idle_us, waits, interrupts
+    );
+    assert!(idle_us >= (WAIT_MS - 1) * 1000);
+    assert!(waits > 0);
+    // the deadline, where ticks would have fired every TIME_SLICE_MS
+    assert!(interrupts < WAIT_MS / TIME_SLICE_MS);
+    println!("idle_test passed!");
+}
+
 pub fn run_tasks() {
+    // the first task switched to starts the ticks
+    let mut idle = true;
     loop {
+        // interrupts stay off from the check of the ready queue until wfi,
+        // so one making a task ready in between is not missed
+        unsafe {
+            sstatus::clear_sie();
+        }
         let mut processor = PROCESSOR.exclusive_access();
         if let Some(task) = fetch_task() {
             let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
             // access coming task TCB exclusively
             let next_task_cx_ptr = task.inner.exclusive_session(|task_inner| {
                 task_inner.task_status = TaskStatus::Running;
+                task_inner.slice_ticks_left = TIME_SLICE_TICKS;
+                task_inner.runtime_since = get_time();
                 &task_inner.task_cx as *const TaskContext
             });
             processor.current = Some(task);
             // release processor manually
             drop(processor);
+            if idle {
+                start_tick();
+                idle = false;
+            }
             unsafe {
+                // tasks run in the kernel with interrupts on, as in syscalls
+                sstatus::set_sie();
                 __switch(idle_task_cx_ptr, next_task_cx_ptr);
             }
         } else {
-            println!("no tasks available in run_tasks");
+            drop(processor);
+            idle = true;
+            // the timer only fires for the next sleeper, not for ticks
+            set_next_trigger(false);
+            idle_wait();
         }
     }
 }
 
+/// The current task leaves the cpu, it is charged the time in the kernel so
+/// far and nothing more until it is switched to again.
 pub fn take_current_task() -> Option<Arc<TaskControlBlock>> {
-    PROCESSOR.exclusive_access().take_current()
+    let task = PROCESSOR.exclusive_access().take_current();
+    if let Some(task) = task.as_ref() {
+        task.inner_exclusive_access().charge_runtime(false);
+    }
+    task
 }
 
 pub fn current_task() -> Option<Arc<TaskControlBlock>> {
     PROCESSOR.exclusive_access().current()
 }
 
+/// Charge a timer tick to the current task, return whether its time slice
+/// is used up.
+pub fn tick_current_task() -> bool {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.slice_ticks_left = task_inner.slice_ticks_left.saturating_sub(1);
+    task_inner.slice_ticks_left == 0
+}
+
+/// Charge the time since the current task last entered or left user mode,
+/// to user mode if it was spent there.
+pub fn charge_current_runtime(in_user: bool) {
+    current_task()
+        .unwrap()
+        .inner_exclusive_access()
+        .charge_runtime(in_user);
+}
+
 pub fn current_process() -> Arc<ProcessControlBlock> {
     current_task().unwrap().process.upgrade().unwrap()
 }
@@ -90,10 +205,6 @@ pub fn current_trap_cx_user_va() -> usize {
         .trap_cx_user_va()
 }
 
-pub fn current_kstack_top() -> usize {
-    current_task().unwrap().kstack.get_top()
-}
-
 pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
     let idle_task_cx_ptr =
         PROCESSOR.exclusive_session(|processor| processor.get_idle_task_cx_ptr());
@@ -0,0 +1,57 @@
+//! Resource limits of a process, see `sys_setrlimit`.
+
+pub const RLIMIT_NPROC: usize = 6;
+pub const RLIMIT_NOFILE: usize = 7;
+pub const RLIMIT_AS: usize = 9;
+/// no limit
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+/// Copied to user space as is. Only one value is kept for each resource, so
+/// both fields hold it.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct RLimit {
+    pub rlim_cur: usize,
+    pub rlim_max: usize,
+}
+
+/// Inherited across fork and spawn and kept across exec. Every limit is
+/// infinite by default. A limit lowered below the current usage only stops
+/// further growth, nothing in use is taken away.
+#[derive(Clone, Copy)]
+pub struct RLimits {
+    /// children and threads other than the main one the process may have,
+    /// zombies not reaped yet included
+    pub nproc: usize,
+    /// fds must be below it
+    pub nofile: usize,
+    /// bytes of the user address space in areas, mapped yet or not
+    pub address_space: usize,
+}
+
+impl RLimits {
+    pub fn new() -> Self {
+        Self {
+            nproc: RLIM_INFINITY,
+            nofile: RLIM_INFINITY,
+            address_space: RLIM_INFINITY,
+        }
+    }
+    /// The limit of `resource`, if it is supported.
+    pub fn get(&self, resource: usize) -> Option<usize> {
+        match resource {
+            RLIMIT_NPROC => Some(self.nproc),
+            RLIMIT_NOFILE => Some(self.nofile),
+            RLIMIT_AS => Some(self.address_space),
+            _ => None,
+        }
+    }
+    pub fn get_mut(&mut self, resource: usize) -> Option<&mut usize> {
+        match resource {
+            RLIMIT_NPROC => Some(&mut self.nproc),
+            RLIMIT_NOFILE => Some(&mut self.nofile),
+            RLIMIT_AS => Some(&mut self.address_space),
+            _ => None,
+        }
+    }
+}
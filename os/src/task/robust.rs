@@ -0,0 +1,102 @@
+use crate::mm::{MemorySet, PhysAddr, VirtAddr};
+use crate::sync::futex_wake;
+use core::mem::size_of;
+
+/// some thread is blocked on the futex
+pub const FUTEX_WAITERS: u32 = 0x8000_0000;
+/// the owner of the futex exited without releasing it
+pub const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+/// the owner field of a locked futex word, which holds `tid + 1`
+pub const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
+
+/// a broken or cyclic list must not keep the exiting thread busy forever
+const ROBUST_LIST_LIMIT: usize = 2048;
+
+/// Layout of the list head registered by `sys_set_robust_list`.
+/// `list` points to the first entry, and the list is terminated by an
+/// entry that points back to the head. The futex word of each entry
+/// lives at `entry + futex_offset`.
+#[repr(C)]
+pub struct RobustListHead {
+    pub list: usize,
+    pub futex_offset: isize,
+    pub list_op_pending: usize,
+}
+
+pub const ROBUST_LIST_HEAD_SIZE: usize = size_of::<RobustListHead>();
+
+/// Only naturally aligned, mapped and writable user words are touched,
+/// so a bogus pointer stops the walk instead of faulting in the kernel.
+/// A page shared copy-on-write is copied first, since the word is handed out
+/// for writing.
+fn user_word<T>(memory_set: &mut MemorySet, va: usize) -> Option<&'static mut T> {
+    if va % size_of::<T>() != 0 {
+        return None;
+    }
+    let va = VirtAddr::from(va);
+    memory_set.handle_cow_fault(va.floor());
+    match memory_set.translate(va.floor()) {
+        Some(pte) if pte.is_valid() && pte.writable() => {
+            let pa: PhysAddr = pte.ppn().into();
+            Some(PhysAddr::from(usize::from(pa) + va.page_offset()).get_mut())
+        }
+        _ => None,
+    }
+}
+
+fn handle_futex_death(memory_set: &mut MemorySet, uaddr: usize, owner: u32) {
+    if let Some(word) = user_word::<u32>(memory_set, uaddr) {
+        if *word & FUTEX_TID_MASK == owner {
+            *word = (*word & FUTEX_WAITERS) | FUTEX_OWNER_DIED;
+            // a waiter blocked on the dead owner takes the lock over
+            futex_wake(word as *mut u32 as usize, 1);
+        }
+    }
+}
+
+/// Walk the robust list of an exiting thread and mark every futex it still
+/// holds with `FUTEX_OWNER_DIED` and wake a waiter of it, so that the next
+/// locker can recover the protected state. Stops quietly at the first
+/// unmapped entry.
+pub fn exit_robust_list(memory_set: &mut MemorySet, head: usize, tid: usize) {
+    let owner = (tid + 1) as u32;
+    let (mut entry, futex_offset, pending) = match (
+        user_word::<usize>(memory_set, head),
+        user_word::<isize>(memory_set, head + size_of::<usize>()),
+        user_word::<usize>(memory_set, head + 2 * size_of::<usize>()),
+    ) {
+        (Some(list), Some(offset), Some(pending)) => (*list, *offset, *pending),
+        _ => return,
+    };
+    let mut limit = ROBUST_LIST_LIMIT;
+    while entry != head && limit > 0 {
+        // fetch the next entry first, since the futex word may be rewritten
+        let next = match user_word::<usize>(memory_set, entry) {
+            Some(next) => *next,
+            None => break,
+        };
+        // the pending entry is handled separately below
+        if entry != pending {
+            handle_futex_death(memory_set, entry.wrapping_add(futex_offset as usize), owner);
+        }
+        entry = next;
+        limit -= 1;
+    }
+    if pending != 0 {
+        handle_futex_death(
+            memory_set,
+            pending.wrapping_add(futex_offset as usize),
+            owner,
+        );
+    }
+}
+
+/// Zero the tid word registered by sys_set_tid_address of an exiting thread,
+/// if it is still mapped writable. Return the physical address of the word,
+/// to wake a thread joining on it as a futex.
+pub fn exit_clear_child_tid(memory_set: &mut MemorySet, va: usize) -> Option<usize> {
+    user_word::<u32>(memory_set, va).map(|word| {
+        *word = 0;
+        word as *mut u32 as usize
+    })
+}
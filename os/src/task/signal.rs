@@ -5,8 +5,13 @@ bitflags! {
         const SIGINT    = 1 << 2;
         const SIGILL    = 1 << 4;
         const SIGABRT   = 1 << 6;
+        const SIGBUS    = 1 << 7;
         const SIGFPE    = 1 << 8;
+        const SIGKILL   = 1 << 9;
         const SIGSEGV   = 1 << 11;
+        const SIGALRM   = 1 << 14;
+        const SIGCONT   = 1 << 18;
+        const SIGSTOP   = 1 << 19;
     }
 }
 
@@ -18,12 +23,38 @@ impl SignalFlags {
             Some((-4, "Illegal Instruction, SIGILL=4"))
         } else if self.contains(Self::SIGABRT) {
             Some((-6, "Aborted, SIGABRT=6"))
+        } else if self.contains(Self::SIGBUS) {
+            Some((-7, "Bus Error, SIGBUS=7"))
         } else if self.contains(Self::SIGFPE) {
             Some((-8, "Erroneous Arithmetic Operation, SIGFPE=8"))
+        } else if self.contains(Self::SIGKILL) {
+            Some((-9, "Killed, SIGKILL=9"))
         } else if self.contains(Self::SIGSEGV) {
             Some((-11, "Segmentation Fault, SIGSEGV=11"))
+        } else if self.contains(Self::SIGALRM) {
+            Some((-14, "Alarm Clock, SIGALRM=14"))
         } else {
             None
         }
     }
+
+    /// signals that can be neither caught nor held by a signalfd
+    pub fn unblockable() -> Self {
+        Self::SIGKILL | Self::SIGSTOP
+    }
+
+    /// signals the trap handler raises for the faulting instruction, which
+    /// runs again if they are held by a signalfd instead of delivered
+    pub fn synchronous() -> Self {
+        Self::SIGILL | Self::SIGBUS | Self::SIGSEGV
+    }
+
+    /// signal number of the lowest signal in the set
+    pub fn lowest_signo(&self) -> Option<u32> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.bits().trailing_zeros())
+        }
+    }
 }
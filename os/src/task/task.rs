@@ -1,5 +1,7 @@
 use super::id::TaskUserRes;
+use super::info::TaskInfo;
 use super::{kstack_alloc, KernelStack, ProcessControlBlock, TaskContext};
+use crate::timer::{cycles_to_us, get_time, TIME_SLICE_TICKS};
 use crate::trap::TrapContext;
 use crate::{
     mm::PhysPageNum,
@@ -16,10 +18,20 @@ pub struct TaskControlBlock {
 }
 
 impl TaskControlBlock {
+    #[track_caller]
     pub fn inner_exclusive_access(&self) -> UPIntrRefMut<'_, TaskControlBlockInner> {
         self.inner.exclusive_access()
     }
 
+    /// Time in us the thread ran in user mode and in the kernel so far.
+    pub fn runtimes_us(&self) -> (usize, usize) {
+        let inner = self.inner_exclusive_access();
+        (
+            cycles_to_us(inner.runtime_in_user),
+            cycles_to_us(inner.runtime_in_kernel),
+        )
+    }
+
     pub fn get_user_token(&self) -> usize {
         let process = self.process.upgrade().unwrap();
         let inner = process.inner_exclusive_access();
@@ -27,19 +39,58 @@ impl TaskControlBlock {
     }
 }
 
+pub const DEFAULT_PRIORITY: usize = 16;
+
 pub struct TaskControlBlockInner {
     pub res: Option<TaskUserRes>,
     pub trap_cx_ppn: PhysPageNum,
     pub task_cx: TaskContext,
     pub task_status: TaskStatus,
     pub exit_code: Option<i32>,
+    /// user address of the robust futex list head, 0 if not registered
+    pub robust_list_head: usize,
+    /// user address of the u32 zeroed when the thread exits, 0 if not
+    /// registered by sys_set_tid_address
+    pub clear_child_tid: usize,
+    /// memory node preferred for frames allocated on behalf of this thread
+    pub mempolicy_node: usize,
+    /// timer ticks left in the time slice of this thread, refilled whenever
+    /// it is switched to
+    pub slice_ticks_left: usize,
+    /// at least 2, a thread runs in proportion to its priority
+    pub priority: usize,
+    /// pass of this thread in stride scheduling, compared with wrapping
+    pub stride: usize,
+    /// syscalls made by this thread
+    pub info: TaskInfo,
+    /// timer cycles this thread ran in user mode and in the kernel, the
+    /// time it is ready or blocked counts as neither
+    pub runtime_in_user: usize,
+    pub runtime_in_kernel: usize,
+    /// when this thread last entered or left user mode, or was switched to
+    pub runtime_since: usize,
 }
 
 impl TaskControlBlockInner {
     pub fn get_trap_cx(&self) -> &'static mut TrapContext {
+        // trap_cx_ppn dangles once the user res of this thread is released
+        debug_assert!(self.res.is_some(), "trap_cx accessed after thread exit");
         self.trap_cx_ppn.get_mut()
     }
 
+    /// Charge the time since the last transition to user mode if it was
+    /// spent there, to the kernel otherwise.
+    pub fn charge_runtime(&mut self, in_user: bool) {
+        let now = get_time();
+        let span = now - self.runtime_since;
+        if in_user {
+            self.runtime_in_user += span;
+        } else {
+            self.runtime_in_kernel += span;
+        }
+        self.runtime_since = now;
+    }
+
     #[allow(unused)]
     fn get_status(&self) -> TaskStatus {
         self.task_status
@@ -66,6 +117,16 @@ impl TaskControlBlock {
                     task_cx: TaskContext::goto_trap_return(kstack_top),
                     task_status: TaskStatus::Ready,
                     exit_code: None,
+                    robust_list_head: 0,
+                    clear_child_tid: 0,
+                    mempolicy_node: 0,
+                    slice_ticks_left: TIME_SLICE_TICKS,
+                    priority: DEFAULT_PRIORITY,
+                    stride: 0,
+                    info: TaskInfo::new(),
+                    runtime_in_user: 0,
+                    runtime_in_kernel: 0,
+                    runtime_since: 0,
                 })
             },
         }
@@ -1,27 +1,113 @@
-use core::cmp::Ordering;
+use core::cmp;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::config::CLOCK_FREQ;
+use crate::config::{CLOCK_FREQ, TIME_SLICE_MS};
 use crate::sbi::set_timer;
 use crate::sync::UPIntrFreeCell;
 use crate::task::{add_task, TaskControlBlock};
 use alloc::collections::BinaryHeap;
 use alloc::sync::Arc;
 use lazy_static::*;
-use riscv::register::time;
+use riscv::register::{cycle, time};
 
 const TICKS_PER_SEC: usize = 100;
 const MSEC_PER_SEC: usize = 1000;
+const USEC_PER_SEC: usize = 1_000_000;
+/// timer ticks a task runs for before it is preempted
+pub const TIME_SLICE_TICKS: usize = if TIME_SLICE_MS * TICKS_PER_SEC <= MSEC_PER_SEC {
+    1
+} else {
+    (TIME_SLICE_MS * TICKS_PER_SEC + MSEC_PER_SEC - 1) / MSEC_PER_SEC
+};
 
 pub fn get_time() -> usize {
     time::read()
 }
 
+/// Clock cycles of this hart since it was reset, read from the `cycle` CSR.
+/// The clock rate of the hart is unknown to the kernel and may differ from
+/// CLOCK_FREQ of `time`, so the count is only good for comparing intervals.
+/// The hart is 64-bit, so the whole counter is read at once without the
+/// `cycleh` dance of rv32, and it does not wrap in practice.
+pub fn get_cycles() -> usize {
+    cycle::read()
+}
+
 pub fn get_time_ms() -> usize {
     time::read() / (CLOCK_FREQ / MSEC_PER_SEC)
 }
 
-pub fn set_next_trigger() {
-    set_timer(get_time() + CLOCK_FREQ / TICKS_PER_SEC);
+/// Microseconds in `cycles` of the timer.
+pub fn cycles_to_us(cycles: usize) -> usize {
+    cycles / CLOCK_FREQ * USEC_PER_SEC + cycles % CLOCK_FREQ * USEC_PER_SEC / CLOCK_FREQ
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TimeVal {
+    pub sec: usize,
+    pub usec: usize,
+}
+
+/// Time since boot at the full resolution of the timer, up to a microsecond.
+pub fn get_time_val() -> TimeVal {
+    let time = time::read();
+    TimeVal {
+        sec: time / CLOCK_FREQ,
+        // CLOCK_FREQ need not be a multiple of USEC_PER_SEC
+        usec: time % CLOCK_FREQ * USEC_PER_SEC / CLOCK_FREQ,
+    }
+}
+
+/// cycles of `time` between two ticks
+const TICK_CYCLES: usize = CLOCK_FREQ / TICKS_PER_SEC;
+
+/// When the next tick of the running task is due, in cycles of `time`.
+static NEXT_TICK: AtomicUsize = AtomicUsize::new(0);
+/// timer interrupts taken since boot
+static TIMER_INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Program the timer for the earliest of the next tick, if a task is running
+/// and its time slice has to be counted, and the nearest sleeper to wake.
+/// An idle hart with no sleepers is not woken by the timer at all.
+pub fn set_next_trigger(running: bool) {
+    let tick = running.then(|| NEXT_TICK.load(Ordering::Relaxed));
+    let sleeper = TIMERS.exclusive_session(|timers| {
+        timers
+            .peek()
+            .map(|timer| timer.expire_ms * (CLOCK_FREQ / MSEC_PER_SEC))
+    });
+    let next = match (tick, sleeper) {
+        (Some(tick), Some(sleeper)) => tick.min(sleeper),
+        (tick, sleeper) => tick.or(sleeper).unwrap_or(usize::MAX),
+    };
+    set_timer(next);
+}
+
+/// A task is switched to on an idle hart, it is charged a tick from now on.
+pub fn start_tick() {
+    NEXT_TICK.store(get_time() + TICK_CYCLES, Ordering::Relaxed);
+    set_next_trigger(true);
+}
+
+/// Wake the sleepers due and program the timer for the next event. Return
+/// whether a tick of the running task is due, as the timer also fires for
+/// sleepers in between.
+pub fn handle_timer_interrupt(running: bool) -> bool {
+    TIMER_INTERRUPTS.fetch_add(1, Ordering::Relaxed);
+    check_timer();
+    let now = get_time();
+    let ticked = now >= NEXT_TICK.load(Ordering::Relaxed);
+    if ticked {
+        NEXT_TICK.store(now + TICK_CYCLES, Ordering::Relaxed);
+    }
+    set_next_trigger(running);
+    ticked
+}
+
+/// timer interrupts taken since boot
+pub fn timer_interrupts() -> usize {
+    TIMER_INTERRUPTS.load(Ordering::Relaxed)
 }
 
 pub struct TimerCondVar {
@@ -36,7 +122,7 @@ impl PartialEq for TimerCondVar {
 }
 impl Eq for TimerCondVar {}
 impl PartialOrd for TimerCondVar {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         let a = -(self.expire_ms as isize);
         let b = -(other.expire_ms as isize);
         Some(a.cmp(&b))
@@ -44,7 +130,7 @@ impl PartialOrd for TimerCondVar {
 }
 
 impl Ord for TimerCondVar {
-    fn cmp(&self, other: &Self) -> Ordering {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
         self.partial_cmp(other).unwrap()
     }
 }
@@ -54,9 +140,13 @@ lazy_static! {
         unsafe { UPIntrFreeCell::new(BinaryHeap::<TimerCondVar>::new()) };
 }
 
+/// Wake task at expire_ms. Called by the running task, which keeps being
+/// charged ticks, the timer fires early if the sleeper is due before them.
 pub fn add_timer(expire_ms: usize, task: Arc<TaskControlBlock>) {
     let mut timers = TIMERS.exclusive_access();
     timers.push(TimerCondVar { expire_ms, task });
+    drop(timers);
+    set_next_trigger(true);
 }
 
 pub fn check_timer() {
@@ -72,3 +162,16 @@ pub fn check_timer() {
         }
     });
 }
+
+pub fn remove_timer(task: Arc<TaskControlBlock>) {
+    TIMERS.exclusive_session(|timers| {
+        let mut temp = BinaryHeap::<TimerCondVar>::new();
+        for condvar in timers.drain() {
+            if Arc::as_ptr(&task) != Arc::as_ptr(&condvar.task) {
+                temp.push(condvar);
+            }
+        }
+        timers.clear();
+        timers.append(&mut temp);
+    });
+}
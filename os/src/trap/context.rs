@@ -1,4 +1,8 @@
-use riscv::register::sstatus::{self, Sstatus, SPP};
+use riscv::register::sstatus::{self, Sstatus, FS, SPP};
+
+/// FS field of sstatus
+const SSTATUS_FS_SHIFT: usize = 13;
+const SSTATUS_FS_MASK: usize = 0b11 << SSTATUS_FS_SHIFT;
 
 #[repr(C)]
 #[derive(Debug)]
@@ -9,12 +13,25 @@ pub struct TrapContext {
     pub kernel_satp: usize,
     pub kernel_sp: usize,
     pub trap_handler: usize,
+    /// f0~f31, saved by `__alltraps` only if sstatus.FS is Dirty and restored
+    /// by `__restore` unless it is Off
+    pub f: [usize; 32],
+    pub fcsr: usize,
 }
 
 impl TrapContext {
     pub fn set_sp(&mut self, sp: usize) {
         self.x[2] = sp;
     }
+    /// the thread pointer, to the thread-local storage of the thread
+    pub fn set_tp(&mut self, tp: usize) {
+        self.x[4] = tp;
+    }
+    /// `Sstatus` of the riscv crate has no setter for the FS field
+    pub fn set_fs(&mut self, fs: FS) {
+        let bits = (self.sstatus.bits() & !SSTATUS_FS_MASK) | (fs as usize) << SSTATUS_FS_SHIFT;
+        self.sstatus = unsafe { core::mem::transmute::<usize, Sstatus>(bits) };
+    }
     pub fn app_init_context(
         entry: usize,
         sp: usize,
@@ -32,8 +49,13 @@ impl TrapContext {
             kernel_satp,
             kernel_sp,
             trap_handler,
+            f: [0; 32],
+            fcsr: 0,
         };
         cx.set_sp(sp);
+        // the floating-point unit is enabled at the first use, so that tasks
+        // not using it never save or restore its registers
+        cx.set_fs(FS::Off);
         cx
     }
 }
@@ -0,0 +1,61 @@
+//! Kernel code that may fault on purpose, and where to resume when it does.
+//!
+//! A fault in the kernel is a bug unless it is taken by one of the
+//! instructions listed in the fixup table. Those are resumed at the fixup
+//! address of their entry instead, which reports the failure to the caller.
+
+use crate::config::MEMORY_END;
+use core::arch::global_asm;
+
+global_asm!(include_str!("fixup.S"));
+
+extern "C" {
+    fn __copy_bytes(dst: *mut u8, src: *const u8, len: usize) -> usize;
+    fn __copy_bytes_end();
+    fn __copy_bytes_fixup();
+}
+
+/// Faults taken by the instructions in `[start, end)` resume at `fixup`.
+struct FixupEntry {
+    start: usize,
+    end: usize,
+    fixup: usize,
+}
+
+fn fixup_table() -> [FixupEntry; 1] {
+    [FixupEntry {
+        start: __copy_bytes as usize,
+        end: __copy_bytes_end as usize,
+        fixup: __copy_bytes_fixup as usize,
+    }]
+}
+
+/// Where to resume after a fault taken at `sepc`, if it is expected.
+pub fn search_fixup(sepc: usize) -> Option<usize> {
+    fixup_table()
+        .iter()
+        .find(|entry| (entry.start..entry.end).contains(&sepc))
+        .map(|entry| entry.fixup)
+}
+
+/// Copy `len` bytes from `src` to `dst`, recovering from a fault on either.
+/// Return the number of bytes not copied, which is 0 on success.
+///
+/// # Safety
+///
+/// `dst` must not overlap anything the kernel is using, if it is mapped.
+pub unsafe fn copy_bytes(dst: *mut u8, src: *const u8, len: usize) -> usize {
+    __copy_bytes(dst, src, len)
+}
+
+/// A copy running off the end of memory stops at the first unmapped byte,
+/// and tells how many were left.
+pub fn copy_bytes_test() {
+    let mut buffer = [0u8; 16];
+    let src = (MEMORY_END - 8) as *const u8;
+    assert_eq!(unsafe { copy_bytes(buffer.as_mut_ptr(), src, 16) }, 8);
+    let end = MEMORY_END as *const u8;
+    assert_eq!(unsafe { copy_bytes(buffer.as_mut_ptr(), end, 16) }, 16);
+    assert_eq!(unsafe { copy_bytes(buffer.as_mut_ptr(), src, 8) }, 0);
+    println!("copy_bytes_test passed!");
+}
@@ -1,25 +1,64 @@
 mod context;
+mod fixup;
 
-use crate::config::TRAMPOLINE;
+use crate::config::{KERNEL_STACK_SIZE, PAGE_SIZE, TRAMPOLINE};
 use crate::syscall::syscall;
 use crate::task::{
-    check_signals_of_current, current_add_signal, current_trap_cx, current_trap_cx_user_va,
-    current_user_token, exit_current_and_run_next, suspend_current_and_run_next, SignalFlags,
+    charge_current_runtime, check_signals_of_current, current_add_signal, current_process,
+    current_task, current_trap_cx, current_trap_cx_user_va, current_user_token,
+    exit_current_and_run_next, handle_cow_fault_of_current, handle_lazy_fault_of_current,
+    is_current_stopped, suspend_current_and_run_next, tick_current_task, SignalFlags,
 };
-use crate::timer::{check_timer, set_next_trigger};
+use crate::timer::handle_timer_interrupt;
 use core::arch::{asm, global_asm};
+use core::ops::Range;
+use fixup::search_fixup;
 use riscv::register::{
     mtvec::TrapMode,
     scause::{self, Exception, Interrupt, Trap},
-    sie, sscratch, sstatus, stval, stvec,
+    sie, sscratch,
+    sstatus::{self, FS, SPP},
+    stval, stvec,
 };
 
-global_asm!(include_str!("trap.S"));
+global_asm!(
+    include_str!("trap.S"),
+    page_size = const PAGE_SIZE,
+    kernel_stack_size = const KERNEL_STACK_SIZE,
+);
 
 pub fn init() {
     set_kernel_trap_entry();
 }
 
+/// What `__alltraps_k` finds through sscratch. There is a single hart, and
+/// the saved registers are given back before the trap frame is pushed.
+#[repr(C)]
+struct KernelTrapScratch {
+    handler: usize,
+    /// the stack to handle a trap on when the kernel stack is used up
+    overflow_sp: usize,
+    saved_t0: usize,
+    saved_t1: usize,
+}
+
+const OVERFLOW_STACK_SIZE: usize = 2 * PAGE_SIZE;
+
+static mut OVERFLOW_STACK: [u8; OVERFLOW_STACK_SIZE] = [0; OVERFLOW_STACK_SIZE];
+
+static mut KERNEL_TRAP_SCRATCH: KernelTrapScratch = KernelTrapScratch {
+    handler: 0,
+    overflow_sp: 0,
+    saved_t0: 0,
+    saved_t1: 0,
+};
+
+/// where `trap_from_kernel` runs after a kernel stack overflow
+pub fn overflow_stack_range() -> Range<usize> {
+    let bottom = unsafe { OVERFLOW_STACK.as_ptr() as usize };
+    bottom..bottom + OVERFLOW_STACK_SIZE
+}
+
 fn set_kernel_trap_entry() {
     extern "C" {
         fn __alltraps();
@@ -27,8 +66,10 @@ fn set_kernel_trap_entry() {
     }
     let __alltraps_k_va = __alltraps_k as usize - __alltraps as usize + TRAMPOLINE;
     unsafe {
+        KERNEL_TRAP_SCRATCH.handler = trap_from_kernel as usize;
+        KERNEL_TRAP_SCRATCH.overflow_sp = overflow_stack_range().end;
         stvec::write(__alltraps_k_va, TrapMode::Direct);
-        sscratch::write(trap_from_kernel as usize);
+        sscratch::write(&KERNEL_TRAP_SCRATCH as *const _ as usize);
     }
 }
 
@@ -56,11 +97,52 @@ fn disable_supervisor_interrupt() {
     }
 }
 
+/// How a memory access of a user program faulted, in words.
+fn fault_name(cause: Trap) -> &'static str {
+    match cause {
+        Trap::Exception(Exception::LoadPageFault) => "load page fault",
+        Trap::Exception(Exception::StorePageFault) => "store page fault",
+        Trap::Exception(Exception::InstructionPageFault) => "instruction page fault",
+        Trap::Exception(Exception::LoadFault) => "load access fault",
+        Trap::Exception(Exception::StoreFault) => "store access fault",
+        Trap::Exception(Exception::InstructionFault) => "instruction access fault",
+        Trap::Exception(Exception::StoreMisaligned) => "store address misaligned",
+        Trap::Exception(Exception::InstructionMisaligned) => "instruction address misaligned",
+        _ => "fault",
+    }
+}
+
+/// scause of a misaligned load
+const LOAD_MISALIGNED: usize = 4;
+
+/// Kill the current process for an access to `va` the hardware did not
+/// handle because it is not aligned.
+fn misaligned_access(name: &str, va: usize) {
+    println!(
+        "[kernel] {} at VA:{:#x} in pid {}, sepc = {:#x}",
+        name,
+        va,
+        current_process().getpid(),
+        current_trap_cx().sepc,
+    );
+    current_add_signal(SignalFlags::SIGBUS);
+}
+
 #[no_mangle]
 pub fn trap_handler() -> ! {
     set_kernel_trap_entry();
     let scause = scause::read();
     let stval = stval::read();
+    // the user entry saved the registers over the TrapContext of the current
+    // task, which cannot be trusted any more
+    if sstatus::read().spp() == SPP::Supervisor {
+        panic!(
+            "Trap from kernel through the user entry: {:?}, stval = {:#x}!",
+            scause.cause(),
+            stval
+        );
+    }
+    charge_current_runtime(true);
     // println!("into {:?}", scause.cause());
     match scause.cause() {
         Trap::Exception(Exception::UserEnvCall) => {
@@ -71,34 +153,64 @@ pub fn trap_handler() -> ! {
             enable_supervisor_interrupt();
 
             // get system call return value
-            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12]]);
+            let result = syscall(cx.x[17], [cx.x[10], cx.x[11], cx.x[12], cx.x[13]]);
             // cx is changed during sys_exec, so we have to call it again
             cx = current_trap_cx();
             cx.x[10] = result as usize;
         }
+        // the first write to a page shared by fork
+        Trap::Exception(Exception::StorePageFault) if handle_cow_fault_of_current(stval) => {}
+        // the first access to a page of a lazy area
+        Trap::Exception(Exception::LoadPageFault)
+        | Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::InstructionPageFault)
+            if handle_lazy_fault_of_current(stval) => {}
         Trap::Exception(Exception::StoreFault)
         | Trap::Exception(Exception::StorePageFault)
         | Trap::Exception(Exception::InstructionFault)
         | Trap::Exception(Exception::InstructionPageFault)
         | Trap::Exception(Exception::LoadFault)
         | Trap::Exception(Exception::LoadPageFault) => {
-            /*
             println!(
-                "[kernel] {:?} in application, bad addr = {:#x}, bad instruction = {:#x}, kernel killed it.",
-                scause.cause(),
+                "[kernel] {} at VA:{:#x} in pid {}, sepc = {:#x}",
+                fault_name(scause.cause()),
                 stval,
+                current_process().getpid(),
                 current_trap_cx().sepc,
             );
-            */
+            current_process().inner_exclusive_access().memory_set.dump();
             current_add_signal(SignalFlags::SIGSEGV);
         }
+        // the first floating-point instruction of the task, enable the unit
+        // with the registers zeroed and run the instruction again
+        Trap::Exception(Exception::IllegalInstruction)
+            if current_trap_cx().sstatus.fs() == FS::Off =>
+        {
+            current_trap_cx().set_fs(FS::Initial);
+        }
+        // stval holds the bits of the instruction
         Trap::Exception(Exception::IllegalInstruction) => {
+            println!(
+                "[kernel] illegal instruction {:#x} in pid {}, sepc = {:#x}",
+                stval,
+                current_process().getpid(),
+                current_trap_cx().sepc,
+            );
             current_add_signal(SignalFlags::SIGILL);
         }
+        Trap::Exception(Exception::InstructionMisaligned)
+        | Trap::Exception(Exception::StoreMisaligned) => {
+            misaligned_access(fault_name(scause.cause()), stval);
+        }
+        // the riscv crate does not decode load address misaligned
+        Trap::Exception(Exception::Unknown) if scause.code() == LOAD_MISALIGNED => {
+            misaligned_access("load address misaligned", stval);
+        }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
-            set_next_trigger();
-            check_timer();
-            suspend_current_and_run_next();
+            // round robin, the task keeps running until its slice is used up
+            if handle_timer_interrupt(true) && tick_current_task() {
+                suspend_current_and_run_next();
+            }
         }
         Trap::Interrupt(Interrupt::SupervisorExternal) => {
             crate::board::irq_handler();
@@ -111,10 +223,17 @@ pub fn trap_handler() -> ! {
             );
         }
     }
-    // check signals
-    if let Some((errno, msg)) = check_signals_of_current() {
-        println!("[kernel] {}", msg);
-        exit_current_and_run_next(errno);
+    // check signals, a stopped process gives the cpu away until it is
+    // continued or killed
+    loop {
+        if let Some((errno, msg)) = check_signals_of_current() {
+            println!("[kernel] {}", msg);
+            exit_current_and_run_next(errno);
+        }
+        if !is_current_stopped() {
+            break;
+        }
+        suspend_current_and_run_next();
     }
     trap_return();
 }
@@ -122,6 +241,7 @@ pub fn trap_handler() -> ! {
 #[no_mangle]
 pub fn trap_return() -> ! {
     disable_supervisor_interrupt();
+    charge_current_runtime(false);
     set_user_trap_entry();
     let trap_cx_user_va = current_trap_cx_user_va();
     let user_satp = current_user_token();
@@ -143,20 +263,76 @@ pub fn trap_return() -> ! {
     }
 }
 
+/// `__alltraps_k` saves only `x` but sp/tp, `sstatus` and `sepc` of the
+/// context, so the rest is never printed.
+fn dump_kernel_trap(trap_cx: &TrapContext) {
+    println!(
+        "[kernel] sepc = {:#x}, sstatus = {:?}",
+        trap_cx.sepc, trap_cx.sstatus
+    );
+    for (i, regs) in trap_cx.x.chunks(4).enumerate() {
+        println!(
+            "[kernel] x{:<2} {:#018x} {:#018x} {:#018x} {:#018x}",
+            i * 4,
+            regs[0],
+            regs[1],
+            regs[2],
+            regs[3]
+        );
+    }
+}
+
 #[no_mangle]
-pub fn trap_from_kernel(_trap_cx: &TrapContext) {
+pub fn trap_from_kernel(trap_cx: &mut TrapContext) {
     let scause = scause::read();
     let stval = stval::read();
+    // the trap came with no room left on the kernel stack
+    if overflow_stack_range().contains(&(trap_cx as *const _ as usize)) {
+        let pid = current_task()
+            .and_then(|task| task.process.upgrade())
+            .map(|process| process.getpid());
+        dump_kernel_trap(trap_cx);
+        panic!(
+            "kernel stack overflow for pid {:?}, {:?}, bad addr = {:#x}, bad instruction = {:#x}!",
+            pid,
+            scause.cause(),
+            stval,
+            trap_cx.sepc
+        );
+    }
     match scause.cause() {
+        Trap::Exception(Exception::StoreFault)
+        | Trap::Exception(Exception::StorePageFault)
+        | Trap::Exception(Exception::LoadFault)
+        | Trap::Exception(Exception::LoadPageFault) => match search_fixup(trap_cx.sepc) {
+            Some(fixup) => trap_cx.sepc = fixup,
+            None => {
+                dump_kernel_trap(trap_cx);
+                panic!(
+                    "{:?} in kernel, bad addr = {:#x}, bad instruction = {:#x}!",
+                    scause.cause(),
+                    stval,
+                    trap_cx.sepc
+                );
+            }
+        },
         Trap::Interrupt(Interrupt::SupervisorExternal) => {
             crate::board::irq_handler();
         }
         Trap::Interrupt(Interrupt::SupervisorTimer) => {
-            set_next_trigger();
-            check_timer();
+            // the hart may be idle, and then only sleepers are due
+            handle_timer_interrupt(current_task().is_some());
             // do not schedule now
         }
+        Trap::Exception(Exception::IllegalInstruction) => {
+            dump_kernel_trap(trap_cx);
+            panic!(
+                "Illegal instruction {:#x} in kernel, bad instruction = {:#x}!",
+                stval, trap_cx.sepc
+            );
+        }
         _ => {
+            dump_kernel_trap(trap_cx);
             panic!(
                 "Unsupported trap from kernel: {:?}, stval = {:#x}!",
                 scause.cause(),
@@ -167,3 +343,4 @@ pub fn trap_from_kernel(_trap_cx: &TrapContext) {
 }
 
 pub use context::TrapContext;
+pub use fixup::{copy_bytes, copy_bytes_test};
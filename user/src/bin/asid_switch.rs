@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, tlb_stats, waitpid, yield_, TlbStats};
+
+const YIELDS: usize = 200;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        for _ in 0..YIELDS {
+            yield_();
+        }
+        exit(0);
+    }
+    let mut before = TlbStats::default();
+    assert_eq!(tlb_stats(&mut before), 0);
+    for _ in 0..YIELDS {
+        yield_();
+    }
+    let mut after = TlbStats::default();
+    assert_eq!(tlb_stats(&mut after), 0);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // switching between the two spaces keeps their translations, only the
+    // child exiting may flush
+    let full_flushes = after.full_flushes - before.full_flushes;
+    println!(
+        "{} full flushes over {} switches, {} ASID rollovers",
+        full_flushes,
+        2 * YIELDS,
+        after.asid_rollovers - before.asid_rollovers
+    );
+    assert!(full_flushes < YIELDS / 10);
+    println!("asid_switch passed!");
+    0
+}
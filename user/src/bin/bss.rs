@@ -0,0 +1,25 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+/// uninitialized, it is in the bss and spans several pages
+const LEN: usize = 5 * 4096 + 123;
+static mut ZEROED: [u8; LEN] = [0; LEN];
+/// initialized, it is in the data segment before the bss
+static mut DATA: [u8; 4] = [1, 2, 3, 4];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let zeroed = unsafe { &mut *core::ptr::addr_of_mut!(ZEROED) };
+    assert!(zeroed.iter().all(|&byte| byte == 0));
+    assert_eq!(unsafe { DATA }, [1, 2, 3, 4]);
+    // every page is mapped and writable
+    for (i, byte) in zeroed.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    assert!(zeroed.iter().enumerate().all(|(i, &byte)| byte == i as u8));
+    println!("bss passed!");
+    0
+}
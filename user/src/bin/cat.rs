@@ -7,18 +7,21 @@ extern crate alloc;
 
 use user_lib::{close, open, read, OpenFlags};
 
+const STDIN: usize = 0;
+
+/// print the file named by the argument, or stdin without one
 #[no_mangle]
 pub fn main(argc: usize, argv: &[&str]) -> i32 {
-    println!("argc = {}", argc);
-    for (i, arg) in argv.iter().enumerate() {
-        println!("argv[{}] = {}", i, arg);
-    }
-    assert!(argc == 2);
-    let fd = open(argv[1], OpenFlags::RDONLY);
-    if fd == -1 {
-        panic!("Error occurred when opening file");
-    }
-    let fd = fd as usize;
+    assert!(argc <= 2);
+    let fd = if argc == 2 {
+        let fd = open(argv[1], OpenFlags::RDONLY);
+        if fd == -1 {
+            panic!("Error occurred when opening file");
+        }
+        fd as usize
+    } else {
+        STDIN
+    };
     let mut buf = [0u8; 256];
     loop {
         let size = read(fd, &mut buf) as usize;
@@ -27,6 +30,8 @@ pub fn main(argc: usize, argv: &[&str]) -> i32 {
         }
         print!("{}", core::str::from_utf8(&buf[..size]).unwrap());
     }
-    close(fd);
+    if fd != STDIN {
+        close(fd);
+    }
     0
 }
@@ -0,0 +1,86 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{clone, close, dup, exit, waitpid, waittid, CLONE_FILES, CLONE_VM, EBADF, EINVAL};
+
+const STACK_SIZE: usize = 0x4000;
+
+#[repr(align(16))]
+struct Stack([u8; STACK_SIZE]);
+
+static mut STACK: Stack = Stack([0; STACK_SIZE]);
+static mut VALUE: usize = 0;
+
+/// set VALUE, open a new fd, which is the exit code, and close fd
+fn child(fd: usize) -> ! {
+    unsafe {
+        VALUE = fd;
+    }
+    let new_fd = dup(0);
+    assert_eq!(close(fd), 0);
+    exit(new_fd as i32)
+}
+
+/// exit with 1 if running on STACK
+fn on_stack(_: usize) -> ! {
+    let local = 0u8;
+    let addr = &local as *const u8 as usize;
+    let base = unsafe { core::ptr::addr_of!(STACK) as usize };
+    exit((base..base + STACK_SIZE).contains(&addr) as i32)
+}
+
+fn stack_top() -> usize {
+    unsafe { core::ptr::addr_of!(STACK) as usize + STACK_SIZE }
+}
+
+/// Clone a child with flags, which closes an fd of the caller and opens
+/// another, and return whether it shared the memory and the fds.
+fn clone_child(flags: usize) -> (bool, bool) {
+    unsafe {
+        VALUE = 0;
+    }
+    let fd = dup(0);
+    assert!(fd > 0);
+    let id = clone(flags, 0, child as usize, fd as usize);
+    assert!(id > 0);
+    let new_fd = if flags & CLONE_VM != 0 {
+        waittid(id as usize)
+    } else {
+        let mut exit_code = 0;
+        assert_eq!(waitpid(id as usize, &mut exit_code), id);
+        exit_code as isize
+    };
+    assert!(new_fd > 0);
+    let shared_memory = unsafe { VALUE } == fd as usize;
+    // the fd closed by the child, and the one it opened
+    let shared_fds = close(fd as usize) == -EBADF;
+    assert_eq!(close(new_fd as usize) == 0, shared_fds);
+    (shared_memory, shared_fds)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(clone_child(0), (false, false));
+    assert_eq!(clone_child(CLONE_FILES), (false, true));
+    assert_eq!(clone_child(CLONE_VM | CLONE_FILES), (true, true));
+
+    // the stack given overrides the default one
+    let pid = clone(0, stack_top(), on_stack as usize, 0);
+    assert!(pid > 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 1);
+    let tid = clone(CLONE_VM | CLONE_FILES, stack_top(), on_stack as usize, 0);
+    assert!(tid > 0);
+    assert_eq!(waittid(tid as usize), 1);
+
+    // threads share the fd table of the process, and need an entry
+    assert_eq!(clone(CLONE_VM, 0, child as usize, 0), -EINVAL);
+    assert_eq!(clone(CLONE_VM | CLONE_FILES, 0, 0, 0), -EINVAL);
+    assert_eq!(clone(1, 0, 0, 0), -EINVAL);
+    println!("clone passed!");
+    0
+}
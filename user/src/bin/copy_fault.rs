@@ -0,0 +1,40 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, fault_inject, getpid, timerfd_create, timerfd_settime, ITimerSpec, CLOCK_MONOTONIC,
+    EFAULT, FAULT_USER_COPY,
+};
+
+/// Only initproc may arm faults, so this test is run by hand in place of
+/// initproc, in a debug kernel with FAULT_INJECTION, and is not part of
+/// usertests. The kernel checks the same recovery at boot, see
+/// `copy_bytes_test` and `copy_from_user_test`.
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = timerfd_create(CLOCK_MONOTONIC, 0);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let spec = ITimerSpec {
+        interval_ms: 0,
+        value_ms: 1000,
+    };
+    let pid = getpid();
+
+    // the kernel faults while reading spec and recovers
+    assert_eq!(fault_inject(FAULT_USER_COPY, 1, 100), 0);
+    assert_eq!(timerfd_settime(fd, &spec, None), -EFAULT);
+    // the user context is intact
+    assert_eq!(getpid(), pid);
+
+    // only the armed copy fails
+    let mut old = ITimerSpec::default();
+    assert_eq!(timerfd_settime(fd, &spec, Some(&mut old)), 0);
+    assert_eq!(old.value_ms, 0);
+    close(fd);
+    println!("copy_fault passed!");
+    0
+}
@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, pipe, read, waitpid, write};
+
+const LEN: usize = 64;
+
+/// lands in .data, a writable user page shared by fork
+static mut SHARED: [u8; LEN] = [1; LEN];
+
+fn shared() -> &'static mut [u8; LEN] {
+    unsafe { &mut SHARED }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let on_stack = [3u8; LEN];
+    let pid = fork();
+    if pid == 0 {
+        // both pages are copied on the first write
+        shared()[0] = 2;
+        assert_eq!(shared()[0], 2);
+        assert!(shared()[1..].iter().all(|&b| b == 1));
+        let mut copy = on_stack;
+        copy[0] = 4;
+        assert_eq!(copy[0], 4);
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    // the parent still sees its own copy
+    assert!(shared().iter().all(|&b| b == 1));
+    assert!(on_stack.iter().all(|&b| b == 3));
+
+    let pid = fork();
+    if pid == 0 {
+        // the kernel writing into a shared page copies it as well
+        assert_eq!(read(fds[0], &mut shared()[..4]), 4);
+        assert_eq!(&shared()[..4], b"cow!");
+        exit(0);
+    }
+    assert_eq!(write(fds[1], b"cow!"), 4);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert!(shared().iter().all(|&b| b == 1));
+    close(fds[0]);
+    close(fds[1]);
+
+    // no one else shares the page any more, it is written in place
+    shared()[0] = 5;
+    assert_eq!(shared()[0], 5);
+    println!("cow_fork passed!");
+    0
+}
@@ -0,0 +1,60 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::{read_volatile, write_volatile};
+use user_lib::{get_time, gettimeofday, sleep, task_info, TaskInfo, TimeVal};
+
+const BUSY_MS: isize = 200;
+/// time in us the kernel takes around the syscalls measuring the times
+const SLACK_US: usize = 5_000;
+
+fn times() -> (usize, usize) {
+    let mut info = TaskInfo::default();
+    assert_eq!(task_info(&mut info), 0);
+    (info.user_time_us, info.kernel_time_us)
+}
+
+fn now_us() -> usize {
+    let mut tv = TimeVal::default();
+    assert_eq!(gettimeofday(&mut tv), 0);
+    tv.sec * 1_000_000 + tv.usec
+}
+
+/// Spin in user mode, asking the kernel for the time now and then.
+fn busy_for(ms: isize) {
+    let start = get_time();
+    let mut x: usize = 0;
+    while get_time() - start < ms {
+        for i in 0..10_000 {
+            unsafe { write_volatile(&mut x, read_volatile(&x).wrapping_add(i)) };
+        }
+    }
+}
+
+/// A busy loop is charged to user mode almost entirely, and no more than
+/// the time it took, a sleep to neither user mode nor the kernel.
+#[no_mangle]
+pub fn main() -> i32 {
+    let (user, kernel) = times();
+    let start = now_us();
+    busy_for(BUSY_MS);
+    let elapsed = now_us() - start;
+    let (busy_user, busy_kernel) = times();
+    let (user_delta, kernel_delta) = (busy_user - user, busy_kernel - kernel);
+    println!(
+        "busy for {} us: {} us in user mode, {} us in the kernel",
+        elapsed, user_delta, kernel_delta
+    );
+    assert!(user_delta + kernel_delta <= elapsed + SLACK_US);
+    assert!(user_delta >= elapsed / 2);
+    assert!(user_delta > kernel_delta);
+
+    sleep(BUSY_MS as usize);
+    let (slept_user, slept_kernel) = times();
+    assert!(slept_user - busy_user + slept_kernel - busy_kernel <= SLACK_US);
+    println!("cpu_time passed!");
+    0
+}
@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{chdir, close, exit, fork, getcwd, mkdir, open, read, waitpid, write, OpenFlags};
+
+const CONTENT: &[u8] = b"found by a relative name\n";
+
+fn assert_cwd(expected: &str) {
+    let mut buf = [0u8; 64];
+    let len = getcwd(&mut buf);
+    assert_eq!(len, expected.len() as isize);
+    assert_eq!(&buf[..len as usize], expected.as_bytes());
+    assert_eq!(buf[len as usize], 0);
+}
+
+fn read_file(path: &str) -> Option<([u8; 64], usize)> {
+    let fd = open(path, OpenFlags::RDONLY);
+    if fd < 0 {
+        return None;
+    }
+    let mut buf = [0u8; 64];
+    let len = read(fd as usize, &mut buf);
+    close(fd as usize);
+    Some((buf, len as usize))
+}
+
+fn assert_content(path: &str) {
+    let (buf, len) = read_file(path).unwrap();
+    assert_eq!(&buf[..len], CONTENT);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_cwd("/");
+    // left over by an earlier run otherwise
+    mkdir("cwd_dir\0");
+    assert_eq!(mkdir("cwd_dir\0"), -1);
+    assert_eq!(mkdir("cwd_missing/dir\0"), -1);
+    assert_eq!(chdir("cwd_missing\0"), -1);
+    assert_eq!(open("cwd_dir\0", OpenFlags::WRONLY), -1);
+
+    assert_eq!(chdir("cwd_dir\0"), 0);
+    assert_cwd("/cwd_dir");
+    let fd = open("cwd_file\0", OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd >= 0);
+    assert_eq!(write(fd as usize, CONTENT), CONTENT.len() as isize);
+    close(fd as usize);
+    assert_content("cwd_file\0");
+    assert_content("./cwd_file\0");
+    assert_content("../cwd_dir/./cwd_file\0");
+    assert_content("/cwd_dir/cwd_file\0");
+    assert_eq!(chdir("cwd_file\0"), -1);
+
+    // inherited by children
+    let pid = fork();
+    if pid == 0 {
+        assert_cwd("/cwd_dir");
+        assert_content("cwd_file\0");
+        exit(0);
+    }
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    assert_eq!(chdir("..\0"), 0);
+    assert_cwd("/");
+    assert!(read_file("cwd_file\0").is_none());
+    assert_content("cwd_dir/cwd_file\0");
+    assert_eq!(chdir("/../cwd_dir/..\0"), 0);
+    assert_cwd("/");
+
+    let mut short = [0u8; 1];
+    assert_eq!(getcwd(&mut short), -1);
+    println!("cwd passed!");
+    0
+}
@@ -0,0 +1,36 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::get_cycles;
+
+const ITERATIONS: usize = 1_000_000;
+
+/// a loop the compiler keeps, each iteration takes a few instructions
+fn busy_loop(iterations: usize) {
+    let mut counter = 0usize;
+    for i in 0..iterations {
+        unsafe {
+            core::ptr::write_volatile(&mut counter, i);
+        }
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let first = get_cycles();
+    let second = get_cycles();
+    assert!(first <= second);
+    let start = get_cycles();
+    busy_loop(ITERATIONS);
+    let delta = get_cycles() - start;
+    println!("{} iterations took {} cycles", ITERATIONS, delta);
+    // at least a cycle per iteration, and not so many that the loop would
+    // have taken minutes, preemption by other tasks included
+    assert!(delta >= ITERATIONS);
+    assert!(delta < ITERATIONS * 10_000);
+    println!("cycles passed!");
+    0
+}
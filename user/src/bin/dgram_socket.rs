@@ -0,0 +1,51 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    bind, close, exit, fork, recv, sendto, socket, waitpid, AF_UNIX, ECONNREFUSED, SOCK_DGRAM,
+};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(socket(AF_UNIX, 1, 0), -1);
+    let server = socket(AF_UNIX, SOCK_DGRAM, 0);
+    assert!(server >= 0);
+    let server = server as usize;
+    assert_eq!(bind(server, "dgram_server\0"), 0);
+    // a name can only be bound once
+    let other = socket(AF_UNIX, SOCK_DGRAM, 0) as usize;
+    assert_eq!(bind(other, "dgram_server\0"), -1);
+    // nobody listens on this name
+    assert_eq!(sendto(other, b"lost", "dgram_nobody\0"), -ECONNREFUSED);
+
+    let pid = fork();
+    if pid == 0 {
+        let client = socket(AF_UNIX, SOCK_DGRAM, 0) as usize;
+        assert_eq!(sendto(client, b"hello", "dgram_server\0"), 5);
+        assert_eq!(sendto(client, b"datagram world", "dgram_server\0"), 14);
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // both datagrams are queued, but each recv returns exactly one of them
+    let mut buf = [0u8; 64];
+    assert_eq!(recv(server, &mut buf), 5);
+    assert_eq!(&buf[..5], b"hello");
+    assert_eq!(recv(server, &mut buf), 14);
+    assert_eq!(&buf[..14], b"datagram world");
+    // a short buffer truncates the datagram
+    assert_eq!(sendto(other, b"truncated", "dgram_server\0"), 9);
+    assert_eq!(recv(server, &mut buf[..5]), 5);
+    assert_eq!(&buf[..5], b"trunc");
+
+    // the name is released with the socket
+    close(server);
+    assert_eq!(bind(other, "dgram_server\0"), 0);
+    println!("dgram_socket passed!");
+    0
+}
@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::{exec, exit, fork, waitpid, E2BIG};
+
+/// together longer than a page, but they fit in the user stack
+const LONG_ARGS: usize = 3;
+const LONG_ARG_LEN: usize = 1500;
+const CHILD_EXIT_CODE: i32 = 42;
+
+fn long_arg(i: usize) -> String {
+    let mut arg: String = (0..LONG_ARG_LEN)
+        .map(|j| (b'a' + ((i + j) % 26) as u8) as char)
+        .collect();
+    arg.push('\0');
+    arg
+}
+
+/// fork and exec this app in the child with args, return its exit code
+fn run_child(path: &str, args: &[*const u8]) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exec(path, args);
+        panic!("exec {} failed", path);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+#[no_mangle]
+pub fn main(argc: usize, argv: &[&str]) -> i32 {
+    // run by the test below with the long arguments
+    if argc == LONG_ARGS + 1 {
+        for (i, arg) in argv[1..].iter().enumerate() {
+            assert_eq!(*arg, &long_arg(i)[..LONG_ARG_LEN]);
+        }
+        exit(CHILD_EXIT_CODE);
+    }
+
+    // an empty argv is passed on as argc = 0
+    assert_eq!(run_child("cmdline_args\0", &[core::ptr::null()]), 0);
+
+    let args: Vec<String> = (0..LONG_ARGS).map(long_arg).collect();
+    let mut argv: Vec<*const u8> = Vec::new();
+    argv.push("exec_args\0".as_ptr());
+    argv.extend(args.iter().map(|arg| arg.as_ptr()));
+    argv.push(core::ptr::null());
+    assert_eq!(run_child("exec_args\0", &argv), CHILD_EXIT_CODE);
+
+    // more than the user stack can hold, this process is left as it is
+    let huge: String = core::iter::repeat('x')
+        .take(8192)
+        .chain(Some('\0'))
+        .collect();
+    assert_eq!(
+        exec("exec_args\0", &[huge.as_ptr(), core::ptr::null()]),
+        -E2BIG
+    );
+    println!("exec_args passed!");
+    0
+}
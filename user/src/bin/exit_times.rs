@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, task_info, thread_create, waitpid, waittid, TaskInfo};
+
+const CHILDREN: usize = 8;
+const BUSY_MS: isize = 20;
+
+fn spin() {
+    let start = get_time();
+    while get_time() - start < BUSY_MS {}
+    let mut info = TaskInfo::default();
+    assert_eq!(task_info(&mut info), 0);
+    assert!(info.user_time_us + info.kernel_time_us > 0);
+}
+
+fn thread_main() -> ! {
+    spin();
+    exit(2)
+}
+
+/// Threads and processes that have run for a while go through the exit
+/// path, which reads their times.
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..CHILDREN {
+        let pid = fork();
+        if pid == 0 {
+            let tid = thread_create(thread_main as usize, 0);
+            spin();
+            assert_eq!(waittid(tid as usize), 2);
+            exit(1);
+        }
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, 1);
+    }
+    println!("exit_times passed!");
+    0
+}
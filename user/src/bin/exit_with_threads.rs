@@ -0,0 +1,35 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{sleep, thread_create, yield_};
+
+fn spin() -> ! {
+    loop {
+        yield_();
+    }
+}
+
+fn nap() -> ! {
+    loop {
+        sleep(5);
+    }
+}
+
+/// The main thread exits while the other threads are still ready or
+/// sleeping. They must be torn down with the process instead of being
+/// scheduled again on a freed trap context.
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..3 {
+        thread_create(spin as usize, 0);
+    }
+    thread_create(nap as usize, 0);
+    for _ in 0..10 {
+        yield_();
+    }
+    println!("main thread exits with other threads alive");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exec, exit, fault_inject, fork, waitpid, EPERM, FAULT_ELF_PARSE, FAULT_FRAME_ALLOC,
+    FAULT_KSTACK_OVERFLOW, FAULT_USER_COPY,
+};
+
+/// Only initproc may arm faults, so every request is refused here. The
+/// injected failures are checked by the kernel at boot.
+#[no_mangle]
+pub fn main() -> i32 {
+    for kind in [
+        FAULT_FRAME_ALLOC,
+        FAULT_ELF_PARSE,
+        FAULT_USER_COPY,
+        FAULT_KSTACK_OVERFLOW,
+        99,
+    ] {
+        assert_eq!(fault_inject(kind, 1, 100), -EPERM);
+    }
+    assert_eq!(fault_inject(FAULT_ELF_PARSE, 0, 0), -EPERM);
+
+    // nothing was armed
+    let pid = fork();
+    if pid == 0 {
+        let args = ["hello_world\0".as_ptr(), core::ptr::null::<u8>()];
+        exec("hello_world\0", &args);
+        exit(-1);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    println!("fault_inject passed!");
+    0
+}
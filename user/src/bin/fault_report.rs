@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, waitpid};
+
+/// where the kernel is loaded, not mapped in user space
+const KERNEL_BASE: usize = 0x8020_0000;
+
+fn store_null() {
+    unsafe { core::ptr::null_mut::<u8>().write_volatile(0) };
+}
+
+fn load_null() {
+    unsafe { core::ptr::null::<u8>().read_volatile() };
+}
+
+fn jump_null() {
+    unsafe { core::arch::asm!("jr zero", options(noreturn)) };
+}
+
+fn store_kernel() {
+    unsafe { (KERNEL_BASE as *mut u8).write_volatile(0) };
+}
+
+/// Each of them is reported by the kernel, which kills only the child.
+#[no_mangle]
+pub fn main() -> i32 {
+    let faults: [fn(); 4] = [store_null, load_null, jump_null, store_kernel];
+    for fault in faults {
+        let pid = fork();
+        if pid == 0 {
+            fault();
+            exit(0);
+        }
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, -11);
+    }
+    println!("fault_report passed!");
+    0
+}
@@ -21,6 +21,8 @@ pub fn main() -> i32 {
     let fd = fd as usize;
     let mut buffer = [0u8; 100];
     let read_len = read(fd, &mut buffer) as usize;
+    // nothing is left past the end of file
+    assert_eq!(read(fd, &mut buffer), 0);
     close(fd);
 
     assert_eq!(test_str, core::str::from_utf8(&buffer[..read_len]).unwrap(),);
@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fincore, open, read, write, OpenFlags, EBADF, EINVAL, PAGE_SIZE};
+
+const FILE: &str = "fincore_file\0";
+const PAGES: usize = 8;
+/// the user stack is too small for buffers of a whole page
+const CHUNK: usize = 512;
+
+/// read a file to the end, which fills the block cache with its blocks
+fn read_through(name: &str) {
+    let fd = open(name, OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; CHUNK];
+    while read(fd as usize, &mut buf) > 0 {}
+    close(fd as usize);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open(FILE, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    for i in 0..PAGES * PAGE_SIZE / CHUNK {
+        let chunk = [(i * CHUNK / PAGE_SIZE) as u8; CHUNK];
+        assert_eq!(write(fd, &chunk), CHUNK as isize);
+    }
+    close(fd);
+    // push the blocks just written out of the cache, which is much smaller
+    // than the test programs
+    read_through("usertests\0");
+
+    let fd = open(FILE, OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let mut buf = [0xffu8; CHUNK];
+    for _ in 0..PAGE_SIZE / CHUNK {
+        assert_eq!(read(fd, &mut buf), CHUNK as isize);
+        assert!(buf.iter().all(|&b| b == 0));
+    }
+    // only the page read is resident, one more page past the end is not
+    let mut vec = [0xffu8; PAGES + 1];
+    assert_eq!(fincore(fd, 0, (PAGES + 1) * PAGE_SIZE, &mut vec), 0);
+    assert_eq!(vec[0], 1);
+    assert!(vec[1..].iter().all(|&r| r == 0));
+    // a partial last page still gets a byte, the rest of vec is untouched
+    let mut vec = [0xffu8; 2];
+    assert_eq!(fincore(fd, 0, 1, &mut vec), 0);
+    assert_eq!(vec, [1, 0xff]);
+    // ranges past the end of file
+    let mut vec = [0xffu8; 2];
+    assert_eq!(fincore(fd, 100 * PAGE_SIZE, 2 * PAGE_SIZE, &mut vec), 0);
+    assert_eq!(vec, [0, 0]);
+
+    assert_eq!(fincore(fd, 1, PAGE_SIZE, &mut vec), -EINVAL);
+    assert_eq!(fincore(1, 0, PAGE_SIZE, &mut vec), -EINVAL);
+    assert_eq!(fincore(99, 0, PAGE_SIZE, &mut vec), -EBADF);
+    close(fd);
+    println!("fincore passed!");
+    0
+}
@@ -0,0 +1,86 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, setrlimit, sleep, thread_create, wait, waitpid, waittid, EAGAIN, RLIMIT_NPROC,
+};
+
+fn thread_exit() -> ! {
+    exit(0)
+}
+
+/// every process forks until it is refused, then exits
+fn bomb() -> ! {
+    while fork() >= 0 {}
+    exit(0)
+}
+
+/// the orphans of the bomb are reaped by initproc, retry until there is
+/// room for a child again
+fn fork_after_bomb() -> isize {
+    for _ in 0..1000 {
+        let pid = fork();
+        if pid != -EAGAIN {
+            return pid;
+        }
+        sleep(10);
+    }
+    -EAGAIN
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // zombies count until they are reaped
+    let mut children = 0;
+    let err = loop {
+        let pid = fork();
+        if pid == 0 {
+            exit(0);
+        }
+        if pid < 0 {
+            break pid;
+        }
+        children += 1;
+    };
+    assert_eq!(err, -EAGAIN);
+    assert!(children > 0);
+    println!("refused after {} children", children);
+    let mut exit_code = 0;
+    for _ in 0..children {
+        assert!(wait(&mut exit_code) > 0);
+    }
+    assert_eq!(wait(&mut exit_code), -1);
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+
+    // a runaway is stopped and the kernel recovers once it is gone
+    let pid = fork();
+    if pid == 0 {
+        bomb();
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    let pid = fork_after_bomb();
+    if pid == 0 {
+        exit(0);
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+
+    // threads count against RLIMIT_NPROC too
+    assert_eq!(setrlimit(RLIMIT_NPROC, 1), 0);
+    let tid = thread_create(thread_exit as usize, 0);
+    assert!(tid > 0);
+    assert_eq!(thread_create(thread_exit as usize, 0), -EAGAIN);
+    assert_eq!(fork(), -EAGAIN);
+    assert_eq!(waittid(tid as usize), 0);
+    println!("fork_bomb passed!");
+    0
+}
@@ -0,0 +1,72 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{exit, fork, getpid, wait};
+
+const TASKS: usize = 3;
+const ROUNDS: usize = 200;
+const SYSCALL_YIELD: usize = 124;
+
+/// Load f0~f31 and fflags from regs and fflags, yield, then store them back.
+fn yield_with_fp(regs: &mut [f64; 32], fflags: usize) -> usize {
+    let after: usize;
+    unsafe {
+        asm!(
+            "csrw fflags, a2",
+            ".irp n, 0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31",
+            "fld f\\n, \\n*8(a1)",
+            ".endr",
+            "ecall",
+            ".irp n, 0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31",
+            "fsd f\\n, \\n*8(a1)",
+            ".endr",
+            "csrr a3, fflags",
+            in("a1") regs.as_mut_ptr(),
+            in("a2") fflags,
+            lateout("a3") after,
+            in("a7") SYSCALL_YIELD,
+            out("f8") _, out("f9") _, out("f18") _, out("f19") _,
+            out("f20") _, out("f21") _, out("f22") _, out("f23") _,
+            out("f24") _, out("f25") _, out("f26") _, out("f27") _,
+            clobber_abi("C"),
+        );
+    }
+    after
+}
+
+/// Every child keeps its own values in all floating-point registers and
+/// fcsr across many yields to the others, and an accumulator in Rust.
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..TASKS {
+        let pid = fork();
+        if pid == 0 {
+            let seed = getpid() as f64;
+            let mut acc = 0.0f64;
+            for round in 0..ROUNDS {
+                let mut regs = [0.0f64; 32];
+                for (i, reg) in regs.iter_mut().enumerate() {
+                    *reg = seed * 1000.0 + (round * 32 + i) as f64 / 8.0;
+                }
+                let expected = regs;
+                let fflags = (getpid() as usize + round) % 32;
+                assert_eq!(yield_with_fp(&mut regs, fflags), fflags);
+                assert_eq!(regs, expected);
+                acc += seed * 0.5;
+            }
+            assert_eq!(acc, seed * 0.5 * ROUNDS as f64);
+            exit(0);
+        }
+    }
+    for _ in 0..TASKS {
+        let mut exit_code: i32 = 0;
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    println!("fp_switch passed!");
+    0
+}
@@ -0,0 +1,49 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, meminfo, waitpid, HeapStats};
+
+const ROUNDS: usize = 10;
+const PAGE_SIZE: usize = 0x1000;
+
+static mut DATA: [u8; 4 * PAGE_SIZE] = [0; 4 * PAGE_SIZE];
+
+fn frames() -> usize {
+    let mut stats = HeapStats::default();
+    assert_eq!(meminfo(&mut stats), 0);
+    stats.frames
+}
+
+/// The child copies some pages shared with the parent before it exits.
+fn fork_and_reap() {
+    let pid = fork();
+    if pid == 0 {
+        for page in 0..4 {
+            unsafe {
+                DATA[page * PAGE_SIZE] = page as u8 + 1;
+            }
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+/// Every frame a child takes is freed once it is reaped.
+#[no_mangle]
+pub fn main() -> i32 {
+    // the first child may grow the page table of the kernel space for its
+    // kernel stack, which keeps the frames
+    fork_and_reap();
+    let baseline = frames();
+    for _ in 0..ROUNDS {
+        fork_and_reap();
+        assert_eq!(frames(), baseline);
+    }
+    println!("frame_leak passed!");
+    0
+}
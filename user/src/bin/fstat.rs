@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fstat, open, pipe, write, OpenFlags, Stat, StatMode, EBADF};
+
+const STDIN: usize = 0;
+const STDOUT: usize = 1;
+const N: usize = 1000;
+
+fn stat_of(fd: usize) -> Stat {
+    let mut st = Stat::default();
+    assert_eq!(fstat(fd, &mut st), 0);
+    st
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open("fstat_file\0", OpenFlags::CREATE | OpenFlags::WRONLY) as usize;
+    assert_eq!(stat_of(fd).size, 0);
+    let data = [0x5au8; N];
+    assert_eq!(write(fd, &data), N as isize);
+    let st = stat_of(fd);
+    assert_eq!(st.mode, StatMode::FILE);
+    assert_eq!(st.size, N as u64);
+    assert_eq!(st.nlink, 1);
+    assert_ne!(st.ino, 0);
+    // the same inode through another fd
+    let other = open("fstat_file\0", OpenFlags::RDONLY) as usize;
+    assert_eq!(stat_of(other).ino, st.ino);
+    assert_eq!(stat_of(other).size, N as u64);
+    close(other);
+    close(fd);
+
+    let dir = open("/\0", OpenFlags::RDONLY) as usize;
+    let st = stat_of(dir);
+    assert_eq!(st.mode, StatMode::DIR);
+    assert_eq!(st.ino, 0);
+    close(dir);
+
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    for fd in fds {
+        let st = stat_of(fd);
+        assert_eq!(st.mode, StatMode::FIFO);
+        assert_eq!(st.size, 0);
+        close(fd);
+    }
+    assert_eq!(stat_of(STDIN).mode, StatMode::CHR);
+    assert_eq!(stat_of(STDOUT).mode, StatMode::CHR);
+
+    let mut st = Stat::default();
+    assert_eq!(fstat(fds[0], &mut st), -EBADF);
+    assert_eq!(fstat(usize::MAX, &mut st), -EBADF);
+    println!("fstat passed!");
+    0
+}
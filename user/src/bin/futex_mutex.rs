@@ -0,0 +1,77 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+use user_lib::{exit, futex_wait, futex_wake, thread_create, waittid, yield_};
+
+const THREADS: usize = 2;
+const ROUNDS: usize = 1000;
+const SPINS: usize = 100;
+const EAGAIN: isize = 11;
+
+/// 0 unlocked, 1 locked, 2 locked and some thread may be blocked
+static LOCK: AtomicU32 = AtomicU32::new(0);
+static mut COUNTER: usize = 0;
+
+fn lock() {
+    for _ in 0..SPINS {
+        if LOCK
+            .compare_exchange(0, 1, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            return;
+        }
+        core::hint::spin_loop();
+    }
+    // from now on the lock is left contended, so the unlocker always wakes
+    while LOCK.swap(2, Ordering::Acquire) != 0 {
+        futex_wait(&LOCK, 2);
+    }
+}
+
+fn unlock() {
+    if LOCK.swap(0, Ordering::Release) == 2 {
+        futex_wake(&LOCK, 1);
+    }
+}
+
+fn worker() -> ! {
+    for i in 0..ROUNDS {
+        lock();
+        let counter = unsafe { COUNTER };
+        // give up the cpu while holding the lock to make the others block
+        if i % 10 == 0 {
+            yield_();
+        }
+        unsafe {
+            COUNTER = counter + 1;
+        }
+        unlock();
+    }
+    exit(0)
+}
+
+/// Threads contending on a mutex built on futex never lose a wakeup, or
+/// some of them would stay blocked forever.
+#[no_mangle]
+pub fn main() -> i32 {
+    let word = AtomicU32::new(1);
+    assert_eq!(futex_wait(&word, 0), -EAGAIN);
+    assert_eq!(futex_wake(&word, 1), 0);
+
+    let tids: Vec<isize> = (0..THREADS)
+        .map(|_| thread_create(worker as usize, 0))
+        .collect();
+    for tid in tids {
+        assert_eq!(waittid(tid as usize), 0);
+    }
+    assert_eq!(unsafe { COUNTER }, THREADS * ROUNDS);
+    assert_eq!(LOCK.load(Ordering::Relaxed), 0);
+    println!("futex_mutex passed!");
+    0
+}
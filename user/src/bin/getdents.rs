@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::{close, getdents, mkdir, open, Dirents, OpenFlags};
+
+const DIR: &str = "getdents_dir\0";
+const NAMES: [&str; 3] = ["apple", "banana", "a_rather_long_file_name"];
+const EINVAL: isize = 22;
+
+/// Every name in the directory, reading through a buffer of buf_len bytes.
+fn ls(buf_len: usize) -> Vec<String> {
+    let fd = open(DIR, OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 256];
+    let mut names = Vec::new();
+    loop {
+        let len = getdents(fd as usize, &mut buf[..buf_len]);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        for (_, name) in Dirents::new(&buf[..len as usize]) {
+            names.push(String::from(name));
+        }
+    }
+    // the end stays the end
+    assert_eq!(getdents(fd as usize, &mut buf[..buf_len]), 0);
+    close(fd as usize);
+    names
+}
+
+/// Listing a directory with three files returns each name exactly once,
+/// whether the records are read one at a time or all together.
+#[no_mangle]
+pub fn main() -> i32 {
+    // left over from an earlier run otherwise
+    mkdir(DIR);
+    for name in NAMES {
+        let path = alloc::format!("getdents_dir/{}\0", name);
+        let fd = open(&path, OpenFlags::CREATE | OpenFlags::WRONLY);
+        assert!(fd >= 0);
+        close(fd as usize);
+    }
+
+    // the longest name fits in 8 + 23 + 1 bytes, so one record at a time
+    for buf_len in [32, 256] {
+        let names = ls(buf_len);
+        assert_eq!(names.len(), NAMES.len());
+        for name in NAMES {
+            assert_eq!(names.iter().filter(|n| n.as_str() == name).count(), 1);
+        }
+    }
+
+    let fd = open(DIR, OpenFlags::RDONLY) as usize;
+    let mut buf = [0u8; 8];
+    assert_eq!(getdents(fd, &mut buf), -EINVAL);
+    close(fd);
+    let fd = open("getdents_dir/apple\0", OpenFlags::RDONLY) as usize;
+    assert_eq!(getdents(fd, &mut buf), -1);
+    close(fd);
+    println!("getdents passed!");
+    0
+}
@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, getpid, getppid, pipe, read, waitpid, write, yield_};
+
+/// pid of initproc, which adopts orphans
+const INITPROC_PID: isize = 0;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    assert!(getppid() >= 0);
+    assert_ne!(getppid(), pid);
+
+    let child = fork();
+    if child == 0 {
+        println!("child: pid = {}, ppid = {}", getpid(), getppid());
+        assert_eq!(getppid(), pid);
+        exit(getpid() as i32);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    // the child saw the pid fork gave to the parent
+    assert_eq!(exit_code as isize, child);
+
+    // a grandchild outliving its parent is adopted by initproc
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let middle = fork();
+    if middle == 0 {
+        let middle_pid = getpid();
+        if fork() == 0 {
+            close(pipe_fd[0]);
+            while getppid() == middle_pid {
+                yield_();
+            }
+            let ppid = getppid();
+            write(pipe_fd[1], &ppid.to_ne_bytes());
+            exit(0);
+        }
+        exit(0);
+    }
+    close(pipe_fd[1]);
+    assert_eq!(waitpid(middle as usize, &mut exit_code), middle);
+    let mut buf = [0u8; core::mem::size_of::<isize>()];
+    assert_eq!(read(pipe_fd[0], &mut buf), buf.len() as isize);
+    close(pipe_fd[0]);
+    assert_eq!(isize::from_ne_bytes(buf), INITPROC_PID);
+    println!("getppid passed!");
+    0
+}
@@ -0,0 +1,37 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{gettimeofday, sleep, TimeVal};
+
+const PAGE_SIZE: usize = 0x1000;
+
+#[repr(C, align(4096))]
+struct TwoPages([u8; 2 * PAGE_SIZE]);
+
+static mut PAGES: TwoPages = TwoPages([0; 2 * PAGE_SIZE]);
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut first = TimeVal::default();
+    assert_eq!(gettimeofday(&mut first), 0);
+    assert!(first.usec < 1_000_000);
+    let mut second = TimeVal::default();
+    assert_eq!(gettimeofday(&mut second), 0);
+    assert!(second >= first);
+    sleep(20);
+    let mut third = TimeVal::default();
+    assert_eq!(gettimeofday(&mut third), 0);
+    let elapsed_us = (third.sec - second.sec) * 1_000_000 + third.usec - second.usec;
+    assert!(elapsed_us >= 20_000);
+
+    // sec at the end of one page, usec at the start of the next
+    let straddling = unsafe { &mut *(PAGES.0.as_mut_ptr().add(PAGE_SIZE - 8) as *mut TimeVal) };
+    assert_eq!(gettimeofday(straddling), 0);
+    assert!(*straddling >= third);
+    assert!(straddling.usec < 1_000_000);
+    println!("gettimeofday passed!");
+    0
+}
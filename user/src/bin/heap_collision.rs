@@ -0,0 +1,55 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{mmap, munmap, sbrk, PAGE_SIZE, PROT_READ, PROT_WRITE};
+
+/// pages between the program break and the mapping in the room of the heap
+const GAP_PAGES: usize = 4;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let brk = sbrk(0);
+    assert!(brk > 0);
+    let brk = brk as usize;
+    let heap_end = (brk + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    let fixed = heap_end + GAP_PAGES * PAGE_SIZE;
+    assert_eq!(mmap(fixed, PAGE_SIZE, PROT_READ | PROT_WRITE), 0);
+    unsafe {
+        (fixed as *mut usize).write_volatile(fixed);
+    }
+
+    // the heap grows right up to the mapping, touching it is fine
+    assert_eq!(sbrk((fixed - brk) as isize), brk as isize);
+    for addr in (heap_end..fixed).step_by(PAGE_SIZE) {
+        unsafe {
+            (addr as *mut usize).write_volatile(addr);
+        }
+    }
+    // but not a byte into it, and the failed grows change nothing
+    assert_eq!(sbrk(1), -1);
+    assert_eq!(sbrk(PAGE_SIZE as isize), -1);
+    assert_eq!(sbrk(0), fixed as isize);
+    assert_eq!(unsafe { (fixed as *const usize).read_volatile() }, fixed);
+    for addr in (heap_end..fixed).step_by(PAGE_SIZE) {
+        assert_eq!(unsafe { (addr as *const usize).read_volatile() }, addr);
+    }
+    // nor is anything mapped over the heap
+    assert_eq!(mmap(fixed - PAGE_SIZE, PAGE_SIZE, PROT_READ), -1);
+
+    // the heap goes on once the mapping is gone
+    assert_eq!(munmap(fixed, PAGE_SIZE), 0);
+    assert_eq!(sbrk(PAGE_SIZE as isize), fixed as isize);
+    unsafe {
+        (fixed as *mut usize).write_volatile(0);
+    }
+    assert_eq!(
+        sbrk(-((fixed + PAGE_SIZE - brk) as isize)),
+        (fixed + PAGE_SIZE) as isize
+    );
+    assert_eq!(sbrk(0), brk as isize);
+    println!("heap_collision passed!");
+    0
+}
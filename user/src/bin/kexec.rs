@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{kexec, EPERM, KEXEC_LOAD_ADDR};
+
+/// A position independent image equivalent to
+/// `QEMU_EXIT_HANDLE.exit_success()`:
+///     lui t0, 0x100           # VIRT_TEST
+///     lui t1, 0x5
+///     addi t1, t1, 0x555      # EXIT_SUCCESS
+///     sw t1, 0(t0)
+///     j .
+static EXIT_SUCCESS_IMAGE: [u32; 5] = [
+    0x0010_02b7,
+    0x0000_5337,
+    0x5553_0313,
+    0x0062_a023,
+    0x0000_006f,
+];
+
+fn image() -> &'static [u8] {
+    unsafe {
+        core::slice::from_raw_parts(
+            EXIT_SUCCESS_IMAGE.as_ptr() as *const u8,
+            core::mem::size_of_val(&EXIT_SUCCESS_IMAGE),
+        )
+    }
+}
+
+/// Only initproc may kexec, and only in a debug kernel with KEXEC set, so
+/// every request from here is refused before it is looked at. The handoff
+/// itself is run by `make kexec-test` in os, with the same image.
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(kexec(image(), KEXEC_LOAD_ADDR), -EPERM);
+    assert_eq!(kexec(&image()[..0], KEXEC_LOAD_ADDR), -EPERM);
+    let unmapped = unsafe { core::slice::from_raw_parts(0x20_0000_0000 as *const u8, 4) };
+    assert_eq!(kexec(unmapped, KEXEC_LOAD_ADDR), -EPERM);
+    println!("kexec passed!");
+    0
+}
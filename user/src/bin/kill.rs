@@ -0,0 +1,43 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, kill, sleep, waitpid, waitpid_nb, SignalFlags};
+
+/// how long the stopped child would spin for if it kept running
+const BUSY_MS: isize = 100;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(kill(usize::MAX, SignalFlags::SIGKILL.bits()), -1);
+
+    let deadline = get_time() + BUSY_MS;
+    let pid = fork();
+    if pid == 0 {
+        while get_time() < deadline {}
+        exit(7);
+    }
+    assert_eq!(kill(pid as usize, 1 << 31), -1);
+    assert_eq!(kill(pid as usize, SignalFlags::SIGSTOP.bits()), 0);
+    // past the deadline, but the child could not run to see it
+    sleep(3 * BUSY_MS as usize);
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid_nb(pid as usize, &mut exit_code), -2);
+    assert_eq!(kill(pid as usize, SignalFlags::SIGCONT.bits()), 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+
+    let pid = fork();
+    if pid == 0 {
+        #[allow(clippy::empty_loop)]
+        loop {}
+    }
+    sleep(10);
+    assert_eq!(kill(pid as usize, SignalFlags::SIGKILL.bits()), 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -9);
+    println!("kill passed!");
+    0
+}
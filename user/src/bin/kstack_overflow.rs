@@ -0,0 +1,21 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{fault_inject, getpid, FAULT_KSTACK_OVERFLOW};
+
+/// The kernel is expected to panic with "kernel stack overflow for pid
+/// Some(N)" and halt. Only initproc may arm faults, so this test is run by
+/// hand in place of initproc, in a debug kernel with FAULT_INJECTION, and is
+/// not part of usertests.
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    println!("overflowing the kernel stack of pid {}", pid);
+    // arming it overflows the stack of this very syscall
+    fault_inject(FAULT_KSTACK_OVERFLOW, 1, 100);
+    println!("kstack_overflow failed: the kernel returned!");
+    -1
+}
@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{exit, fork, pipe, read, waitpid, write, PAGE_SIZE};
+
+/// USER_STACK_SIZE of the kernel, the stack is mapped lazily
+const STACK_SIZE: usize = PAGE_SIZE * 2;
+
+/// run f in a child and return its exit code
+fn in_child(f: fn(usize)) -> i32 {
+    let sp: usize;
+    unsafe {
+        asm!("mv {}, sp", out(reg) sp);
+    }
+    // main is shallow, so sp is still in the top page of the stack
+    let top = (sp + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    let pid = fork();
+    if pid == 0 {
+        f(top);
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn touch_edges(top: usize) {
+    let bottom = top - STACK_SIZE;
+    // the kernel maps the untouched bottom page to write to it
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    assert_eq!(write(fds[1], b"lazy"), 4);
+    let buf = unsafe { core::slice::from_raw_parts_mut(bottom as *mut u8, 4) };
+    assert_eq!(read(fds[0], buf), 4);
+    assert_eq!(buf, b"lazy");
+    // first and last byte of the range and of each page
+    for addr in [bottom, bottom + PAGE_SIZE - 1, bottom + PAGE_SIZE, top - 1] {
+        let ptr = addr as *mut u8;
+        // the top of the stack holds the arguments
+        unsafe {
+            let old = ptr.read_volatile();
+            ptr.write_volatile(0x5a);
+            assert_eq!(ptr.read_volatile(), 0x5a);
+            ptr.write_volatile(old);
+        }
+    }
+}
+
+fn below_bottom(top: usize) {
+    unsafe {
+        ((top - STACK_SIZE - 1) as *mut u8).write_volatile(0);
+    }
+}
+
+fn above_top(top: usize) {
+    unsafe {
+        (top as *const u8).read_volatile();
+    }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(in_child(touch_edges), 0);
+    // the guard pages around the stack are not part of the lazy area
+    assert_eq!(in_child(below_bottom), -11);
+    assert_eq!(in_child(above_top), -11);
+    println!("lazy_stack passed!");
+    0
+}
@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+#[macro_use]
+extern crate user_lib;
+
+use alloc::string::String;
+use user_lib::console::{LineEditor, HISTORY_SIZE};
+
+const UP: &[u8] = b"\x1b[A";
+const DOWN: &[u8] = b"\x1b[B";
+
+/// feed every byte, return the last finished line
+fn feed_all(editor: &mut LineEditor, input: &[u8]) -> Option<String> {
+    let mut finished = None;
+    for &c in input {
+        if let Some(line) = editor.feed(c) {
+            finished = Some(line);
+        }
+    }
+    finished
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut editor = LineEditor::new();
+    assert_eq!(feed_all(&mut editor, b"first\r").unwrap(), "first");
+    assert_eq!(feed_all(&mut editor, b"second\r").unwrap(), "second");
+    // two ups recall the first line into the current one
+    feed_all(&mut editor, b"par");
+    assert!(feed_all(&mut editor, UP).is_none());
+    assert_eq!(editor.line(), "second");
+    feed_all(&mut editor, UP);
+    assert_eq!(editor.line(), "first");
+    // nothing older to recall
+    feed_all(&mut editor, UP);
+    assert_eq!(editor.line(), "first");
+    // down walks back to the line being typed
+    feed_all(&mut editor, DOWN);
+    assert_eq!(editor.line(), "second");
+    feed_all(&mut editor, DOWN);
+    assert_eq!(editor.line(), "par");
+    // a recalled line can be edited and entered
+    feed_all(&mut editor, UP);
+    feed_all(&mut editor, b"\x7f\x7f\x7fnd");
+    assert_eq!(feed_all(&mut editor, b"\n").unwrap(), "secnd");
+
+    // a partial sequence leaves the line alone until it completes
+    feed_all(&mut editor, b"ab\x1b");
+    assert_eq!(editor.line(), "ab");
+    feed_all(&mut editor, b"[");
+    assert_eq!(editor.line(), "ab");
+    feed_all(&mut editor, b"A");
+    assert_eq!(editor.line(), "secnd");
+    // ESC not followed by '[' is dropped, unknown sequences are ignored
+    feed_all(&mut editor, b"\x1bx\x1b[C");
+    assert_eq!(editor.line(), "secndx");
+    feed_all(&mut editor, b"\r");
+
+    // only the latest HISTORY_SIZE lines are kept
+    let mut editor = LineEditor::new();
+    for i in 0..HISTORY_SIZE + 2 {
+        feed_all(&mut editor, &[b'a' + i as u8, b'\r']);
+    }
+    for _ in 0..HISTORY_SIZE + 2 {
+        feed_all(&mut editor, UP);
+    }
+    assert_eq!(editor.line().as_bytes(), &[b'c']);
+    println!("\nline_history passed!");
+    0
+}
@@ -0,0 +1,97 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, dup, lseek, open, pipe, read, write, OpenFlags, EBADF, EINVAL, ESPIPE, SEEK_CUR,
+    SEEK_END, SEEK_SET,
+};
+
+const LEN: usize = 1000;
+
+fn byte_at(i: usize) -> u8 {
+    (i % 251) as u8
+}
+
+fn read_at_offset(fd: usize, len: usize) -> [u8; 16] {
+    let mut buf = [0u8; 16];
+    assert_eq!(read(fd, &mut buf[..len]), len as isize);
+    buf
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open(
+        "lseek_file\0",
+        OpenFlags::CREATE | OpenFlags::RDWR | OpenFlags::TRUNC,
+    );
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let data: [u8; LEN] = core::array::from_fn(byte_at);
+    assert_eq!(write(fd, &data), LEN as isize);
+    assert_eq!(lseek(fd, 0, SEEK_CUR), LEN as isize);
+
+    // the bytes in the middle are the ones written there
+    assert_eq!(lseek(fd, 500, SEEK_SET), 500);
+    let buf = read_at_offset(fd, 16);
+    assert!((0..16).all(|i| buf[i] == byte_at(500 + i)));
+    assert_eq!(lseek(fd, -32, SEEK_CUR), 484);
+    let buf = read_at_offset(fd, 4);
+    assert!((0..4).all(|i| buf[i] == byte_at(484 + i)));
+    assert_eq!(lseek(fd, -10, SEEK_END), (LEN - 10) as isize);
+    let buf = read_at_offset(fd, 10);
+    assert!((0..10).all(|i| buf[i] == byte_at(LEN - 10 + i)));
+    assert_eq!(read(fd, &mut [0u8; 16]), 0);
+
+    // overwrite in place
+    assert_eq!(lseek(fd, 100, SEEK_SET), 100);
+    assert_eq!(write(fd, b"seek"), 4);
+    assert_eq!(lseek(fd, 98, SEEK_SET), 98);
+    let buf = read_at_offset(fd, 8);
+    assert_eq!(
+        buf[..8],
+        [
+            byte_at(98),
+            byte_at(99),
+            b's',
+            b'e',
+            b'e',
+            b'k',
+            byte_at(104),
+            byte_at(105)
+        ]
+    );
+
+    // a dup'ed fd shares the offset, a file opened again does not
+    let shared = dup(fd) as usize;
+    assert_eq!(lseek(fd, 10, SEEK_SET), 10);
+    assert_eq!(lseek(shared, 0, SEEK_CUR), 10);
+    let other = open("lseek_file\0", OpenFlags::RDONLY) as usize;
+    assert_eq!(lseek(other, 0, SEEK_CUR), 0);
+    assert_eq!(read_at_offset(other, 1)[0], byte_at(0));
+    assert_eq!(lseek(fd, 0, SEEK_CUR), 10);
+    close(other);
+    close(shared);
+
+    // past the end, the hole reads as zeros
+    assert_eq!(lseek(fd, 8, SEEK_END), (LEN + 8) as isize);
+    assert_eq!(write(fd, b"x"), 1);
+    assert_eq!(lseek(fd, LEN as isize, SEEK_SET), LEN as isize);
+    let buf = read_at_offset(fd, 9);
+    assert_eq!(buf[..9], *b"\0\0\0\0\0\0\0\0x");
+
+    assert_eq!(lseek(fd, -1, SEEK_SET), -EINVAL);
+    assert_eq!(lseek(fd, -(LEN as isize) - 10, SEEK_END), -EINVAL);
+    assert_eq!(lseek(fd, 0, 3), -EINVAL);
+    close(fd);
+    assert_eq!(lseek(fd, 0, SEEK_SET), -EBADF);
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    assert_eq!(lseek(fds[0], 0, SEEK_SET), -ESPIPE);
+    close(fds[0]);
+    close(fds[1]);
+    println!("lseek passed!");
+    0
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, meminfo, waitpid, HeapStats, EFAULT};
+
+/// lands in .rodata, which is mapped readable only
+static READ_ONLY: HeapStats = HeapStats {
+    allocated: 0,
+    peak: 0,
+    allocs: 0,
+    failed: 0,
+    frames: 0,
+};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = HeapStats::default();
+    assert_eq!(meminfo(&mut before), 0);
+    assert!(before.allocated > 0);
+    assert!(before.peak >= before.allocated);
+    assert!(before.allocs > 0);
+    assert!(before.frames > 0);
+    let read_only = &READ_ONLY as *const HeapStats as *mut HeapStats;
+    assert_eq!(meminfo(unsafe { &mut *read_only }), -EFAULT);
+
+    // a new process takes some kernel objects
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    let mut after = HeapStats::default();
+    assert_eq!(meminfo(&mut after), 0);
+    assert!(after.allocs > before.allocs);
+    assert!(after.peak >= before.peak);
+    assert!(after.peak >= after.allocated);
+    println!(
+        "kernel heap: {} bytes allocated, {} bytes at peak, {} allocations, {} failed",
+        after.allocated, after.peak, after.allocs, after.failed
+    );
+    println!("meminfo passed!");
+    0
+}
@@ -0,0 +1,31 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, set_mempolicy, thread_create, waitpid, waittid, EINVAL};
+
+fn thread_exit() -> ! {
+    exit(3)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_mempolicy(0), 0);
+    // there is a single memory node
+    assert_eq!(set_mempolicy(1), -EINVAL);
+    // frames are still allocated as before: a thread and a child process
+    let tid = thread_create(thread_exit as usize, 0);
+    assert_eq!(waittid(tid as usize), 3);
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(set_mempolicy(0), 0);
+        exit(7);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 7);
+    println!("mempolicy passed!");
+    0
+}
@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, waitpid, EINVAL, PAGE_SIZE, PROT_EXEC, PROT_READ, PROT_WRITE};
+
+/// far from the app, its heap and its stacks
+const START: usize = 0x1000_0000;
+const LEN: usize = 3 * PAGE_SIZE;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START + 1, LEN, PROT_READ), -1);
+    assert_eq!(mmap(START, LEN, 0), -1);
+    assert_eq!(mmap(START, LEN, PROT_READ | 1 << 3), -1);
+    assert_eq!(mmap(START, 0, PROT_READ), -EINVAL);
+    assert_eq!(mmap(START, usize::MAX - START, PROT_READ), -EINVAL);
+
+    assert_eq!(mmap(START, LEN, PROT_READ | PROT_WRITE), 0);
+    let pages = unsafe { core::slice::from_raw_parts_mut(START as *mut u8, LEN) };
+    assert!(pages.iter().all(|&b| b == 0));
+    for (i, byte) in pages.iter_mut().enumerate() {
+        *byte = (i / PAGE_SIZE + i) as u8;
+    }
+    assert!(pages
+        .iter()
+        .enumerate()
+        .all(|(i, &b)| b == (i / PAGE_SIZE + i) as u8));
+    // the range is taken, wholly or in part
+    assert_eq!(mmap(START, LEN, PROT_READ | PROT_WRITE), -1);
+    assert_eq!(mmap(START + LEN - PAGE_SIZE, LEN, PROT_READ), -1);
+    assert_eq!(mmap(START - PAGE_SIZE, 2 * PAGE_SIZE, PROT_EXEC), -1);
+
+    // a read-only page cannot be written
+    let read_only = START + 2 * LEN;
+    assert_eq!(mmap(read_only, PAGE_SIZE, PROT_READ), 0);
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            assert_eq!((read_only as *const u8).read_volatile(), 0);
+            (read_only as *mut u8).write_volatile(1);
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -11);
+    println!("mmap passed!");
+    0
+}
@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, munmap, waitpid, EINVAL, PAGE_SIZE, PROT_READ, PROT_WRITE};
+
+/// far from the app, its heap and its stacks
+const START: usize = 0x1000_0000;
+
+/// whether reading `addr` in a child faults
+fn faults(addr: usize) -> bool {
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            (addr as *const u8).read_volatile();
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code == -11
+}
+
+/// map `len` bytes at START, which must take exactly one page
+fn maps_one_page(len: usize) {
+    assert_eq!(mmap(START, len, PROT_READ | PROT_WRITE), 0);
+    unsafe {
+        ((START + PAGE_SIZE - 1) as *mut u8).write_volatile(1);
+    }
+    assert!(faults(START + PAGE_SIZE));
+    // the whole page is taken
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_READ), -1);
+    // the same rounding for munmap, two pages are not the mapping
+    assert_eq!(munmap(START, PAGE_SIZE + 1), -1);
+    assert_eq!(munmap(START, len), 0);
+    assert!(faults(START));
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // zero len
+    assert_eq!(mmap(START, 0, PROT_READ), -EINVAL);
+    assert_eq!(munmap(START, 0), -EINVAL);
+    // overflowing, and past the end of the address space
+    assert_eq!(mmap(START, usize::MAX - START + 1, PROT_READ), -EINVAL);
+    assert_eq!(munmap(START, usize::MAX - START + 1), -EINVAL);
+    assert_eq!(mmap(START, usize::MAX - START, PROT_READ), -EINVAL);
+    assert_eq!(munmap(START, usize::MAX - START), -EINVAL);
+    // a byte, a page less a byte and exactly a page
+    maps_one_page(1);
+    maps_one_page(PAGE_SIZE - 1);
+    maps_one_page(PAGE_SIZE);
+    // a page and a byte take two
+    assert_eq!(mmap(START, PAGE_SIZE + 1, PROT_READ | PROT_WRITE), 0);
+    assert!(!faults(START + 2 * PAGE_SIZE - 1));
+    assert!(faults(START + 2 * PAGE_SIZE));
+    assert_eq!(munmap(START, PAGE_SIZE), -1);
+    assert_eq!(munmap(START, 2 * PAGE_SIZE), 0);
+    println!("mmap_bounds passed!");
+    0
+}
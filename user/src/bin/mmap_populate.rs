@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    mincore, mmap, mmap_flags, munmap, EINVAL, ENOMEM, MAP_POPULATE, PAGE_SIZE, PROT_READ,
+    PROT_WRITE,
+};
+
+/// far from the app, its heap and its stacks
+const START: usize = 0x1000_0000;
+const PAGES: usize = 4;
+const LEN: usize = PAGES * PAGE_SIZE;
+
+fn resident() -> [u8; PAGES] {
+    let mut vec = [0xffu8; PAGES];
+    assert_eq!(mincore(START, LEN, &mut vec), 0);
+    vec
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut vec = [0xffu8; PAGES];
+    assert_eq!(mincore(START, LEN, &mut vec), -ENOMEM);
+    assert_eq!(mincore(START + 1, LEN, &mut vec), -EINVAL);
+    assert_eq!(mmap_flags(START, LEN, PROT_READ, 1 << 30), -EINVAL);
+
+    // without MAP_POPULATE a page gets its frame once touched
+    assert_eq!(mmap(START, LEN, PROT_READ | PROT_WRITE), 0);
+    assert_eq!(resident(), [0; PAGES]);
+    unsafe {
+        ((START + 2 * PAGE_SIZE) as *mut u8).write_volatile(1);
+    }
+    assert_eq!(resident(), [0, 0, 1, 0]);
+    // a range going past the mapping
+    assert_eq!(
+        mincore(START, LEN + PAGE_SIZE, &mut [0; PAGES + 1]),
+        -ENOMEM
+    );
+    assert_eq!(munmap(START, LEN), 0);
+
+    // with it every page has its zeroed frame before it is touched
+    assert_eq!(
+        mmap_flags(START, LEN, PROT_READ | PROT_WRITE, MAP_POPULATE),
+        0
+    );
+    assert_eq!(resident(), [1; PAGES]);
+    let pages = unsafe { core::slice::from_raw_parts(START as *const u8, LEN) };
+    assert!(pages.iter().all(|&b| b == 0));
+    assert_eq!(munmap(START, LEN), 0);
+    assert_eq!(mincore(START, LEN, &mut vec), -ENOMEM);
+    println!("mmap_populate passed!");
+    0
+}
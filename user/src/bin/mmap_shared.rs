@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, mincore, mmap_flags, waitpid, EINVAL, MAP_ANONYMOUS, MAP_PRIVATE, MAP_SHARED,
+    PAGE_SIZE, PROT_READ, PROT_WRITE,
+};
+
+/// far from the app, its heap and its stacks
+const SHARED: usize = 0x1000_0000;
+const PRIVATE: usize = SHARED + 4 * PAGE_SIZE;
+const LEN: usize = 2 * PAGE_SIZE;
+
+fn read(addr: usize) -> u8 {
+    unsafe { (addr as *const u8).read_volatile() }
+}
+
+fn write(addr: usize, value: u8) {
+    unsafe { (addr as *mut u8).write_volatile(value) }
+}
+
+/// run `f` in a child, which must exit with 0
+fn in_child(f: fn()) {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let rw = PROT_READ | PROT_WRITE;
+    assert_eq!(
+        mmap_flags(SHARED, LEN, rw, MAP_SHARED | MAP_PRIVATE),
+        -EINVAL
+    );
+    assert_eq!(mmap_flags(SHARED, LEN, rw, MAP_SHARED | MAP_ANONYMOUS), 0);
+    assert_eq!(mmap_flags(PRIVATE, LEN, rw, MAP_PRIVATE | MAP_ANONYMOUS), 0);
+    // shared pages get their frames up front, for the children to map them
+    let mut vec = [0u8; 2];
+    assert_eq!(mincore(SHARED, LEN, &mut vec), 0);
+    assert_eq!(vec, [1, 1]);
+    // one private page touched before fork, one after
+    write(PRIVATE, 1);
+
+    in_child(|| {
+        assert_eq!(read(PRIVATE), 1);
+        for addr in [SHARED, SHARED + PAGE_SIZE, PRIVATE, PRIVATE + PAGE_SIZE] {
+            write(addr, 0x5a);
+        }
+    });
+    assert_eq!(read(SHARED), 0x5a);
+    assert_eq!(read(SHARED + PAGE_SIZE), 0x5a);
+    assert_eq!(read(PRIVATE), 1);
+    assert_eq!(read(PRIVATE + PAGE_SIZE), 0);
+
+    // and the other way round
+    write(SHARED, 7);
+    in_child(|| {
+        assert_eq!(read(SHARED), 7);
+        write(SHARED, 9);
+    });
+    assert_eq!(read(SHARED), 9);
+    println!("mmap_shared passed!");
+    0
+}
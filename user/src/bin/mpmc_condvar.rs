@@ -0,0 +1,84 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use user_lib::exit;
+use user_lib::{
+    condvar_create, condvar_signal, condvar_wait, mutex_blocking_create, mutex_lock, mutex_unlock,
+};
+use user_lib::{thread_create, waittid};
+
+const MUTEX: usize = 0;
+const NOT_FULL: usize = 0;
+const NOT_EMPTY: usize = 1;
+const BUFFER_SIZE: usize = 4;
+const PRODUCER_COUNT: usize = 3;
+const CONSUMER_COUNT: usize = 3;
+const NUMBER_PER_PRODUCER: usize = 60;
+const TOTAL: usize = PRODUCER_COUNT * NUMBER_PER_PRODUCER;
+
+static mut BUFFER: [usize; BUFFER_SIZE] = [0; BUFFER_SIZE];
+static mut FRONT: usize = 0;
+static mut COUNT: usize = 0;
+/// how many times each item has been consumed
+static mut SEEN: [usize; TOTAL] = [0; TOTAL];
+
+unsafe fn producer(id: *const usize) -> ! {
+    let id = *id;
+    for i in 0..NUMBER_PER_PRODUCER {
+        mutex_lock(MUTEX);
+        while COUNT == BUFFER_SIZE {
+            condvar_wait(NOT_FULL, MUTEX);
+        }
+        BUFFER[(FRONT + COUNT) % BUFFER_SIZE] = id * NUMBER_PER_PRODUCER + i;
+        COUNT += 1;
+        condvar_signal(NOT_EMPTY);
+        mutex_unlock(MUTEX);
+    }
+    exit(0)
+}
+
+unsafe fn consumer() -> ! {
+    for _ in 0..TOTAL / CONSUMER_COUNT {
+        mutex_lock(MUTEX);
+        while COUNT == 0 {
+            condvar_wait(NOT_EMPTY, MUTEX);
+        }
+        SEEN[BUFFER[FRONT]] += 1;
+        FRONT = (FRONT + 1) % BUFFER_SIZE;
+        COUNT -= 1;
+        condvar_signal(NOT_FULL);
+        mutex_unlock(MUTEX);
+    }
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mutex_blocking_create() as usize, MUTEX);
+    assert_eq!(condvar_create() as usize, NOT_FULL);
+    assert_eq!(condvar_create() as usize, NOT_EMPTY);
+    let ids: Vec<_> = (0..PRODUCER_COUNT).collect();
+    let mut threads = Vec::new();
+    for id in ids.iter() {
+        threads.push(thread_create(producer as usize, id as *const _ as usize));
+    }
+    for _ in 0..CONSUMER_COUNT {
+        threads.push(thread_create(consumer as usize, 0));
+    }
+    for thread in threads.iter() {
+        waittid(*thread as usize);
+    }
+    // every item went through the buffer exactly once
+    unsafe {
+        assert_eq!(COUNT, 0);
+        assert!(SEEN.iter().all(|&seen| seen == 1));
+    }
+    println!("mpmc_condvar passed!");
+    0
+}
@@ -0,0 +1,58 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, waitpid, PAGE_SIZE, PROT_READ, PROT_WRITE};
+
+/// far from the app, its heap and its stacks
+const START: usize = 0x1000_0000;
+const LEN: usize = 3 * PAGE_SIZE;
+
+fn page(i: usize) -> *mut u8 {
+    (START + i * PAGE_SIZE) as *mut u8
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(mmap(START, LEN, PROT_READ | PROT_WRITE), 0);
+    for i in 0..3 {
+        unsafe { page(i).write_volatile(i as u8 + 1) };
+    }
+    assert_eq!(mprotect(START + 1, PAGE_SIZE, PROT_READ), -1);
+    assert_eq!(mprotect(START, 0, PROT_READ), -1);
+    assert_eq!(mprotect(START, LEN, 0), -1);
+    // the range runs past the mapping
+    assert_eq!(mprotect(START, LEN + PAGE_SIZE, PROT_READ), -1);
+    assert_eq!(mprotect(START - PAGE_SIZE, 2 * PAGE_SIZE, PROT_READ), -1);
+
+    // only the middle page becomes read-only, its neighbours stay writable
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(mprotect(START + PAGE_SIZE, PAGE_SIZE, PROT_READ), 0);
+        unsafe {
+            page(0).write_volatile(10);
+            page(2).write_volatile(30);
+            assert_eq!(page(0).read_volatile(), 10);
+            assert_eq!(page(1).read_volatile(), 2);
+            assert_eq!(page(2).read_volatile(), 30);
+            page(1).write_volatile(20);
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -11);
+    // the parent is not affected, and its pages can be made writable again
+    assert_eq!(mprotect(START, LEN, PROT_READ), 0);
+    assert_eq!(mprotect(START, LEN, PROT_READ | PROT_WRITE), 0);
+    for i in 0..3 {
+        unsafe {
+            assert_eq!(page(i).read_volatile(), i as u8 + 1);
+            page(i).write_volatile(0);
+        }
+    }
+    println!("mprotect passed!");
+    0
+}
@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, munmap, waitpid, PAGE_SIZE, PROT_READ, PROT_WRITE};
+
+/// far from the app, its heap and its stacks
+const START: usize = 0x1000_0000;
+const LEN: usize = 2 * PAGE_SIZE;
+
+fn pages() -> &'static mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(START as *mut u8, LEN) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(munmap(START, LEN), -1);
+    assert_eq!(mmap(START, LEN, PROT_READ | PROT_WRITE), 0);
+    pages().fill(0x5a);
+    // only the whole mapping can be unmapped, and nothing is on failure
+    assert_eq!(munmap(START + 1, LEN), -1);
+    assert_eq!(munmap(START, PAGE_SIZE), -1);
+    assert_eq!(munmap(START + PAGE_SIZE, PAGE_SIZE), -1);
+    assert!(pages().iter().all(|&b| b == 0x5a));
+
+    assert_eq!(munmap(START, LEN), 0);
+    assert_eq!(munmap(START, LEN), -1);
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            (START as *const u8).read_volatile();
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -11);
+
+    // the range is free again, with fresh pages
+    assert_eq!(mmap(START, LEN, PROT_READ | PROT_WRITE), 0);
+    assert!(pages().iter().all(|&b| b == 0));
+    assert_eq!(munmap(START, LEN), 0);
+    println!("munmap passed!");
+    0
+}
@@ -0,0 +1,63 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, getpid, getppid, pipe, read, waitpid, write, yield_};
+
+/// pid of initproc, which adopts orphans
+const INITPROC_PID: isize = 0;
+const MAX_PROBES: usize = 100;
+
+/// Pids are handed out again most recently freed first, and only freed when
+/// the process is reaped, so a fork returning the pid of the grandchild
+/// shows that initproc has waited for it.
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let middle = fork();
+    if middle == 0 {
+        let middle_pid = getpid();
+        let grandchild = fork();
+        if grandchild == 0 {
+            close(pipe_fd[0]);
+            while getppid() == middle_pid {
+                yield_();
+            }
+            assert_eq!(getppid(), INITPROC_PID);
+            write(pipe_fd[1], &getpid().to_ne_bytes());
+            exit(0);
+        }
+        // the grandchild is left to initproc
+        exit(0);
+    }
+    close(pipe_fd[1]);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(middle as usize, &mut exit_code), middle);
+    assert_eq!(exit_code, 0);
+
+    let mut buf = [0u8; core::mem::size_of::<isize>()];
+    assert_eq!(read(pipe_fd[0], &mut buf), buf.len() as isize);
+    let grandchild = isize::from_ne_bytes(buf);
+    // the write end is closed once the grandchild has exited
+    assert_eq!(read(pipe_fd[0], &mut buf), 0);
+    close(pipe_fd[0]);
+    // nobody but initproc can reap it
+    assert_eq!(waitpid(grandchild as usize, &mut exit_code), -1);
+
+    for _ in 0..MAX_PROBES {
+        let probe = fork();
+        if probe == 0 {
+            exit(0);
+        }
+        assert_eq!(waitpid(probe as usize, &mut exit_code), probe);
+        if probe == grandchild {
+            println!("orphan_reap passed!");
+            return 0;
+        }
+        yield_();
+    }
+    panic!("orphan {} was never reaped by initproc", grandchild);
+}
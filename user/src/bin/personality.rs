@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exec, fork, personality, waitpid, write, Personality, EFAULT, PERSONALITY_QUERY};
+
+/// exec'ed children report where their stack landed through the exit code
+fn report_stack() -> i32 {
+    let persona = personality(PERSONALITY_QUERY) as u32;
+    if !Personality::from_bits_truncate(persona).contains(Personality::ADDR_NO_RANDOMIZE) {
+        return -1;
+    }
+    let local = 0u8;
+    &local as *const u8 as usize as i32
+}
+
+#[no_mangle]
+pub fn main(argc: usize, _argv: &[&str]) -> i32 {
+    if argc > 1 {
+        return report_stack();
+    }
+    assert_eq!(personality(PERSONALITY_QUERY), 0);
+    assert_eq!(personality(Personality::ADDR_NO_RANDOMIZE.bits()), 0);
+    // unsupported bits are rejected and leave the personality untouched
+    assert_eq!(personality(1), -1);
+
+    let mut stacks = [0i32; 2];
+    for stack in stacks.iter_mut() {
+        let pid = fork();
+        if pid == 0 {
+            let args = [
+                "personality\0".as_ptr(),
+                "child\0".as_ptr(),
+                core::ptr::null::<u8>(),
+            ];
+            exec("personality\0", &args);
+            panic!("unreachable!");
+        }
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert!(exit_code > 0);
+        *stack = exit_code;
+    }
+    println!("stack of both execs: {:#x} {:#x}", stacks[0], stacks[1]);
+    assert_eq!(stacks[0], stacks[1]);
+
+    let persona = Personality::ADDR_NO_RANDOMIZE | Personality::STRICT_POINTERS;
+    assert_eq!(
+        personality(persona.bits()),
+        Personality::ADDR_NO_RANDOMIZE.bits() as isize
+    );
+    let msg = "non-canonical\n";
+    let bad = unsafe {
+        core::slice::from_raw_parts((msg.as_ptr() as usize | (1 << 40)) as *const u8, msg.len())
+    };
+    assert_eq!(write(1, bad), -EFAULT);
+    println!("personality passed!");
+    0
+}
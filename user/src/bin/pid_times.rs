@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use user_lib::{close, exit, fork, getpid, pipe, read, task_info, waitpid, TaskInfo};
+
+/// children forked and reaped one by one before
+const REAPED: usize = 64;
+/// children kept alive to push the pid of the last one up
+const ALIVE: usize = 32;
+
+fn reap(pid: isize, expected: i32) {
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, expected);
+}
+
+/// The times of a task with a pid above every earlier one are its own,
+/// after many tasks came and went.
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..REAPED {
+        let pid = fork();
+        if pid == 0 {
+            exit(0);
+        }
+        reap(pid, 0);
+    }
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let mut alive = Vec::new();
+    for _ in 0..ALIVE {
+        let pid = fork();
+        if pid == 0 {
+            close(pipe_fd[1]);
+            // returns once the parent closes the write end
+            let mut buf = [0u8; 1];
+            read(pipe_fd[0], &mut buf);
+            exit(0);
+        }
+        alive.push(pid);
+    }
+    let pid = fork();
+    if pid == 0 {
+        let mut info = TaskInfo::default();
+        assert_eq!(task_info(&mut info), 0);
+        assert!(info.user_time_us + info.kernel_time_us > 0);
+        exit((getpid() as usize > ALIVE) as i32);
+    }
+    reap(pid, 1);
+    close(pipe_fd[1]);
+    for pid in alive {
+        reap(pid, 0);
+    }
+    println!("pid_times passed!");
+    0
+}
@@ -0,0 +1,133 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::format;
+use core::mem::size_of;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use user_lib::{
+    close, exec, exit, fork, getpid, getppid, open, pipe, prctl, process_vm_readv, read, waitpid,
+    write, OpenFlags, EINVAL, EPERM, ESRCH, PR_GET_DUMPABLE, PR_SET_DUMPABLE,
+};
+
+/// set by the child after fork, which has its own copy
+static SECRET: AtomicUsize = AtomicUsize::new(0);
+
+/// run `f` in a child and return its exit code
+fn in_child(f: fn() -> i32) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        exit(f());
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+fn secret_addr() -> usize {
+    &SECRET as *const AtomicUsize as usize
+}
+
+/// read the secret of `pid` into `value`
+fn read_secret(pid: usize, value: &mut usize) -> isize {
+    let local = unsafe {
+        core::slice::from_raw_parts_mut(value as *mut usize as *mut u8, size_of::<usize>())
+    };
+    process_vm_readv(pid, local, secret_addr())
+}
+
+fn open_maps(pid: usize) -> isize {
+    let path = format!("/proc/{}/maps\0", pid);
+    open(path.as_str(), OpenFlags::RDONLY)
+}
+
+/// A parent reads the memory of its child until the child sets itself not
+/// dumpable, and nobody else may read it.
+fn parent_denied() {
+    let (mut to_child, mut to_parent) = ([0usize; 2], [0usize; 2]);
+    assert_eq!(pipe(&mut to_child), 0);
+    assert_eq!(pipe(&mut to_parent), 0);
+    let mut byte = [0u8; 1];
+    let pid = fork();
+    if pid == 0 {
+        SECRET.store(0x5ec4e7, Ordering::SeqCst);
+        // someone else than the parent is refused even when dumpable
+        let mut value = 0;
+        assert_eq!(read_secret(getppid() as usize, &mut value), -EPERM);
+        assert_eq!(write(to_parent[1], &byte), 1);
+        assert_eq!(read(to_child[0], &mut byte), 1);
+        assert_eq!(prctl(PR_SET_DUMPABLE, 0), 0);
+        assert_eq!(write(to_parent[1], &byte), 1);
+        assert_eq!(read(to_child[0], &mut byte), 1);
+        exit(0);
+    }
+    let pid = pid as usize;
+    assert_eq!(read(to_parent[0], &mut byte), 1);
+    let mut value = 0;
+    assert_eq!(read_secret(pid, &mut value), size_of::<usize>() as isize);
+    assert_eq!(value, 0x5ec4e7);
+    assert_eq!(SECRET.load(Ordering::SeqCst), 0);
+    let fd = open_maps(pid);
+    assert!(fd >= 0);
+    let mut maps = [0u8; 64];
+    assert!(read(fd as usize, &mut maps) > 0);
+    close(fd as usize);
+
+    assert_eq!(write(to_child[1], &byte), 1);
+    assert_eq!(read(to_parent[0], &mut byte), 1);
+    let mut value = 0;
+    assert_eq!(read_secret(pid, &mut value), -EPERM);
+    assert_eq!(value, 0);
+    assert_eq!(open_maps(pid), -EPERM);
+    // a process may always read itself
+    assert_eq!(prctl(PR_SET_DUMPABLE, 0), 0);
+    assert_eq!(
+        read_secret(getpid() as usize, &mut value),
+        size_of::<usize>() as isize
+    );
+    let fd = open("/proc/self/maps\0", OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    close(fd as usize);
+    assert_eq!(prctl(PR_SET_DUMPABLE, 1), 0);
+
+    assert_eq!(write(to_child[1], &byte), 1);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid, &mut exit_code), pid as isize);
+    assert_eq!(exit_code, 0);
+    assert_eq!(read_secret(pid, &mut value), -ESRCH);
+    for fd in to_child.iter().chain(to_parent.iter()) {
+        close(*fd);
+    }
+}
+
+#[no_mangle]
+pub fn main(argc: usize, _argv: &[&str]) -> i32 {
+    if argc > 1 {
+        return prctl(PR_GET_DUMPABLE, 0) as i32;
+    }
+    assert_eq!(prctl(PR_GET_DUMPABLE, 0), 1);
+    assert_eq!(prctl(PR_SET_DUMPABLE, 2), -EINVAL);
+    assert_eq!(prctl(99, 0), -EINVAL);
+    assert_eq!(prctl(PR_SET_DUMPABLE, 0), 0);
+    assert_eq!(prctl(PR_GET_DUMPABLE, 0), 0);
+    // inherited by fork, reset by exec
+    assert_eq!(in_child(|| prctl(PR_GET_DUMPABLE, 0) as i32), 0);
+    let exec_code = in_child(|| {
+        let args = [
+            "prctl_dumpable\0".as_ptr(),
+            "exec\0".as_ptr(),
+            core::ptr::null::<u8>(),
+        ];
+        exec("prctl_dumpable\0", &args);
+        -1
+    });
+    assert_eq!(exec_code, 1);
+    assert_eq!(prctl(PR_SET_DUMPABLE, 1), 0);
+    assert_eq!(prctl(PR_GET_DUMPABLE, 0), 1);
+    parent_denied();
+    println!("prctl_dumpable passed!");
+    0
+}
@@ -0,0 +1,107 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::{close, exit, fork, getpid, open, pipe, read, waitpid, yield_, OpenFlags};
+
+const PATH: &str = "/proc/tasks\0";
+const CHILDREN: usize = 2;
+
+/// (pid, tid, ppid, status) of every line in the listing
+fn list_tasks() -> Vec<(isize, isize, isize, String)> {
+    let fd = open(PATH, OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    let mut text = Vec::new();
+    // small reads go on in the listing taken by the first one
+    let mut buf = [0u8; 16];
+    loop {
+        let len = read(fd as usize, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            break;
+        }
+        text.extend_from_slice(&buf[..len as usize]);
+    }
+    close(fd as usize);
+    let text = String::from_utf8(text).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some("PID TID PPID STATUS USER_US KERNEL_US"));
+    lines
+        .map(|line| {
+            let fields: Vec<&str> = line.split(' ').collect();
+            assert_eq!(fields.len(), 6);
+            (
+                fields[0].parse().unwrap(),
+                fields[1].parse().unwrap(),
+                fields[2].parse().unwrap(),
+                String::from(fields[3]),
+            )
+        })
+        .collect()
+}
+
+fn status_of(tasks: &[(isize, isize, isize, String)], pid: isize) -> Option<(isize, &str)> {
+    tasks
+        .iter()
+        .find(|(p, tid, _, _)| *p == pid && *tid == 0)
+        .map(|(_, _, ppid, status)| (*ppid, status.as_str()))
+}
+
+/// The listing has the children forked with the right parent, a child
+/// exited and not waited for yet as a zombie, and the reader as running.
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    assert!(open(PATH, OpenFlags::WRONLY) < 0);
+    assert!(open("/proc/nothing\0", OpenFlags::RDONLY) < 0);
+
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), 0);
+    let mut children = Vec::new();
+    for _ in 0..CHILDREN {
+        let child = fork();
+        if child == 0 {
+            // stay alive until the parent closes the pipe
+            close(pipe_fd[1]);
+            let mut byte = [0u8; 1];
+            read(pipe_fd[0], &mut byte);
+            exit(0);
+        }
+        children.push(child);
+    }
+    let zombie = fork();
+    if zombie == 0 {
+        exit(0);
+    }
+    close(pipe_fd[0]);
+
+    let mut tasks = list_tasks();
+    while status_of(&tasks, zombie) != Some((pid, "Zombie")) {
+        yield_();
+        tasks = list_tasks();
+    }
+    assert_eq!(
+        status_of(&tasks, pid).map(|(_, status)| status),
+        Some("Running")
+    );
+    for &child in children.iter() {
+        let (ppid, status) = status_of(&tasks, child).unwrap();
+        assert_eq!(ppid, pid);
+        assert_ne!(status, "Zombie");
+    }
+
+    close(pipe_fd[1]);
+    let mut exit_code = 0;
+    for child in children.into_iter().chain([zombie]) {
+        assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    }
+    let tasks = list_tasks();
+    assert!(status_of(&tasks, zombie).is_none());
+    println!("proc_tasks passed!");
+    0
+}
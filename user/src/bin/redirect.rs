@@ -0,0 +1,59 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, dup, exec, fork, open, pipe, read, waitpid, write, OpenFlags};
+
+const CONTENT: &[u8] = b"copied by cat through both redirections\n";
+/// longer than CONTENT, the output must be truncated first
+const STALE: &[u8] = b"stale content of the output file, which is longer than the new one\n";
+const SCRIPT: &[u8] = b"cat < redirect_in > redirect_out\n";
+
+fn write_file(name: &str, content: &[u8]) {
+    let fd = open(
+        name,
+        OpenFlags::CREATE | OpenFlags::WRONLY | OpenFlags::TRUNC,
+    );
+    assert!(fd >= 0);
+    assert_eq!(write(fd as usize, content), content.len() as isize);
+    close(fd as usize);
+}
+
+/// run user_shell on the script with stdin redirected, wait until it exits
+fn run_shell(script: &[u8]) {
+    let mut input = [0usize; 2];
+    assert_eq!(pipe(&mut input), 0);
+    let pid = fork();
+    if pid == 0 {
+        close(0);
+        assert_eq!(dup(input[0]), 0);
+        close(input[0]);
+        close(input[1]);
+        exec("user_shell\0", &[core::ptr::null()]);
+        panic!("exec user_shell failed");
+    }
+    close(input[0]);
+    assert_eq!(write(input[1], script), script.len() as isize);
+    close(input[1]);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    write_file("redirect_in\0", CONTENT);
+    write_file("redirect_out\0", STALE);
+    run_shell(SCRIPT);
+
+    let fd = open("redirect_out\0", OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    let mut buf = [0u8; 128];
+    let len = read(fd as usize, &mut buf);
+    close(fd as usize);
+    assert_eq!(&buf[..len as usize], CONTENT);
+    println!("redirect passed!");
+    0
+}
@@ -0,0 +1,81 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, dup, exit, fork, getrlimit, mmap, open, pipe, read, sbrk, setrlimit, waitpid, OpenFlags,
+    RLimit, EAGAIN, EINVAL, EMFILE, EPERM, PAGE_SIZE, PROT_READ, RLIMIT_AS, RLIMIT_NOFILE,
+    RLIMIT_NPROC, RLIM_INFINITY,
+};
+
+/// far from the app, its heap and its stacks
+const START: usize = 0x1000_0000;
+
+fn limit(resource: usize) -> usize {
+    let mut rlim = RLimit::default();
+    assert_eq!(getrlimit(resource, &mut rlim), 0);
+    assert_eq!(rlim.rlim_cur, rlim.rlim_max);
+    rlim.rlim_cur
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(limit(RLIMIT_NOFILE), RLIM_INFINITY);
+    assert_eq!(getrlimit(99, &mut RLimit::default()), -EINVAL);
+    assert_eq!(setrlimit(99, 0), -EINVAL);
+
+    // fds 0, 1 and 2 are open, the 5th one is over the limit
+    assert_eq!(setrlimit(RLIMIT_NOFILE, 4), 0);
+    assert_eq!(limit(RLIMIT_NOFILE), 4);
+    assert_eq!(setrlimit(RLIMIT_NOFILE, 5), -EPERM);
+    let fd = open("rlimit\0", OpenFlags::RDONLY);
+    assert_eq!(fd, 3);
+    assert_eq!(open("rlimit\0", OpenFlags::RDONLY), -EMFILE);
+    assert_eq!(dup(0), -EMFILE);
+    let mut pipe_fd = [0usize; 2];
+    assert_eq!(pipe(&mut pipe_fd), -EMFILE);
+
+    // a limit below the usage keeps the fds open but no more are made
+    assert_eq!(setrlimit(RLIMIT_NOFILE, 2), 0);
+    let mut buf = [0u8; 4];
+    assert_eq!(read(fd as usize, &mut buf), 4);
+    assert_eq!(&buf, b"\x7fELF");
+    assert_eq!(close(fd as usize), 0);
+    assert_eq!(open("rlimit\0", OpenFlags::RDONLY), -EMFILE);
+    // inherited by children
+    let pid = fork();
+    if pid == 0 {
+        exit(limit(RLIMIT_NOFILE) as i32);
+    }
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 2);
+
+    // children not reaped yet count
+    assert_eq!(setrlimit(RLIMIT_NPROC, 1), 0);
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    assert!(pid > 0);
+    assert_eq!(fork(), -EAGAIN);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    let pid = fork();
+    if pid == 0 {
+        exit(0);
+    }
+    assert!(pid > 0);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+
+    // the address space in use stays mapped, but does not grow
+    assert_eq!(setrlimit(RLIMIT_AS, 0), 0);
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_READ), -1);
+    let brk = sbrk(0);
+    assert!(brk > 0);
+    assert_eq!(sbrk(PAGE_SIZE as isize), -1);
+    assert_eq!(sbrk(0), brk);
+    println!("rlimit passed!");
+    0
+}
@@ -0,0 +1,133 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::mem::size_of;
+use core::ptr::{read_volatile, write_volatile};
+use core::sync::atomic::{AtomicU32, Ordering};
+use user_lib::{
+    exit, fork, futex_wait, getpid, gettid, kill, set_robust_list, shm_attach, shm_get,
+    thread_create, waitpid, waittid, yield_, RobustListHead, SignalFlags, FUTEX_OWNER_DIED,
+    FUTEX_TID_MASK, FUTEX_WAITERS, IPC_PRIVATE, PROT_READ, PROT_WRITE,
+};
+
+/// what a robust mutex lock returns when it takes over from a dead owner
+const EOWNERDEAD: isize = 130;
+
+#[repr(C)]
+struct RobustMutex {
+    next: usize,
+    word: u32,
+}
+
+static mut HEAD: RobustListHead = RobustListHead {
+    list: 0,
+    futex_offset: 8,
+    list_op_pending: 0,
+};
+static mut LOCK: RobustMutex = RobustMutex { next: 0, word: 0 };
+
+fn lock_and_die() -> ! {
+    unsafe {
+        let head = &mut HEAD as *mut RobustListHead as usize;
+        LOCK.next = head;
+        HEAD.list = &LOCK as *const _ as usize;
+        assert_eq!(set_robust_list(head, size_of::<RobustListHead>()), 0);
+        // take the lock and exit without releasing it
+        write_volatile(&mut LOCK.word, gettid() as u32 + 1);
+    }
+    exit(0)
+}
+
+fn register_bad_list() -> ! {
+    // an unmapped list head must be ignored at exit
+    assert_eq!(set_robust_list(0x10, size_of::<RobustListHead>()), 0);
+    exit(0)
+}
+
+/// The robust mutex protocol of user space: an unlocked word is taken by
+/// storing the owner, a locked one is marked with FUTEX_WAITERS and waited
+/// on. A word whose owner died is taken over with -EOWNERDEAD.
+fn robust_lock(word: &AtomicU32) -> isize {
+    let owner = gettid() as u32 + 1;
+    loop {
+        let current = word.load(Ordering::Acquire);
+        if current & FUTEX_TID_MASK == 0 {
+            if word
+                .compare_exchange(current, owner, Ordering::Acquire, Ordering::Relaxed)
+                .is_ok()
+            {
+                return if current & FUTEX_OWNER_DIED != 0 {
+                    -EOWNERDEAD
+                } else {
+                    0
+                };
+            }
+        } else if word
+            .compare_exchange(
+                current,
+                current | FUTEX_WAITERS,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            futex_wait(word, current | FUTEX_WAITERS);
+        }
+    }
+}
+
+/// A process holding a robust mutex in shared memory is killed while
+/// another one is blocked on it, which is woken and takes the lock over.
+fn owner_killed_across_processes() {
+    let id = shm_get(IPC_PRIVATE, 0x1000);
+    assert!(id > 0);
+    let addr = shm_attach(id as usize, PROT_READ | PROT_WRITE);
+    assert!(addr > 0);
+    let lock = unsafe { &mut *(addr as *mut RobustMutex) };
+    let word = unsafe { &*(&lock.word as *const u32 as *const AtomicU32) };
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            let head = &mut HEAD as *mut RobustListHead as usize;
+            lock.next = head;
+            HEAD.list = addr as usize;
+            assert_eq!(set_robust_list(head, size_of::<RobustListHead>()), 0);
+        }
+        assert_eq!(robust_lock(word), 0);
+        while word.load(Ordering::Relaxed) & FUTEX_WAITERS == 0 {
+            yield_();
+        }
+        kill(getpid() as usize, SignalFlags::SIGKILL.bits());
+        unreachable!();
+    }
+    while word.load(Ordering::Relaxed) == 0 {
+        yield_();
+    }
+    assert_eq!(robust_lock(word), -EOWNERDEAD);
+    assert_eq!(word.load(Ordering::Relaxed), gettid() as u32 + 1);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -9);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_robust_list(0, 1), -1);
+
+    let tid = thread_create(lock_and_die as usize, 0);
+    assert_eq!(waittid(tid as usize), 0);
+    let word = unsafe { read_volatile(&LOCK.word) };
+    println!("robust futex word after owner exit: {:#x}", word);
+    assert!(word & FUTEX_OWNER_DIED != 0);
+    assert_eq!(word & FUTEX_TID_MASK, 0);
+
+    let tid = thread_create(register_bad_list as usize, 0);
+    assert_eq!(waittid(tid as usize), 0);
+
+    owner_killed_across_processes();
+    println!("robust_list passed!");
+    0
+}
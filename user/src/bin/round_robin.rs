@@ -0,0 +1,47 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, waitpid};
+
+/// how long each task spins, in milliseconds
+const BUSY_MS: isize = 300;
+/// a longer pause in the clock means another task ran meanwhile
+const PREEMPTED_MS: isize = 5;
+
+/// Spin without yielding, return how many times the task was preempted.
+fn busy() -> i32 {
+    let start = get_time();
+    let mut last = start;
+    let mut preempted = 0;
+    while last - start < BUSY_MS {
+        let now = get_time();
+        if now - last >= PREEMPTED_MS {
+            preempted += 1;
+        }
+        last = now;
+    }
+    preempted
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut pids = [0isize; 2];
+    for pid in pids.iter_mut() {
+        *pid = fork();
+        if *pid == 0 {
+            exit(busy());
+        }
+    }
+    // both tasks spun at the same time, so each had to give the cpu away
+    for pid in pids {
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        println!("task {} was preempted {} times", pid, exit_code);
+        assert!(exit_code > 0);
+    }
+    println!("round_robin passed!");
+    0
+}
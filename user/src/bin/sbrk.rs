@@ -0,0 +1,52 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sbrk, waitpid};
+
+const SIZE: usize = 8192;
+
+fn heap(brk: isize) -> &'static mut [u8] {
+    unsafe { core::slice::from_raw_parts_mut(brk as *mut u8, SIZE) }
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let bottom = sbrk(0);
+    assert!(bottom > 0);
+    // the heap never ends below where it started
+    assert_eq!(sbrk(-1), -1);
+    assert_eq!(sbrk(isize::MAX), -1);
+    assert_eq!(sbrk(0), bottom);
+
+    assert_eq!(sbrk(SIZE as isize), bottom);
+    assert_eq!(sbrk(0), bottom + SIZE as isize);
+    for (i, byte) in heap(bottom).iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    assert!(heap(bottom).iter().enumerate().all(|(i, &b)| b == i as u8));
+
+    // freed pages are gone
+    let pid = fork();
+    if pid == 0 {
+        assert_eq!(sbrk(-(SIZE as isize)), bottom + SIZE as isize);
+        unsafe {
+            (bottom as *mut u8).write_volatile(1);
+        }
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -11);
+    // while the parent keeps its own heap
+    assert!(heap(bottom).iter().enumerate().all(|(i, &b)| b == i as u8));
+
+    assert_eq!(sbrk(-(SIZE as isize)), bottom + SIZE as isize);
+    assert_eq!(sbrk(SIZE as isize), bottom);
+    assert!(heap(bottom).iter().all(|&b| b == 0));
+    assert_eq!(sbrk(-(SIZE as isize)), bottom + SIZE as isize);
+    println!("sbrk passed!");
+    0
+}
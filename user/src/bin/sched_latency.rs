@@ -0,0 +1,73 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, sched_latency_hist, sleep, wait, yield_, SCHED_LATENCY_BUCKETS};
+
+const CHILDREN: usize = 4;
+const YIELDS: usize = 50;
+
+/// the full histogram and the number of resumes it was taken at
+fn snapshot() -> ([u64; SCHED_LATENCY_BUCKETS], u64) {
+    let mut buckets = [0u64; SCHED_LATENCY_BUCKETS];
+    let measured = sched_latency_hist(&mut buckets);
+    assert!(measured >= 0);
+    (buckets, measured as u64)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let (_, before) = snapshot();
+    // yielding children compete with sleeping ones for the cpu
+    for i in 0..CHILDREN {
+        if fork() == 0 {
+            if i % 2 == 0 {
+                for _ in 0..YIELDS {
+                    yield_();
+                }
+            } else {
+                for _ in 0..YIELDS / 10 {
+                    sleep(1);
+                }
+            }
+            exit(0);
+        }
+    }
+    for _ in 0..YIELDS {
+        yield_();
+    }
+    let mut exit_code = 0;
+    for _ in 0..CHILDREN {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+
+    let (buckets, after) = snapshot();
+    // every yield of the parent and the yielding children is resumed once
+    assert!(after - before >= (YIELDS * (1 + CHILDREN / 2)) as u64);
+    assert_eq!(buckets.iter().sum::<u64>(), after);
+
+    // a short buffer gets only the buckets that fit
+    let mut short = [u64::MAX; SCHED_LATENCY_BUCKETS];
+    assert!(sched_latency_hist(&mut short[..4]) as u64 >= after);
+    assert!(short[4..].iter().all(|&count| count == u64::MAX));
+    // a long one keeps what is past the real buckets
+    let mut long = [u64::MAX; SCHED_LATENCY_BUCKETS + 8];
+    sched_latency_hist(&mut long);
+    assert!(long[SCHED_LATENCY_BUCKETS..]
+        .iter()
+        .all(|&count| count == u64::MAX));
+    // counts never go backwards
+    for i in 0..SCHED_LATENCY_BUCKETS {
+        assert!(long[i] >= buckets[i]);
+    }
+    for i in 0..4 {
+        assert!(short[i] >= buckets[i]);
+    }
+    // only the number of resumes is asked for
+    assert!(sched_latency_hist(&mut []) as u64 >= after);
+    println!("sched_latency passed!");
+    0
+}
@@ -0,0 +1,114 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use user_lib::{
+    close, exit, fork, lseek, open, pipe, read, sendfile, unlink, waitpid, write, OpenFlags,
+    SEEK_CUR, SEEK_SET,
+};
+
+/// spans a few blocks and ends in the middle of one
+const LEN: usize = 1300;
+
+fn byte_at(i: usize) -> u8 {
+    (i % 251) as u8
+}
+
+fn create(path: &str) -> usize {
+    let fd = open(path, OpenFlags::CREATE | OpenFlags::RDWR | OpenFlags::TRUNC);
+    assert!(fd >= 0);
+    fd as usize
+}
+
+/// everything left to read from fd
+fn read_rest(fd: usize) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 128];
+    loop {
+        let len = read(fd, &mut buf);
+        assert!(len >= 0);
+        if len == 0 {
+            return data;
+        }
+        data.extend_from_slice(&buf[..len as usize]);
+    }
+}
+
+/// the whole contents of the file at path
+fn contents(path: &str) -> Vec<u8> {
+    let fd = open(path, OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    let data = read_rest(fd as usize);
+    close(fd as usize);
+    data
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let data: Vec<u8> = (0..LEN).map(byte_at).collect();
+    let src = create("sendfile_src\0");
+    assert_eq!(write(src, &data), LEN as isize);
+
+    // from the offset of src, which moves to the end, stopping there
+    assert_eq!(lseek(src, 0, SEEK_SET), 0);
+    let dst = create("sendfile_dst\0");
+    assert_eq!(sendfile(dst, src, None, LEN + 100), LEN as isize);
+    assert_eq!(lseek(src, 0, SEEK_CUR), LEN as isize);
+    assert_eq!(sendfile(dst, src, None, 10), 0);
+    close(dst);
+    assert_eq!(contents("sendfile_dst\0"), data);
+
+    // from an offset of our own, src stays where it is
+    let dst = create("sendfile_dst\0");
+    let mut offset = 300;
+    assert_eq!(sendfile(dst, src, Some(&mut offset), 700), 700);
+    assert_eq!(offset, 1000);
+    assert_eq!(sendfile(dst, src, Some(&mut offset), 700), 300);
+    assert_eq!(offset, LEN);
+    assert_eq!(lseek(src, 0, SEEK_CUR), LEN as isize);
+    close(dst);
+    assert_eq!(contents("sendfile_dst\0"), data[300..]);
+
+    // into a pipe, more than it holds, drained by the parent meanwhile
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let pid = fork();
+    if pid == 0 {
+        close(fds[0]);
+        let mut offset = 0;
+        let sent = sendfile(fds[1], src, Some(&mut offset), LEN);
+        exit(if sent == LEN as isize && offset == LEN {
+            0
+        } else {
+            1
+        });
+    }
+    close(fds[1]);
+    assert_eq!(read_rest(fds[0]), data);
+    close(fds[0]);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    // a pipe has no offset to read from
+    assert_eq!(pipe(&mut fds), 0);
+    assert_eq!(write(fds[1], b"pipe"), 4);
+    let dst = create("sendfile_dst\0");
+    let mut offset = 0;
+    assert_eq!(sendfile(dst, fds[0], Some(&mut offset), 4), -1);
+    assert_eq!(sendfile(dst, fds[0], None, 4), 4);
+    close(dst);
+    assert_eq!(contents("sendfile_dst\0"), b"pipe");
+    close(fds[0]);
+    close(fds[1]);
+
+    close(src);
+    unlink("sendfile_src\0");
+    unlink("sendfile_dst\0");
+    println!("sendfile passed!");
+    0
+}
@@ -0,0 +1,39 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpgid, getpid, getsid, setsid, waitpid};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let pid = getpid();
+    let old_pgid = getpgid(0);
+    let old_sid = getsid(0);
+    assert_eq!(getpgid(pid as usize), old_pgid);
+    assert_eq!(getsid(pid as usize), old_sid);
+    // we are forked from the shell and do not lead any group yet
+    assert_ne!(old_pgid, pid);
+
+    assert_eq!(setsid(), pid);
+    assert_eq!(getsid(0), pid);
+    assert_eq!(getpgid(0), pid);
+    // a group leader cannot start another session
+    assert_eq!(setsid(), -1);
+
+    // children stay in the session and group of their parent
+    let child = fork();
+    if child == 0 {
+        assert_eq!(getsid(0), pid);
+        assert_eq!(getpgid(0), pid);
+        exit(0);
+    }
+    assert_eq!(getsid(child as usize), pid);
+    assert_eq!(getpgid(child as usize), pid);
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(child as usize, &mut exit_code), child);
+    assert_eq!(exit_code, 0);
+    println!("setsid passed!");
+    0
+}
@@ -0,0 +1,61 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+extern crate alloc;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use user_lib::{close, dup, exec, fork, pipe, read, waitpid, write};
+
+const SCRIPT: &[u8] = b"hello_world\n   \nno_such_app\nhello_world | \n";
+
+/// Run user_shell with stdin and stdout redirected to pipes, feeding it a
+/// script and collecting everything it prints until it sees the end of input.
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut input = [0usize; 2];
+    let mut output = [0usize; 2];
+    assert_eq!(pipe(&mut input), 0);
+    assert_eq!(pipe(&mut output), 0);
+    let pid = fork();
+    if pid == 0 {
+        close(0);
+        assert_eq!(dup(input[0]), 0);
+        close(1);
+        assert_eq!(dup(output[1]), 1);
+        for fd in input.iter().chain(output.iter()) {
+            close(*fd);
+        }
+        exec("user_shell\0", &[core::ptr::null()]);
+        panic!("exec user_shell failed");
+    }
+    close(input[0]);
+    close(output[1]);
+    assert_eq!(write(input[1], SCRIPT), SCRIPT.len() as isize);
+    close(input[1]);
+
+    let mut transcript: Vec<u8> = Vec::new();
+    let mut buf = [0u8; 256];
+    loop {
+        let len = read(output[0], &mut buf);
+        if len <= 0 {
+            break;
+        }
+        transcript.extend_from_slice(&buf[..len as usize]);
+    }
+    close(output[0]);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+
+    let transcript = String::from_utf8(transcript).unwrap();
+    assert!(transcript.contains("Hello world from user mode program!"));
+    assert!(transcript.contains("exited with code 0"));
+    assert!(transcript.contains("no_such_app: command not found"));
+    assert!(transcript.contains("Empty command in a pipeline"));
+    println!("shell_script passed!");
+    0
+}
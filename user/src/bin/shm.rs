@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    exit, fork, shm_attach, shm_detach, shm_get, waitpid, IPC_PRIVATE, PROT_READ, PROT_WRITE,
+};
+
+const KEY: usize = 0x5348;
+const PAGE_SIZE: usize = 0x1000;
+const SIZE: usize = 2 * PAGE_SIZE;
+
+fn attach(id: isize) -> &'static mut [u8] {
+    let addr = shm_attach(id as usize, PROT_READ | PROT_WRITE);
+    assert!(addr > 0);
+    unsafe { core::slice::from_raw_parts_mut(addr as *mut u8, SIZE) }
+}
+
+/// A forked child attaches the segment of the parent again, at another
+/// address, and writes through both attachments.
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(shm_get(KEY, 0), -1);
+    let id = shm_get(KEY, SIZE);
+    assert!(id > 0);
+    assert_eq!(shm_get(KEY, SIZE), id);
+    assert_eq!(shm_get(KEY, SIZE + 1), -1);
+    let private = shm_get(IPC_PRIVATE, SIZE);
+    assert!(private > 0 && private != id);
+    attach(private);
+    assert_eq!(shm_detach(private as usize), 0);
+    let segment = attach(id);
+    assert!(segment.iter().all(|&byte| byte == 0));
+    segment[0] = 1;
+
+    let pid = fork();
+    if pid == 0 {
+        let again = attach(shm_get(KEY, SIZE));
+        assert_ne!(again.as_ptr(), segment.as_ptr());
+        assert_eq!(again[0], 1);
+        again[PAGE_SIZE] = 2;
+        // the attachment inherited by fork is not copied on write
+        segment[SIZE - 1] = 3;
+        assert_eq!(again[SIZE - 1], 3);
+        assert_eq!(shm_detach(id as usize), 0);
+        assert_eq!(shm_detach(id as usize), -1);
+        exit(0);
+    }
+    let mut exit_code: i32 = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(segment[PAGE_SIZE], 2);
+    assert_eq!(segment[SIZE - 1], 3);
+
+    // the segment is freed with the last attachment, the key gets a new one
+    assert_eq!(shm_detach(id as usize), 0);
+    assert_eq!(shm_attach(id as usize, PROT_READ), -1);
+    let id = shm_get(KEY, SIZE);
+    assert!(id > 0);
+    let segment = attach(id);
+    assert!(segment.iter().all(|&byte| byte == 0));
+    assert_eq!(shm_detach(id as usize), 0);
+    println!("shm passed!");
+    0
+}
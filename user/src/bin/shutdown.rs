@@ -0,0 +1,33 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, open, read, shutdown, write, OpenFlags};
+
+const MARKER: &str = "shutdown_marker\0";
+const CONTENT: &[u8] = b"written before shutdown";
+
+/// Not run by usertests since it powers the machine off. The file written
+/// right before the shutdown is checked by the next boot running this again.
+#[no_mangle]
+pub fn main() -> i32 {
+    let fd = open(MARKER, OpenFlags::RDONLY);
+    if fd >= 0 {
+        let mut buf = [0u8; 64];
+        let len = read(fd as usize, &mut buf);
+        close(fd as usize);
+        assert_eq!(&buf[..len as usize], CONTENT);
+        println!("file written before the last shutdown survived");
+    }
+    let fd = open(
+        MARKER,
+        OpenFlags::CREATE | OpenFlags::TRUNC | OpenFlags::WRONLY,
+    );
+    assert!(fd >= 0);
+    assert_eq!(write(fd as usize, CONTENT), CONTENT.len() as isize);
+    close(fd as usize);
+    println!("shutdown passed!");
+    shutdown(0)
+}
@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, exit, fork, getpid, kill, read, read_siginfo, signalfd, sleep, waitpid, SignalFlags,
+    SignalfdSiginfo, EAGAIN, EINVAL, SFD_NONBLOCK,
+};
+
+const SIGALRM: u32 = 14;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // without a signalfd SIGALRM terminates the process
+    let pid = fork();
+    if pid == 0 {
+        loop {
+            sleep(10);
+        }
+    }
+    assert_eq!(kill(pid as usize, SignalFlags::SIGALRM.bits()), 0);
+    let mut exit_code = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -(SIGALRM as i32));
+
+    assert_eq!(signalfd(usize::MAX, SignalFlags::SIGALRM, 1), -EINVAL);
+    let nonblock = signalfd(usize::MAX, SignalFlags::SIGALRM, SFD_NONBLOCK);
+    assert!(nonblock > 0);
+    let nonblock = nonblock as usize;
+    let mut info = SignalfdSiginfo::new();
+    assert_eq!(read_siginfo(nonblock, &mut info), -EAGAIN);
+    assert_eq!(read(nonblock, &mut [0u8; 4]), -EINVAL);
+    // only signalfds can have their mask changed
+    assert_eq!(signalfd(1, SignalFlags::SIGALRM, 0), -EINVAL);
+
+    // the alarm comes from a child after a while, and is held for reading
+    let fd = signalfd(usize::MAX, SignalFlags::SIGALRM, 0) as usize;
+    let parent = getpid() as usize;
+    let pid = fork();
+    if pid == 0 {
+        sleep(30);
+        kill(parent, SignalFlags::SIGALRM.bits());
+        exit(0);
+    }
+    assert_eq!(read_siginfo(fd, &mut info), 128);
+    assert_eq!(info.ssi_signo, SIGALRM);
+    // it has been consumed
+    assert_eq!(read_siginfo(nonblock, &mut info), -EAGAIN);
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+
+    // masks can be replaced, SIGINT is held instead from now on
+    assert_eq!(
+        signalfd(nonblock, SignalFlags::SIGINT, SFD_NONBLOCK),
+        nonblock as isize
+    );
+    assert_eq!(signalfd(fd, SignalFlags::SIGINT, 0), fd as isize);
+    assert_eq!(kill(parent, SignalFlags::SIGINT.bits()), 0);
+    assert_eq!(read_siginfo(nonblock, &mut info), 128);
+    assert_eq!(info.ssi_signo, 2);
+    close(nonblock);
+    close(fd);
+
+    // a fault is delivered even with SIGSEGV in the mask, instead of being
+    // held while the faulting store runs again
+    let pid = fork();
+    if pid == 0 {
+        signalfd(usize::MAX, SignalFlags::SIGSEGV, 0);
+        unsafe {
+            core::ptr::null_mut::<u8>().write_volatile(0);
+        }
+        exit(0);
+    }
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, -11);
+    println!("signalfd passed!");
+    0
+}
@@ -0,0 +1,38 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, exit, fork, pipe, read, sleep, wait, write};
+
+/// how long each child sleeps, in milliseconds
+const SLEEP_MS: [usize; 4] = [90, 30, 60, 10];
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    for (i, ms) in SLEEP_MS.iter().enumerate() {
+        if fork() == 0 {
+            close(fds[0]);
+            sleep(*ms);
+            // report in the order of waking up
+            assert_eq!(write(fds[1], &[i as u8]), 1);
+            exit(0);
+        }
+    }
+    close(fds[1]);
+    let mut woken = [0u8; SLEEP_MS.len()];
+    for byte in woken.iter_mut() {
+        assert_eq!(read(fds[0], core::slice::from_mut(byte)), 1);
+    }
+    assert_eq!(woken, [3, 1, 2, 0]);
+    let mut exit_code: i32 = 0;
+    for _ in SLEEP_MS {
+        assert!(wait(&mut exit_code) > 0);
+        assert_eq!(exit_code, 0);
+    }
+    println!("sleep_order passed!");
+    0
+}
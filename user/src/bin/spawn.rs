@@ -0,0 +1,98 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, dup, fstat, getpid, pipe, read, spawn, waitpid, write, Stat, StatMode};
+
+const STDIN: usize = 0;
+const STDOUT: usize = 1;
+const CHILD_EXIT_CODE: i32 = 42;
+const INPUT: &[u8] = b"read by the spawned child\n";
+
+fn mode_of(fd: usize) -> StatMode {
+    let mut st = Stat::default();
+    assert_eq!(fstat(fd, &mut st), 0);
+    st.mode
+}
+
+/// Copy one line from stdin to stdout in upper case. The child holds the
+/// write end of its stdin pipe as well, so it never sees the end of it.
+fn echo_line() -> i32 {
+    let mut byte = [0u8; 1];
+    loop {
+        assert_eq!(read(STDIN, &mut byte), 1);
+        let upper = [byte[0].to_ascii_uppercase()];
+        assert_eq!(write(STDOUT, &upper), 1);
+        if byte[0] == b'\n' {
+            return CHILD_EXIT_CODE;
+        }
+    }
+}
+
+/// The child, spawned without arguments, can use the stdio of the parent.
+fn child() -> i32 {
+    if mode_of(STDIN) == StatMode::FIFO {
+        return echo_line();
+    }
+    for fd in 0..3 {
+        assert_eq!(mode_of(fd), StatMode::CHR);
+    }
+    let msg = b"spawned child writes to stdout\n";
+    assert_eq!(write(STDOUT, msg), msg.len() as isize);
+    CHILD_EXIT_CODE
+}
+
+fn wait_child(pid: isize) {
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, CHILD_EXIT_CODE);
+}
+
+#[no_mangle]
+pub fn main(argc: usize, _argv: &[&str]) -> i32 {
+    if argc == 0 {
+        return child();
+    }
+    let pid = spawn("spawn\0");
+    assert!(pid > 0);
+    assert_ne!(pid, getpid());
+    wait_child(pid);
+    assert_eq!(spawn("no_such_app\0"), -1);
+
+    // the child inherits stdin and stdout redirected to pipes
+    let mut input = [0usize; 2];
+    let mut output = [0usize; 2];
+    assert_eq!(pipe(&mut input), 0);
+    assert_eq!(pipe(&mut output), 0);
+    let saved_stdin = dup(STDIN) as usize;
+    let saved_stdout = dup(STDOUT) as usize;
+    close(STDIN);
+    assert_eq!(dup(input[0]), STDIN as isize);
+    close(STDOUT);
+    assert_eq!(dup(output[1]), STDOUT as isize);
+    let pid = spawn("spawn\0");
+    close(STDIN);
+    assert_eq!(dup(saved_stdin), STDIN as isize);
+    close(STDOUT);
+    assert_eq!(dup(saved_stdout), STDOUT as isize);
+    close(saved_stdin);
+    close(saved_stdout);
+    assert!(pid > 0);
+    assert_eq!(write(input[1], INPUT), INPUT.len() as isize);
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    while len < INPUT.len() {
+        let n = read(output[0], &mut buf[len..]);
+        assert!(n > 0);
+        len += n as usize;
+    }
+    wait_child(pid);
+    assert_eq!(&buf[..len], INPUT.to_ascii_uppercase().as_slice());
+    for fd in input.iter().chain(output.iter()) {
+        close(*fd);
+    }
+    println!("spawn passed!");
+    0
+}
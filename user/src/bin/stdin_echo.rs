@@ -0,0 +1,85 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, dup, exec, fork, pipe, read, waitpid, write, EBADF};
+
+const PAGE_SIZE: usize = 0x1000;
+const STDIN: usize = 0;
+const STDOUT: usize = 1;
+const INPUT: &[u8] = b"the first line\nand the second one, read across a page boundary\n";
+
+#[repr(C, align(4096))]
+struct TwoPages([u8; 2 * PAGE_SIZE]);
+
+static mut PAGES: TwoPages = TwoPages([0; 2 * PAGE_SIZE]);
+
+/// copy stdin to stdout through a buffer crossing a page boundary
+fn echo() -> i32 {
+    let buf = unsafe { &mut PAGES.0[PAGE_SIZE - 16..PAGE_SIZE + 16] };
+    loop {
+        let len = read(STDIN, buf);
+        assert!(len >= 0);
+        if len == 0 {
+            return 0;
+        }
+        assert_eq!(write(STDOUT, &buf[..len as usize]), len);
+    }
+}
+
+/// Run this program on INPUT with stdin and stdout redirected to pipes.
+#[no_mangle]
+pub fn main(argc: usize, _argv: &[&str]) -> i32 {
+    if argc == 2 {
+        return echo();
+    }
+    let mut buf = [0u8; 128];
+    assert_eq!(read(STDOUT, &mut buf), -EBADF);
+    assert_eq!(read(42, &mut buf), -EBADF);
+
+    let mut input = [0usize; 2];
+    let mut output = [0usize; 2];
+    assert_eq!(pipe(&mut input), 0);
+    assert_eq!(pipe(&mut output), 0);
+    let pid = fork();
+    if pid == 0 {
+        close(STDIN);
+        assert_eq!(dup(input[0]), STDIN as isize);
+        close(STDOUT);
+        assert_eq!(dup(output[1]), STDOUT as isize);
+        for fd in input.iter().chain(output.iter()) {
+            close(*fd);
+        }
+        exec(
+            "stdin_echo\0",
+            &[
+                "stdin_echo\0".as_ptr(),
+                "echo\0".as_ptr(),
+                core::ptr::null(),
+            ],
+        );
+        panic!("exec stdin_echo failed");
+    }
+    close(input[0]);
+    close(output[1]);
+    assert_eq!(write(input[1], INPUT), INPUT.len() as isize);
+    close(input[1]);
+    let mut len = 0;
+    loop {
+        let n = read(output[0], &mut buf[len..]);
+        assert!(n >= 0);
+        if n == 0 {
+            break;
+        }
+        len += n as usize;
+    }
+    close(output[0]);
+    let mut exit_code = -1;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(&buf[..len], INPUT);
+    println!("stdin_echo passed!");
+    0
+}
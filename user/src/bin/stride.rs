@@ -0,0 +1,53 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, get_time, set_priority, waitpid};
+
+/// how long both tasks spin together, in milliseconds
+const BUSY_MS: isize = 500;
+/// a longer pause in the clock means another task ran meanwhile
+const PREEMPTED_MS: isize = 5;
+
+/// Spin without yielding until deadline, return the milliseconds spent
+/// running, which leaves out the pauses while other tasks ran.
+fn busy(deadline: isize) -> i32 {
+    let mut last = get_time();
+    let mut ran = 0;
+    while last < deadline {
+        let now = get_time();
+        if now - last < PREEMPTED_MS {
+            ran += now - last;
+        }
+        last = now;
+    }
+    ran as i32
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(set_priority(1), -1);
+    assert_eq!(set_priority(0), -1);
+    assert_eq!(set_priority(-16), -1);
+    let deadline = get_time() + BUSY_MS;
+    let mut pids = [0isize; 2];
+    for (pid, prio) in pids.iter_mut().zip([10, 5]) {
+        *pid = fork();
+        if *pid == 0 {
+            assert_eq!(set_priority(prio), prio);
+            exit(busy(deadline));
+        }
+    }
+    let mut ran = [0i32; 2];
+    for (pid, ran) in pids.iter().zip(ran.iter_mut()) {
+        assert_eq!(waitpid(*pid as usize, ran), *pid);
+    }
+    println!("priority 10 ran {}ms, priority 5 ran {}ms", ran[0], ran[1]);
+    // twice the priority should get about twice the cpu time
+    assert!(ran[0] * 10 > ran[1] * 14);
+    assert!(ran[0] < ran[1] * 3);
+    println!("stride passed!");
+    0
+}
@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, get_time, pipe, read, submit_batch, SyscallOp, TimeVal, EBADF, EINVAL,
+    OP_STOP_ON_ERROR,
+};
+
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_GET_TIME: usize = 169;
+const SYSCALL_FORK: usize = 220;
+
+/// a result the kernel never writes
+const UNTOUCHED: isize = isize::MIN;
+
+fn write_op(fd: usize, buf: &[u8], flags: usize) -> SyscallOp {
+    let mut op = SyscallOp::new(SYSCALL_WRITE, [fd, buf.as_ptr() as usize, buf.len()], flags);
+    op.result = UNTOUCHED;
+    op
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let start = get_time();
+    let mut ts = TimeVal::default();
+    let mut ops = [
+        write_op(fds[1], b"a", 0),
+        write_op(fds[1], b"bb", 0),
+        write_op(fds[1], b"ccc", 0),
+        SyscallOp::new(SYSCALL_GET_TIME, [&mut ts as *mut _ as usize, 0, 0], 0),
+    ];
+    assert_eq!(submit_batch(&mut ops), 4);
+    assert_eq!(ops[0].result, 1);
+    assert_eq!(ops[1].result, 2);
+    assert_eq!(ops[2].result, 3);
+    assert_eq!(ops[3].result, 0);
+    let time_ms = (ts.sec * 1000 + ts.usec / 1000) as isize;
+    assert!(time_ms >= start && time_ms <= get_time());
+    let mut buf = [0u8; 6];
+    assert_eq!(read(fds[0], &mut buf), 6);
+    assert_eq!(&buf, b"abbccc");
+
+    // a failed op stops the batch only when it asks to
+    let mut ops = [
+        write_op(99, b"x", 0),
+        write_op(fds[1], b"d", 0),
+        write_op(99, b"x", OP_STOP_ON_ERROR),
+        write_op(fds[1], b"e", 0),
+    ];
+    assert_eq!(submit_batch(&mut ops), 3);
+    assert_eq!(ops[0].result, -EBADF);
+    assert_eq!(ops[1].result, 1);
+    assert_eq!(ops[2].result, -EBADF);
+    assert_eq!(ops[3].result, UNTOUCHED);
+    assert_eq!(read(fds[0], &mut buf[..1]), 1);
+    assert_eq!(buf[0], b'd');
+
+    // ops that would resume user code by themselves are refused
+    let mut ops = [SyscallOp::new(SYSCALL_FORK, [0; 3], 0)];
+    assert_eq!(submit_batch(&mut ops), 1);
+    assert_eq!(ops[0].result, -EINVAL);
+    // unknown flags are refused too
+    let mut ops = [write_op(fds[1], b"f", 2)];
+    assert_eq!(submit_batch(&mut ops), 1);
+    assert_eq!(ops[0].result, -EINVAL);
+    // empty and oversized batches
+    assert_eq!(submit_batch(&mut []), -EINVAL);
+    let mut ops = [SyscallOp::new(SYSCALL_GET_TIME, [0; 3], 0); 65];
+    assert_eq!(submit_batch(&mut ops), -EINVAL);
+    close(fds[0]);
+    close(fds[1]);
+    println!("submit_batch passed!");
+    0
+}
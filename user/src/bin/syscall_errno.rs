@@ -0,0 +1,123 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::slice::{from_raw_parts, from_raw_parts_mut};
+use user_lib::{
+    close, condvar_create, condvar_signal, condvar_wait, dup, exec, exit, fork,
+    mutex_blocking_create, mutex_lock, mutex_unlock, open, pipe, read, semaphore_down,
+    semaphore_up, waitpid, write, OpenFlags, EBADF, EFAULT, EINVAL,
+};
+
+/// canonical but never mapped in user space
+const UNMAPPED: usize = 0x20_0000_0000;
+
+/// lands in .rodata, which is mapped readable only
+static READ_ONLY: [usize; 2] = [0; 2];
+
+fn check_fd() {
+    assert_eq!(write(99, b"x"), -EBADF);
+    assert_eq!(read(99, &mut [0u8; 1]), -EBADF);
+    assert_eq!(close(99), -EBADF);
+    assert_eq!(dup(99), -EBADF);
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    // wrong direction of an opened fd
+    assert_eq!(write(fds[0], b"x"), -EBADF);
+    assert_eq!(read(fds[1], &mut [0u8; 1]), -EBADF);
+    let fd = dup(fds[1]);
+    assert!(fd > 0);
+    assert_eq!(close(fd as usize), 0);
+    assert_eq!(close(fd as usize), -EBADF);
+    close(fds[0]);
+    close(fds[1]);
+}
+
+fn check_user_slice() {
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    let null = unsafe { from_raw_parts(core::ptr::null::<u8>(), 4) };
+    assert_eq!(write(fds[1], null), -EFAULT);
+    let unmapped = unsafe { from_raw_parts(UNMAPPED as *const u8, 4) };
+    assert_eq!(write(fds[1], unmapped), -EFAULT);
+    // the buffer of read must be writable
+    let read_only = unsafe { from_raw_parts_mut(READ_ONLY.as_ptr() as *mut u8, 4) };
+    assert_eq!(read(fds[0], read_only), -EFAULT);
+    // the same buffer is fine to write from
+    assert_eq!(write(fds[1], read_only), 4);
+    let mut buf = [1u8; 4];
+    assert_eq!(read(fds[0], &mut buf), 4);
+    assert_eq!(buf, [0u8; 4]);
+    assert_eq!(write(fds[1], b""), 0);
+    close(fds[0]);
+    close(fds[1]);
+}
+
+fn check_user_ptr() {
+    let read_only = unsafe { from_raw_parts_mut(READ_ONLY.as_ptr() as *mut usize, 2) };
+    assert_eq!(pipe(read_only), -EFAULT);
+    let unmapped = unsafe { from_raw_parts_mut(UNMAPPED as *mut usize, 2) };
+    assert_eq!(pipe(unmapped), -EFAULT);
+
+    let pid = fork();
+    if pid == 0 {
+        exit(7);
+    }
+    let mut codes = [0i32; 2];
+    let misaligned = unsafe { &mut *((codes.as_mut_ptr() as usize + 1) as *mut i32) };
+    assert_eq!(waitpid(pid as usize, misaligned), -EFAULT);
+    let read_only = unsafe { &mut *(READ_ONLY.as_ptr() as *mut i32) };
+    assert_eq!(waitpid(pid as usize, read_only), -EFAULT);
+    // the failed waits must not reap the child
+    assert_eq!(waitpid(pid as usize, &mut codes[0]), pid);
+    assert_eq!(codes[0], 7);
+}
+
+fn check_user_str() {
+    let unmapped =
+        unsafe { core::str::from_utf8_unchecked(from_raw_parts(UNMAPPED as *const u8, 1)) };
+    assert_eq!(open(unmapped, OpenFlags::RDONLY), -EFAULT);
+    let args = [core::ptr::null::<u8>()];
+    assert_eq!(exec(unmapped, &args), -EFAULT);
+    // a well-formed path that names nothing is not a fault
+    assert_eq!(open("syscall_errno_none\0", OpenFlags::RDONLY), -1);
+    let unknown = unsafe { OpenFlags::from_bits_unchecked(1 << 31) };
+    assert_eq!(open("syscall_errno\0", unknown), -EINVAL);
+    // one of the arguments is unreadable
+    let args = [UNMAPPED as *const u8, core::ptr::null::<u8>()];
+    assert_eq!(exec("syscall_errno\0", &args), -EFAULT);
+    // the argument array itself is unreadable
+    let args = unsafe { from_raw_parts(UNMAPPED as *const *const u8, 1) };
+    assert_eq!(exec("syscall_errno\0", args), -EFAULT);
+}
+
+fn check_sync_ids() {
+    assert_eq!(mutex_lock(99), -EINVAL);
+    assert_eq!(mutex_unlock(99), -EINVAL);
+    let mutex_id = mutex_blocking_create();
+    assert!(mutex_id >= 0);
+    assert_eq!(mutex_lock(mutex_id as usize), 0);
+    assert_eq!(mutex_unlock(mutex_id as usize), 0);
+    assert_eq!(semaphore_up(99), -EINVAL);
+    assert_eq!(semaphore_down(99), -EINVAL);
+    assert_eq!(condvar_signal(99), -EINVAL);
+    let condvar_id = condvar_create();
+    assert!(condvar_id >= 0);
+    assert_eq!(condvar_wait(99, mutex_id as usize), -EINVAL);
+    assert_eq!(condvar_wait(condvar_id as usize, 99), -EINVAL);
+    // nobody is waiting
+    assert_eq!(condvar_signal(condvar_id as usize), 0);
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    check_fd();
+    check_user_slice();
+    check_user_ptr();
+    check_user_str();
+    check_sync_ids();
+    println!("syscall_errno passed!");
+    0
+}
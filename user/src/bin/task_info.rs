@@ -0,0 +1,41 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{gettid, sleep, task_info, write, yield_, TaskInfo, MAX_SYSCALL_NUM};
+
+const SYSCALL_WRITE: usize = 64;
+const SYSCALL_YIELD: usize = 124;
+const SYSCALL_TASK_INFO: usize = 410;
+const STDOUT: usize = 1;
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut before = TaskInfo::default();
+    assert_eq!(task_info(&mut before), 0);
+    assert!(before.first_time_ms > 0);
+    assert!(before.syscall_times[SYSCALL_TASK_INFO] >= 1);
+
+    sleep(10);
+    for _ in 0..3 {
+        write(STDOUT, b"task_info: write\n");
+    }
+    yield_();
+    yield_();
+    // far past the array, counted in the last slot
+    gettid();
+    let mut after = TaskInfo::default();
+    assert_eq!(task_info(&mut after), 0);
+
+    let delta = |id: usize| after.syscall_times[id] - before.syscall_times[id];
+    assert_eq!(delta(SYSCALL_WRITE), 3);
+    assert_eq!(delta(SYSCALL_YIELD), 2);
+    assert_eq!(delta(SYSCALL_TASK_INFO), 1);
+    assert_eq!(delta(MAX_SYSCALL_NUM - 1), 1);
+    assert_eq!(after.first_time_ms, before.first_time_ms);
+    assert!(after.last_time_ms >= before.last_time_ms + 10);
+    println!("task_info passed!");
+    0
+}
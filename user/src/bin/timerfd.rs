@@ -0,0 +1,68 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{
+    close, get_time, read, sleep, timerfd_create, timerfd_settime, write, ITimerSpec,
+    CLOCK_MONOTONIC, EAGAIN, EBADF, EINVAL, TFD_NONBLOCK,
+};
+
+fn read_count(fd: usize) -> isize {
+    let mut buf = [0u8; 8];
+    let ret = read(fd, &mut buf);
+    if ret < 0 {
+        return ret;
+    }
+    assert_eq!(ret, 8);
+    u64::from_ne_bytes(buf) as isize
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(timerfd_create(0, 0), -EINVAL);
+    assert_eq!(timerfd_create(CLOCK_MONOTONIC, 1), -EINVAL);
+    let periodic = ITimerSpec {
+        interval_ms: 30,
+        value_ms: 30,
+    };
+
+    let nonblock = timerfd_create(CLOCK_MONOTONIC, TFD_NONBLOCK) as usize;
+    // disarmed, then armed but not expired yet
+    assert_eq!(read_count(nonblock), -EAGAIN);
+    assert_eq!(timerfd_settime(nonblock, &periodic, None), 0);
+    assert_eq!(read_count(nonblock), -EAGAIN);
+    assert_eq!(read(nonblock, &mut [0u8; 4]), -EINVAL);
+    assert_eq!(write(nonblock, &[0u8; 8]), -EBADF);
+    assert_eq!(timerfd_settime(1, &periodic, None), -EINVAL);
+
+    // a blocking read waits for the first expiration
+    let fd = timerfd_create(CLOCK_MONOTONIC, 0) as usize;
+    let start = get_time();
+    assert_eq!(timerfd_settime(fd, &periodic, None), 0);
+    let count = read_count(fd);
+    let waited = get_time() - start;
+    println!("{} expiration(s) after {} ms", count, waited);
+    assert!(count >= 1);
+    assert!(waited >= 30);
+
+    // expirations add up until they are read
+    sleep(100);
+    let count = read_count(nonblock);
+    println!("{} expirations after sleeping", count);
+    assert!(count >= 3);
+    assert_eq!(read_count(nonblock), -EAGAIN);
+
+    let mut old = ITimerSpec::default();
+    assert_eq!(
+        timerfd_settime(nonblock, &ITimerSpec::default(), Some(&mut old)),
+        0
+    );
+    assert_eq!(old.interval_ms, 30);
+    assert!(old.value_ms >= 1 && old.value_ms <= 30);
+    close(nonblock);
+    close(fd);
+    println!("timerfd passed!");
+    0
+}
@@ -0,0 +1,79 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, mmap, mprotect, munmap, waitpid, PAGE_SIZE, PROT_READ, PROT_WRITE};
+
+/// far from the app, its heap and its stacks
+const START: usize = 0x1000_0000;
+
+/// Run f in a child and return its exit code.
+fn in_child(f: fn()) -> i32 {
+    let pid = fork();
+    if pid == 0 {
+        f();
+        exit(0);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    exit_code
+}
+
+/// Touch the page so its translation gets cached.
+fn map_and_touch() -> *mut u8 {
+    assert_eq!(mmap(START, PAGE_SIZE, PROT_READ | PROT_WRITE), 0);
+    let page = START as *mut u8;
+    unsafe {
+        page.write_volatile(0x5a);
+        assert_eq!(page.read_volatile(), 0x5a);
+    }
+    page
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    // the page is gone right after munmap returns
+    assert_eq!(
+        in_child(|| {
+            let page = map_and_touch();
+            assert_eq!(munmap(START, PAGE_SIZE), 0);
+            unsafe {
+                page.read_volatile();
+            }
+        }),
+        -11
+    );
+    // and is no longer writable right after mprotect returns
+    assert_eq!(
+        in_child(|| {
+            let page = map_and_touch();
+            assert_eq!(mprotect(START, PAGE_SIZE, PROT_READ), 0);
+            unsafe {
+                assert_eq!(page.read_volatile(), 0x5a);
+                page.write_volatile(0);
+            }
+        }),
+        -11
+    );
+    // a page shared by fork is copied before the parent writes to it, even
+    // if its writable translation was cached before the fork
+    let page = map_and_touch();
+    let pid = fork();
+    if pid == 0 {
+        unsafe {
+            assert_eq!(page.read_volatile(), 0x5a);
+        }
+        exit(0);
+    }
+    unsafe {
+        page.write_volatile(0xa5);
+    }
+    let mut exit_code: i32 = 0;
+    assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(unsafe { page.read_volatile() }, 0xa5);
+    println!("tlb_flush passed!");
+    0
+}
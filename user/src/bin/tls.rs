@@ -0,0 +1,67 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{exit, gettid, set_tid_address, thread_create, thread_pointer, waittid, yield_};
+
+const THREADS: usize = 2;
+
+/// the thread pointer of each thread, to check that they differ
+static mut TPS: [usize; THREADS] = [0; THREADS];
+/// zeroed by the kernel when the thread exits
+static mut TID_WORDS: [u32; THREADS] = [u32::MAX; THREADS];
+
+fn store_tls(value: usize) {
+    unsafe {
+        asm!("sd {}, 0(tp)", in(reg) value);
+    }
+}
+
+fn load_tls() -> usize {
+    let value: usize;
+    unsafe {
+        asm!("ld {}, 0(tp)", out(reg) value);
+    }
+    value
+}
+
+fn thread_main(i: usize) -> ! {
+    let tid = unsafe { set_tid_address(&mut TID_WORDS[i] as *mut u32) };
+    assert_eq!(tid, gettid());
+    unsafe {
+        TPS[i] = thread_pointer();
+    }
+    let value = 0x1000 + i;
+    store_tls(value);
+    // the other thread runs in between and stores its own value
+    for _ in 0..4 {
+        yield_();
+        assert_eq!(load_tls(), value);
+    }
+    exit(0)
+}
+
+/// Each thread finds its own value through tp, the main thread included,
+/// and has its tid word zeroed on exit.
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_ne!(thread_pointer(), 0);
+    store_tls(0x1234);
+    let tids = [
+        thread_create(thread_main as usize, 0),
+        thread_create(thread_main as usize, 1),
+    ];
+    for (i, tid) in tids.iter().enumerate() {
+        assert_eq!(waittid(*tid as usize), 0);
+        assert_eq!(unsafe { TID_WORDS[i] }, 0);
+    }
+    let tps = unsafe { TPS };
+    assert!(tps[0] != 0 && tps[1] != 0 && tps[0] != tps[1]);
+    assert!(!tps.contains(&thread_pointer()));
+    assert_eq!(load_tls(), 0x1234);
+    println!("tls passed!");
+    0
+}
@@ -0,0 +1,30 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::arch::asm;
+use user_lib::{exit, fork, waitpid};
+
+const ROUNDS: usize = 3;
+
+/// An illegal instruction kills the child with SIGILL, again and again,
+/// while the kernel and the parent keep running.
+#[no_mangle]
+pub fn main() -> i32 {
+    for _ in 0..ROUNDS {
+        let pid = fork();
+        if pid == 0 {
+            unsafe {
+                asm!("unimp");
+            }
+            exit(0);
+        }
+        let mut exit_code: i32 = 0;
+        assert_eq!(waitpid(pid as usize, &mut exit_code), pid);
+        assert_eq!(exit_code, -4);
+    }
+    println!("unimp passed!");
+    0
+}
@@ -0,0 +1,74 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, fstat, mkdir, open, read, unlink, write, OpenFlags, Stat};
+
+const FILE: &str = "unlink_file\0";
+/// spans a few blocks
+const LEN: usize = 2000;
+
+fn create(path: &str) -> usize {
+    let fd = open(path, OpenFlags::CREATE | OpenFlags::WRONLY);
+    assert!(fd >= 0);
+    fd as usize
+}
+
+fn stat_of(fd: usize) -> Stat {
+    let mut stat = Stat::default();
+    assert_eq!(fstat(fd, &mut stat), 0);
+    stat
+}
+
+/// An unlinked file stays readable through an fd open on it, and its inode
+/// is only freed once that is closed, to be taken by the next file.
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut content = [0u8; LEN];
+    for (i, byte) in content.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let fd = create(FILE);
+    assert_eq!(write(fd, &content), LEN as isize);
+    close(fd);
+
+    let fd = open(FILE, OpenFlags::RDONLY);
+    assert!(fd >= 0);
+    let fd = fd as usize;
+    let ino = stat_of(fd).ino;
+    assert_eq!(stat_of(fd).nlink, 1);
+    assert_eq!(unlink(FILE), 0);
+    assert_eq!(open(FILE, OpenFlags::RDONLY), -1);
+    assert_eq!(unlink(FILE), -1);
+    assert_eq!(stat_of(fd).nlink, 0);
+
+    // the inode is still in use
+    let other = create("unlink_other\0");
+    assert_ne!(stat_of(other).ino, ino);
+    close(other);
+
+    let mut buf = [0u8; LEN];
+    assert_eq!(read(fd, &mut buf), LEN as isize);
+    assert_eq!(buf, content);
+    close(fd);
+
+    // freed now, the lowest free inode is taken first
+    let reuse = create("unlink_reuse\0");
+    assert_eq!(stat_of(reuse).ino, ino);
+    assert_eq!(stat_of(reuse).size, 0);
+    close(reuse);
+    assert_eq!(unlink("unlink_reuse\0"), 0);
+    assert_eq!(unlink("unlink_other\0"), 0);
+
+    // only empty directories go
+    assert_eq!(mkdir("unlink_dir\0"), 0);
+    close(create("unlink_dir/file\0"));
+    assert_eq!(unlink("unlink_dir\0"), -1);
+    assert_eq!(unlink("unlink_dir/file\0"), 0);
+    assert_eq!(unlink("unlink_dir\0"), 0);
+    assert_eq!(unlink("/\0"), -1);
+    println!("unlink passed!");
+    0
+}
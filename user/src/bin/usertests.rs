@@ -10,36 +10,106 @@ extern crate user_lib;
 // item of TESTS : app_name(argv_0), argv_1, argv_2, argv_3, exit_code
 static SUCC_TESTS: &[(&str, &str, &str, &str, i32)] = &[
     ("filetest_simple\0", "\0", "\0", "\0", 0),
+    ("asid_switch\0", "\0", "\0", "\0", 0),
+    ("bss\0", "\0", "\0", "\0", 0),
     ("cat\0", "filea\0", "\0", "\0", 0),
+    ("clone\0", "\0", "\0", "\0", 0),
     ("cmdline_args\0", "1\0", "2\0", "3\0", 0),
+    ("cow_fork\0", "\0", "\0", "\0", 0),
+    ("cpu_time\0", "\0", "\0", "\0", 0),
+    ("cwd\0", "\0", "\0", "\0", 0),
+    ("cycles\0", "\0", "\0", "\0", 0),
+    ("dgram_socket\0", "\0", "\0", "\0", 0),
     ("eisenberg\0", "\0", "\0", "\0", 0),
+    ("exec_args\0", "\0", "\0", "\0", 0),
     ("exit\0", "\0", "\0", "\0", 0),
+    ("exit_times\0", "\0", "\0", "\0", 0),
+    ("exit_with_threads\0", "\0", "\0", "\0", 0),
     ("fantastic_text\0", "\0", "\0", "\0", 0),
+    ("fault_inject\0", "\0", "\0", "\0", 0),
+    ("fault_report\0", "\0", "\0", "\0", 0),
+    ("fincore\0", "\0", "\0", "\0", 0),
+    ("fork_bomb\0", "\0", "\0", "\0", 0),
     ("forktest_simple\0", "\0", "\0", "\0", 0),
     ("forktest\0", "\0", "\0", "\0", 0),
     ("forktest2\0", "\0", "\0", "\0", 0),
     ("forktree\0", "\0", "\0", "\0", 0),
+    ("fp_switch\0", "\0", "\0", "\0", 0),
+    ("frame_leak\0", "\0", "\0", "\0", 0),
+    ("fstat\0", "\0", "\0", "\0", 0),
+    ("futex_mutex\0", "\0", "\0", "\0", 0),
+    ("getdents\0", "\0", "\0", "\0", 0),
+    ("getppid\0", "\0", "\0", "\0", 0),
+    ("gettimeofday\0", "\0", "\0", "\0", 0),
+    ("heap_collision\0", "\0", "\0", "\0", 0),
     ("hello_world\0", "\0", "\0", "\0", 0),
     ("huge_write\0", "\0", "\0", "\0", 0),
+    ("kexec\0", "\0", "\0", "\0", 0),
+    ("kill\0", "\0", "\0", "\0", 0),
+    ("lazy_stack\0", "\0", "\0", "\0", 0),
+    ("line_history\0", "\0", "\0", "\0", 0),
+    ("lseek\0", "\0", "\0", "\0", 0),
     ("matrix\0", "\0", "\0", "\0", 0),
+    ("meminfo\0", "\0", "\0", "\0", 0),
+    ("mempolicy\0", "\0", "\0", "\0", 0),
+    ("mmap\0", "\0", "\0", "\0", 0),
+    ("mmap_bounds\0", "\0", "\0", "\0", 0),
+    ("mmap_populate\0", "\0", "\0", "\0", 0),
+    ("mmap_shared\0", "\0", "\0", "\0", 0),
+    ("mpmc_condvar\0", "\0", "\0", "\0", 0),
+    ("mprotect\0", "\0", "\0", "\0", 0),
     ("mpsc_sem\0", "\0", "\0", "\0", 0),
+    ("munmap\0", "\0", "\0", "\0", 0),
+    ("orphan_reap\0", "\0", "\0", "\0", 0),
+    ("personality\0", "\0", "\0", "\0", 0),
     ("peterson\0", "\0", "\0", "\0", 0),
     ("phil_din_mutex\0", "\0", "\0", "\0", 0),
+    ("pid_times\0", "\0", "\0", "\0", 0),
+    ("prctl_dumpable\0", "\0", "\0", "\0", 0),
     ("pipe_large_test\0", "\0", "\0", "\0", 0),
     ("pipetest\0", "\0", "\0", "\0", 0),
+    ("proc_tasks\0", "\0", "\0", "\0", 0),
+    ("redirect\0", "\0", "\0", "\0", 0),
+    ("rlimit\0", "\0", "\0", "\0", 0),
+    ("robust_list\0", "\0", "\0", "\0", 0),
+    ("round_robin\0", "\0", "\0", "\0", 0),
     ("adder_peterson_spin\0", "\0", "\0", "\0", 0),
     ("adder_peterson_yield\0", "\0", "\0", "\0", 0),
     ("adder_mutex_blocking\0", "\0", "\0", "\0", 0),
     ("adder_mutex_spin\0", "\0", "\0", "\0", 0),
     ("run_pipe_test\0", "\0", "\0", "\0", 0),
+    ("sbrk\0", "\0", "\0", "\0", 0),
+    ("sched_latency\0", "\0", "\0", "\0", 0),
+    ("sendfile\0", "\0", "\0", "\0", 0),
+    ("setsid\0", "\0", "\0", "\0", 0),
+    ("shell_script\0", "\0", "\0", "\0", 0),
+    ("shm\0", "\0", "\0", "\0", 0),
+    ("signalfd\0", "\0", "\0", "\0", 0),
     ("sleep_simple\0", "\0", "\0", "\0", 0),
     ("sleep\0", "\0", "\0", "\0", 0),
+    ("sleep_order\0", "\0", "\0", "\0", 0),
     ("sleep_simple\0", "\0", "\0", "\0", 0),
+    ("spawn\0", "\0", "\0", "\0", 0),
+    ("stdin_echo\0", "\0", "\0", "\0", 0),
+    ("stride\0", "\0", "\0", "\0", 0),
+    ("submit_batch\0", "\0", "\0", "\0", 0),
     ("sync_sem\0", "\0", "\0", "\0", 0),
+    ("syscall_errno\0", "\0", "\0", "\0", 0),
+    ("task_info\0", "\0", "\0", "\0", 0),
     ("test_condvar\0", "\0", "\0", "\0", 0),
     ("threads_arg\0", "\0", "\0", "\0", 0),
     ("threads\0", "\0", "\0", "\0", 0),
+    ("timerfd\0", "\0", "\0", "\0", 0),
+    ("tlb_flush\0", "\0", "\0", "\0", 0),
+    ("tls\0", "\0", "\0", "\0", 0),
+    ("unimp\0", "\0", "\0", "\0", 0),
+    ("unlink\0", "\0", "\0", "\0", 0),
+    ("validate\0", "\0", "\0", "\0", 0),
+    ("waitpid\0", "\0", "\0", "\0", 0),
+    ("write_straddle\0", "\0", "\0", "\0", 0),
     ("yield\0", "\0", "\0", "\0", 0),
+    ("yield_order\0", "\0", "\0", "\0", 0),
+    ("yield_until\0", "\0", "\0", "\0", 0),
 ];
 
 static FAIL_TESTS: &[(&str, &str, &str, &str, i32)] = &[
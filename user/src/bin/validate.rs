@@ -0,0 +1,16 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{validate, EPERM};
+
+/// Only initproc may run the kernel self checks, which the kernel runs at
+/// boot on a healthy and a corrupted space, see `validate_test`.
+#[no_mangle]
+pub fn main() -> i32 {
+    assert_eq!(validate(), -EPERM);
+    println!("validate passed!");
+    0
+}
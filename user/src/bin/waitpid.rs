@@ -0,0 +1,44 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{exit, fork, getpid, sleep, wait, waitpid, waitpid_discard, waitpid_nb};
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let mut exit_code: i32 = 0;
+    // not a child
+    assert_eq!(waitpid_nb(getpid() as usize, &mut exit_code), -1);
+    assert_eq!(wait(&mut exit_code), -1);
+
+    let slow = fork();
+    if slow == 0 {
+        sleep(100);
+        exit(3);
+    }
+    let fast = fork();
+    if fast == 0 {
+        exit(4);
+    }
+    assert_eq!(waitpid_nb(slow as usize, &mut exit_code), -2);
+    // only the child asked for is reaped
+    assert_eq!(waitpid(fast as usize, &mut exit_code), fast);
+    assert_eq!(exit_code, 4);
+    assert_eq!(waitpid_nb(fast as usize, &mut exit_code), -1);
+    assert_eq!(wait(&mut exit_code), slow);
+    assert_eq!(exit_code, 3);
+    assert_eq!(wait(&mut exit_code), -1);
+
+    let pid = fork();
+    if pid == 0 {
+        exit(5);
+    }
+    exit_code = 0;
+    assert_eq!(waitpid_discard(-1), pid);
+    assert_eq!(exit_code, 0);
+    assert_eq!(waitpid_discard(pid), -1);
+    println!("waitpid passed!");
+    0
+}
@@ -0,0 +1,56 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use user_lib::{close, pipe, read, write};
+
+const PAGE_SIZE: usize = 0x1000;
+const STDOUT: usize = 1;
+const MESSAGE: &[u8] = b"write_straddle: this line crosses a page boundary\n";
+const UTF8_MESSAGE: &[u8] = "write_straddle: ü is split by the page boundary\n".as_bytes();
+
+#[repr(C, align(4096))]
+struct TwoPages([u8; 2 * PAGE_SIZE]);
+
+static mut PAGES: TwoPages = TwoPages([0; 2 * PAGE_SIZE]);
+
+/// The two pages of the buffer are backed by frames that need not be
+/// contiguous, the kernel has to split the copy at the boundary.
+#[no_mangle]
+pub fn main() -> i32 {
+    let start = PAGE_SIZE - MESSAGE.len() / 2;
+    let buf = unsafe { &mut PAGES.0[start..start + MESSAGE.len()] };
+    buf.copy_from_slice(MESSAGE);
+    assert_eq!(write(STDOUT, buf), MESSAGE.len() as isize);
+
+    // the bytes arrive in order, none are lost at the boundary
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    assert_eq!(write(fds[1], buf), MESSAGE.len() as isize);
+    close(fds[1]);
+    let mut received = [0u8; MESSAGE.len()];
+    assert_eq!(read(fds[0], &mut received), MESSAGE.len() as isize);
+    close(fds[0]);
+    assert_eq!(&received[..], MESSAGE);
+
+    // and the other way round
+    let mut fds = [0usize; 2];
+    assert_eq!(pipe(&mut fds), 0);
+    assert_eq!(write(fds[1], MESSAGE), MESSAGE.len() as isize);
+    close(fds[1]);
+    buf.fill(0);
+    assert_eq!(read(fds[0], buf), MESSAGE.len() as isize);
+    close(fds[0]);
+    assert_eq!(buf, MESSAGE);
+
+    // a character is not whole in either page
+    let split = UTF8_MESSAGE.iter().position(|&b| !b.is_ascii()).unwrap() + 1;
+    let start = PAGE_SIZE - split;
+    let buf = unsafe { &mut PAGES.0[start..start + UTF8_MESSAGE.len()] };
+    buf.copy_from_slice(UTF8_MESSAGE);
+    assert_eq!(write(STDOUT, buf), UTF8_MESSAGE.len() as isize);
+    println!("write_straddle passed!");
+    0
+}
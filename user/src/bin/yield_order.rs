@@ -0,0 +1,54 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use user_lib::{exit, thread_create, waittid, yield_};
+
+const THREADS: usize = 3;
+const ROUNDS: usize = 20;
+
+static mut ORDER: [usize; THREADS * ROUNDS] = [0; THREADS * ROUNDS];
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+/// set once every thread is created, so that they start in the same round
+static START: AtomicBool = AtomicBool::new(false);
+
+fn yielder(id: usize) -> ! {
+    while !START.load(Ordering::Relaxed) {
+        yield_();
+    }
+    for _ in 0..ROUNDS {
+        let slot = NEXT.fetch_add(1, Ordering::Relaxed);
+        unsafe {
+            ORDER[slot] = id;
+        }
+        yield_();
+    }
+    exit(0)
+}
+
+/// Threads yielding in a loop take turns, each goes behind the others.
+#[no_mangle]
+pub fn main() -> i32 {
+    let tids: Vec<isize> = (0..THREADS)
+        .map(|id| thread_create(yielder as usize, id))
+        .collect();
+    START.store(true, Ordering::Relaxed);
+    for tid in tids {
+        assert_eq!(waittid(tid as usize), 0);
+    }
+    let order = unsafe { &ORDER };
+    println!("yield order: {:?}", &order[..THREADS * 2]);
+    for i in 0..THREADS {
+        assert!(!order[..i].contains(&order[i]));
+    }
+    for i in THREADS..order.len() {
+        assert_eq!(order[i], order[i - THREADS]);
+    }
+    println!("yield_order passed!");
+    0
+}
@@ -0,0 +1,42 @@
+#![no_std]
+#![no_main]
+
+#[macro_use]
+extern crate user_lib;
+
+use core::ptr::write_volatile;
+use user_lib::{exit, get_time, sleep, thread_create, waittid, yield_until, EINVAL, ETIMEDOUT};
+
+static mut FLAG: u32 = 0;
+
+fn flip_later() -> ! {
+    sleep(20);
+    unsafe {
+        write_volatile(&mut FLAG, 1);
+    }
+    exit(0)
+}
+
+#[no_mangle]
+pub fn main() -> i32 {
+    let flag = unsafe { &FLAG };
+    // already satisfied, even without any time to wait
+    assert_eq!(yield_until(flag, 0, 0), 0);
+
+    let start = get_time();
+    assert_eq!(yield_until(flag, 1, 30), -ETIMEDOUT);
+    assert!(get_time() - start >= 30);
+
+    let misaligned = unsafe { &*((flag as *const u32 as usize + 1) as *const u32) };
+    assert_eq!(yield_until(misaligned, 0, 0), -EINVAL);
+
+    let tid = thread_create(flip_later as usize, 0);
+    let start = get_time();
+    assert_eq!(yield_until(flag, 1, 5000), 0);
+    let waited = get_time() - start;
+    println!("flag flipped after {} ms", waited);
+    assert!(waited < 1000);
+    assert_eq!(waittid(tid as usize), 0);
+    println!("yield_until passed!");
+    0
+}
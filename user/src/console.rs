@@ -4,6 +4,8 @@ const STDIN: usize = 0;
 const STDOUT: usize = 1;
 
 use super::{read, write};
+use alloc::collections::VecDeque;
+use alloc::string::String;
 
 struct Stdout;
 
@@ -37,3 +39,159 @@ pub fn getchar() -> u8 {
     read(STDIN, &mut c);
     c[0]
 }
+
+/// None at the end of input, when stdin is redirected
+pub fn try_getchar() -> Option<u8> {
+    let mut c = [0u8; 1];
+    match read(STDIN, &mut c) {
+        1 => Some(c[0]),
+        _ => None,
+    }
+}
+
+const LF: u8 = 0x0au8;
+const CR: u8 = 0x0du8;
+const DL: u8 = 0x7fu8;
+const BS: u8 = 0x08u8;
+const ESC: u8 = 0x1bu8;
+/// Ctrl-C
+const ETX: u8 = 0x03u8;
+
+/// Number of recent lines kept for recall.
+pub const HISTORY_SIZE: usize = 16;
+
+/// Progress through an `ESC [ <final>` sequence.
+enum EscapeState {
+    Normal,
+    Escape,
+    Csi,
+}
+
+/// Line reader with echo, backspace, Ctrl-C and history recall by up/down
+/// arrows.
+/// Bytes are fed one at a time, so a partial escape sequence never blocks.
+pub struct LineEditor {
+    line: String,
+    history: VecDeque<String>,
+    /// position in history while recalling, `history.len()` for the line being typed
+    recall: usize,
+    /// the line being typed before recalling started
+    pending: String,
+    state: EscapeState,
+}
+
+impl Default for LineEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LineEditor {
+    pub fn new() -> Self {
+        Self {
+            line: String::new(),
+            history: VecDeque::new(),
+            recall: 0,
+            pending: String::new(),
+            state: EscapeState::Normal,
+        }
+    }
+
+    pub fn line(&self) -> &str {
+        self.line.as_str()
+    }
+
+    /// Feed one input byte, return the finished line at the end of it.
+    pub fn feed(&mut self, c: u8) -> Option<String> {
+        match self.state {
+            EscapeState::Escape => {
+                self.state = EscapeState::Normal;
+                if c == b'[' {
+                    self.state = EscapeState::Csi;
+                    return None;
+                }
+            }
+            EscapeState::Csi => {
+                // parameters are not used by any supported sequence
+                if (0x40..=0x7e).contains(&c) {
+                    self.state = EscapeState::Normal;
+                    match c {
+                        b'A' => self.recall_prev(),
+                        b'B' => self.recall_next(),
+                        _ => {}
+                    }
+                }
+                return None;
+            }
+            EscapeState::Normal => {}
+        }
+        match c {
+            ESC => self.state = EscapeState::Escape,
+            LF | CR => {
+                print!("\n");
+                let line = core::mem::take(&mut self.line);
+                if !line.is_empty() {
+                    if self.history.len() == HISTORY_SIZE {
+                        self.history.pop_front();
+                    }
+                    self.history.push_back(line.clone());
+                }
+                self.recall = self.history.len();
+                self.pending.clear();
+                return Some(line);
+            }
+            ETX => {
+                // drop the line typed so far, it is not kept in history
+                print!("^C\n");
+                self.line.clear();
+                self.recall = self.history.len();
+                self.pending.clear();
+                return Some(String::new());
+            }
+            BS | DL => {
+                if self.line.pop().is_some() {
+                    print!("{} {}", BS as char, BS as char);
+                }
+            }
+            _ => {
+                print!("{}", c as char);
+                self.line.push(c as char);
+            }
+        }
+        None
+    }
+
+    fn recall_prev(&mut self) {
+        if self.recall == 0 {
+            return;
+        }
+        if self.recall == self.history.len() {
+            self.pending = self.line.clone();
+        }
+        self.recall -= 1;
+        let line = self.history[self.recall].clone();
+        self.redraw(line);
+    }
+
+    fn recall_next(&mut self) {
+        if self.recall == self.history.len() {
+            return;
+        }
+        self.recall += 1;
+        let line = if self.recall == self.history.len() {
+            core::mem::take(&mut self.pending)
+        } else {
+            self.history[self.recall].clone()
+        };
+        self.redraw(line);
+    }
+
+    /// Erase the echoed line and show `line` instead.
+    fn redraw(&mut self, line: String) {
+        for _ in 0..self.line.len() {
+            print!("{} {}", BS as char, BS as char);
+        }
+        print!("{}", line);
+        self.line = line;
+    }
+}
@@ -10,21 +10,236 @@ bitflags! {
     }
 }
 
+bitflags! {
+    pub struct StatMode: u32 {
+        const FIFO = 0o010000;
+        const CHR = 0o020000;
+        const DIR = 0o040000;
+        const FILE = 0o100000;
+        const SOCK = 0o140000;
+    }
+}
+
+/// metadata of an open file
+#[repr(C)]
+#[derive(Debug)]
+pub struct Stat {
+    pub dev: u64,
+    /// inode number, 0 for files not on the filesystem
+    pub ino: u64,
+    pub mode: StatMode,
+    pub nlink: u32,
+    /// size in bytes, 0 for files other than regular files and directories
+    pub size: u64,
+}
+
+impl Default for Stat {
+    fn default() -> Self {
+        Self {
+            dev: 0,
+            ino: 0,
+            mode: StatMode::empty(),
+            nlink: 0,
+            size: 0,
+        }
+    }
+}
+
 pub fn dup(fd: usize) -> isize {
     sys_dup(fd)
 }
 pub fn open(path: &str, flags: OpenFlags) -> isize {
     sys_open(path, flags.bits)
 }
+/// path should end with '\0'
+pub fn mkdir(path: &str) -> isize {
+    sys_mkdir(path)
+}
+/// remove the file or empty directory at path, a file still open stays
+/// usable until the last fd on it is closed. path should end with '\0'
+pub fn unlink(path: &str) -> isize {
+    sys_unlink(path)
+}
+/// path should end with '\0'
+pub fn chdir(path: &str) -> isize {
+    sys_chdir(path)
+}
+/// copy the working directory with an ending '\0' to buf, return the length
+/// of the path or -1 if buf is too short
+pub fn getcwd(buf: &mut [u8]) -> isize {
+    sys_getcwd(buf)
+}
+pub fn fstat(fd: usize, st: &mut Stat) -> isize {
+    sys_fstat(fd, st)
+}
 pub fn close(fd: usize) -> isize {
     sys_close(fd)
 }
 pub fn pipe(pipe_fd: &mut [usize]) -> isize {
     sys_pipe(pipe_fd)
 }
+/// fill buf with records of the entries of the directory fd, going on from
+/// where the last call stopped, return the number of bytes filled, 0 at the
+/// end of the directory, -1 if fd is not a directory, or -EINVAL if buf is
+/// too short for the next record. Walk the records with `Dirents`
+pub fn getdents(fd: usize, buf: &mut [u8]) -> isize {
+    sys_getdents(fd, buf)
+}
+
+/// The (inode number, name) of each record filled in by getdents.
+pub struct Dirents<'a> {
+    records: &'a [u8],
+}
+
+impl<'a> Dirents<'a> {
+    pub fn new(records: &'a [u8]) -> Self {
+        Self { records }
+    }
+}
+
+impl<'a> Iterator for Dirents<'a> {
+    type Item = (u32, &'a str);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.records.len() < 8 {
+            return None;
+        }
+        let ino = u32::from_ne_bytes(self.records[..4].try_into().unwrap());
+        let name_len = u32::from_ne_bytes(self.records[4..8].try_into().unwrap()) as usize;
+        let name = core::str::from_utf8(&self.records[8..8 + name_len]).unwrap();
+        // records are padded to 4 bytes
+        let record_len = (8 + name_len + 3) & !3;
+        self.records = &self.records[record_len.min(self.records.len())..];
+        Some((ino, name))
+    }
+}
+
+pub const SEEK_SET: usize = 0;
+pub const SEEK_CUR: usize = 1;
+pub const SEEK_END: usize = 2;
+
+/// move the offset of fd, shared with the fds dup'ed from it, return the new
+/// offset
+pub fn lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    sys_lseek(fd, offset, whence)
+}
 pub fn read(fd: usize, buf: &mut [u8]) -> isize {
     sys_read(fd, buf)
 }
 pub fn write(fd: usize, buf: &[u8]) -> isize {
     sys_write(fd, buf)
-}
\ No newline at end of file
+}
+
+/// copy up to count bytes from in_fd to out_fd without passing them through
+/// user memory, return the number of bytes copied. They are read from
+/// *offset if given, which moves past them instead of the offset of in_fd
+pub fn sendfile(out_fd: usize, in_fd: usize, offset: Option<&mut usize>, count: usize) -> isize {
+    let offset = offset.map_or(core::ptr::null_mut(), |offset| offset as *mut usize);
+    sys_sendfile(out_fd, in_fd, offset, count)
+}
+
+/// fincore reports residency in pages of this size
+pub const PAGE_SIZE: usize = 0x1000;
+
+/// set vec[i] to 1 if the i-th page of [offset, offset + len) in the file
+/// is cached, vec must hold a byte for every page of the range
+pub fn fincore(fd: usize, offset: usize, len: usize, vec: &mut [u8]) -> isize {
+    sys_fincore(fd, offset, len, vec)
+}
+
+pub const AF_UNIX: usize = 1;
+pub const SOCK_DGRAM: usize = 2;
+
+#[repr(C)]
+pub struct MsgHdr {
+    /// nul-terminated destination name, unused by recvmsg
+    pub name: usize,
+    pub buf: usize,
+    pub len: usize,
+}
+
+pub fn socket(domain: usize, socket_type: usize, protocol: usize) -> isize {
+    sys_socket(domain, socket_type, protocol)
+}
+/// name should end with '\0'
+pub fn bind(fd: usize, name: &str) -> isize {
+    sys_bind(fd, name)
+}
+/// send one datagram to the socket bound to name, which should end with '\0'
+pub fn sendto(fd: usize, buf: &[u8], name: &str) -> isize {
+    let msg = MsgHdr {
+        name: name.as_ptr() as usize,
+        buf: buf.as_ptr() as usize,
+        len: buf.len(),
+    };
+    sys_sendmsg(fd, &msg)
+}
+/// receive exactly one datagram
+pub fn recv(fd: usize, buf: &mut [u8]) -> isize {
+    let mut msg = MsgHdr {
+        name: 0,
+        buf: buf.as_mut_ptr() as usize,
+        len: buf.len(),
+    };
+    sys_recvmsg(fd, &mut msg)
+}
+
+pub const CLOCK_MONOTONIC: usize = 1;
+pub const TFD_NONBLOCK: usize = 0o4000;
+
+/// timerfd setting in milliseconds, value_ms == 0 disarms and
+/// interval_ms == 0 expires only once
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct ITimerSpec {
+    pub interval_ms: usize,
+    pub value_ms: usize,
+}
+
+pub fn timerfd_create(clock_id: usize, flags: usize) -> isize {
+    sys_timerfd_create(clock_id, flags)
+}
+/// arm the timer relative to now and return its previous setting through old
+pub fn timerfd_settime(fd: usize, new: &ITimerSpec, old: Option<&mut ITimerSpec>) -> isize {
+    let old = match old {
+        Some(old) => old as *mut ITimerSpec,
+        None => core::ptr::null_mut(),
+    };
+    sys_timerfd_settime(fd, new, old)
+}
+
+pub const SFD_NONBLOCK: usize = 0o4000;
+
+/// record read from a signalfd, only the signal number is filled in
+#[repr(C)]
+pub struct SignalfdSiginfo {
+    pub ssi_signo: u32,
+    _pad: [u8; 124],
+}
+
+impl Default for SignalfdSiginfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SignalfdSiginfo {
+    pub fn new() -> Self {
+        Self { ssi_signo: 0, _pad: [0; 124] }
+    }
+}
+
+/// read the signals in mask from a new signalfd if fd is usize::MAX,
+/// otherwise change the mask of the signalfd fd
+pub fn signalfd(fd: usize, mask: SignalFlags, flags: usize) -> isize {
+    sys_signalfd(fd, mask.bits(), flags)
+}
+/// dequeue one pending signal
+pub fn read_siginfo(fd: usize, info: &mut SignalfdSiginfo) -> isize {
+    let buf = unsafe {
+        core::slice::from_raw_parts_mut(
+            info as *mut SignalfdSiginfo as *mut u8,
+            core::mem::size_of::<SignalfdSiginfo>(),
+        )
+    };
+    sys_read(fd, buf)
+}
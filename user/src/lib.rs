@@ -25,6 +25,20 @@ pub use task::*;
 pub use sync::*;
 pub use io::*;
 
+/// errnos returned negated by the syscalls
+pub const EPERM: isize = 1;
+pub const ESRCH: isize = 3;
+pub const E2BIG: isize = 7;
+pub const EBADF: isize = 9;
+pub const EAGAIN: isize = 11;
+pub const ENOMEM: isize = 12;
+pub const EFAULT: isize = 14;
+pub const EINVAL: isize = 22;
+pub const EMFILE: isize = 24;
+pub const ESPIPE: isize = 29;
+pub const ETIMEDOUT: isize = 110;
+pub const ECONNREFUSED: isize = 111;
+
 const USER_HEAP_SIZE: usize = 32768;
 
 static mut HEAP_SPACE: [u8; USER_HEAP_SIZE] = [0; USER_HEAP_SIZE];
@@ -1,4 +1,5 @@
 use super::*;
+use core::sync::atomic::AtomicU32;
 
 pub fn mutex_create() -> isize {
     sys_mutex_create(false)
@@ -6,27 +7,46 @@ pub fn mutex_create() -> isize {
 pub fn mutex_blocking_create() -> isize {
     sys_mutex_create(true)
 }
-pub fn mutex_lock(mutex_id: usize) {
-    sys_mutex_lock(mutex_id);
+/// return 0, or -EINVAL if mutex_id is not created
+pub fn mutex_lock(mutex_id: usize) -> isize {
+    sys_mutex_lock(mutex_id)
 }
-pub fn mutex_unlock(mutex_id: usize) {
-    sys_mutex_unlock(mutex_id);
+pub fn mutex_unlock(mutex_id: usize) -> isize {
+    sys_mutex_unlock(mutex_id)
 }
 pub fn semaphore_create(res_count: usize) -> isize {
     sys_semaphore_create(res_count)
 }
-pub fn semaphore_up(sem_id: usize) {
-    sys_semaphore_up(sem_id);
+/// return 0, or -EINVAL if sem_id is not created
+pub fn semaphore_up(sem_id: usize) -> isize {
+    sys_semaphore_up(sem_id)
 }
-pub fn semaphore_down(sem_id: usize) {
-    sys_semaphore_down(sem_id);
+pub fn semaphore_down(sem_id: usize) -> isize {
+    sys_semaphore_down(sem_id)
 }
 pub fn condvar_create() -> isize {
     sys_condvar_create(0)
 }
-pub fn condvar_signal(condvar_id: usize) {
-    sys_condvar_signal(condvar_id);
+/// return 0, or -EINVAL if condvar_id (or mutex_id) is not created
+pub fn condvar_signal(condvar_id: usize) -> isize {
+    sys_condvar_signal(condvar_id)
+}
+pub fn condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
+    sys_condvar_wait(condvar_id, mutex_id)
+}/// return 0 once *addr == expected, or -ETIMEDOUT after timeout_ms
+pub fn yield_until(addr: &u32, expected: u32, timeout_ms: usize) -> isize {
+    sys_yield_until(addr as *const u32, expected, timeout_ms)
+}
+
+pub const FUTEX_WAIT: u32 = 0;
+pub const FUTEX_WAKE: u32 = 1;
+
+/// block until woken if *addr == val, return 0 once woken or -EAGAIN if
+/// *addr differs
+pub fn futex_wait(addr: &AtomicU32, val: u32) -> isize {
+    sys_futex(addr as *const AtomicU32 as *mut u32, FUTEX_WAIT, val)
+}
+/// wake up to count threads blocked on addr, return the number woken
+pub fn futex_wake(addr: &AtomicU32, count: u32) -> isize {
+    sys_futex(addr as *const AtomicU32 as *mut u32, FUTEX_WAKE, count)
 }
-pub fn condvar_wait(condvar_id: usize, mutex_id: usize) {
-    sys_condvar_wait(condvar_id, mutex_id);
-}
\ No newline at end of file
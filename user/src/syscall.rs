@@ -1,18 +1,65 @@
+use crate::{
+    HeapStats, ITimerSpec, MsgHdr, RLimit, Stat, SyscallOp, TaskInfo, TimeVal, TlbStats,
+};
+
+const SYSCALL_GETCWD: usize = 17;
 const SYSCALL_DUP: usize = 24;
+const SYSCALL_MKDIR: usize = 34;
+const SYSCALL_UNLINK: usize = 35;
+const SYSCALL_CHDIR: usize = 49;
 const SYSCALL_OPEN: usize = 56;
 const SYSCALL_CLOSE: usize = 57;
 const SYSCALL_PIPE: usize = 59;
+const SYSCALL_GETDENTS: usize = 61;
+const SYSCALL_LSEEK: usize = 62;
 const SYSCALL_READ: usize = 63;
 const SYSCALL_WRITE: usize = 64;
+const SYSCALL_SENDFILE: usize = 71;
+const SYSCALL_SIGNALFD: usize = 74;
+const SYSCALL_FSTAT: usize = 80;
+const SYSCALL_TIMERFD_CREATE: usize = 85;
+const SYSCALL_TIMERFD_SETTIME: usize = 86;
+const SYSCALL_PERSONALITY: usize = 92;
 const SYSCALL_EXIT: usize = 93;
+const SYSCALL_SET_TID_ADDRESS: usize = 96;
+const SYSCALL_FUTEX: usize = 98;
+const SYSCALL_SET_ROBUST_LIST: usize = 99;
 const SYSCALL_SLEEP: usize = 101;
+const SYSCALL_KEXEC: usize = 104;
 const SYSCALL_YIELD: usize = 124;
 const SYSCALL_KILL: usize = 129;
+const SYSCALL_SET_PRIORITY: usize = 140;
+const SYSCALL_SHUTDOWN: usize = 142;
+const SYSCALL_GETPGID: usize = 155;
+const SYSCALL_GETSID: usize = 156;
+const SYSCALL_SETSID: usize = 157;
+const SYSCALL_GETRLIMIT: usize = 163;
+const SYSCALL_SETRLIMIT: usize = 164;
+const SYSCALL_PRCTL: usize = 167;
 const SYSCALL_GET_TIME: usize = 169;
 const SYSCALL_GETPID: usize = 172;
-const SYSCALL_FORK: usize = 220;
+const SYSCALL_GETPPID: usize = 173;
+const SYSCALL_SHM_GET: usize = 194;
+const SYSCALL_SHM_ATTACH: usize = 196;
+const SYSCALL_SHM_DETACH: usize = 197;
+const SYSCALL_SOCKET: usize = 198;
+const SYSCALL_BIND: usize = 200;
+const SYSCALL_SENDMSG: usize = 211;
+const SYSCALL_RECVMSG: usize = 212;
+const SYSCALL_SBRK: usize = 214;
+const SYSCALL_MUNMAP: usize = 215;
+const SYSCALL_CLONE: usize = 220;
 const SYSCALL_EXEC: usize = 221;
+const SYSCALL_MMAP: usize = 222;
+const SYSCALL_MPROTECT: usize = 226;
+const SYSCALL_MINCORE: usize = 232;
+const SYSCALL_SET_MEMPOLICY: usize = 237;
 const SYSCALL_WAITPID: usize = 260;
+const SYSCALL_PROCESS_VM_READV: usize = 270;
+const SYSCALL_SPAWN: usize = 400;
+const SYSCALL_TASK_INFO: usize = 410;
+const SYSCALL_SUBMIT_BATCH: usize = 426;
+const SYSCALL_FINCORE: usize = 460;
 const SYSCALL_THREAD_CREATE: usize = 1000;
 const SYSCALL_GETTID: usize = 1001;
 const SYSCALL_WAITTID: usize = 1002;
@@ -25,10 +72,17 @@ const SYSCALL_SEMAPHORE_DOWN: usize = 1022;
 const SYSCALL_CONDVAR_CREATE: usize = 1030;
 const SYSCALL_CONDVAR_SIGNAL: usize = 1031;
 const SYSCALL_CONDVAR_WAIT: usize = 1032;
+const SYSCALL_YIELD_UNTIL: usize = 1040;
 const SYSCALL_FRAMEBUFFER: usize = 2000;
 const SYSCALL_FRAMEBUFFER_FLUSH: usize = 2001;
 const SYSCALL_EVENT_GET: usize = 3000;
 const SYSCALL_KEY_PRESSED: usize = 3001;
+const SYSCALL_VALIDATE: usize = 4000;
+const SYSCALL_FAULT_INJECT: usize = 4001;
+const SYSCALL_SCHED_LATENCY_HIST: usize = 4002;
+const SYSCALL_MEMINFO: usize = 4003;
+const SYSCALL_TLB_STATS: usize = 4004;
+const SYSCALL_GET_CYCLES: usize = 4005;
 
 fn syscall(id: usize, args: [usize; 3]) -> isize {
     let mut ret: isize;
@@ -44,6 +98,37 @@ fn syscall(id: usize, args: [usize; 3]) -> isize {
     ret
 }
 
+fn syscall4(id: usize, args: [usize; 4]) -> isize {
+    let mut ret: isize;
+    unsafe {
+        core::arch::asm!(
+            "ecall",
+            inlateout("x10") args[0] => ret,
+            in("x11") args[1],
+            in("x12") args[2],
+            in("x13") args[3],
+            in("x17") id
+        );
+    }
+    ret
+}
+
+pub fn sys_getcwd(buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_GETCWD, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
+pub fn sys_mkdir(path: &str) -> isize {
+    syscall(SYSCALL_MKDIR, [path.as_ptr() as usize, 0, 0])
+}
+
+pub fn sys_unlink(path: &str) -> isize {
+    syscall(SYSCALL_UNLINK, [path.as_ptr() as usize, 0, 0])
+}
+
+pub fn sys_chdir(path: &str) -> isize {
+    syscall(SYSCALL_CHDIR, [path.as_ptr() as usize, 0, 0])
+}
+
 pub fn sys_dup(fd: usize) -> isize {
     syscall(SYSCALL_DUP, [fd, 0, 0])
 }
@@ -52,6 +137,10 @@ pub fn sys_open(path: &str, flags: u32) -> isize {
     syscall(SYSCALL_OPEN, [path.as_ptr() as usize, flags as usize, 0])
 }
 
+pub fn sys_fstat(fd: usize, st: &mut Stat) -> isize {
+    syscall(SYSCALL_FSTAT, [fd, st as *mut _ as usize, 0])
+}
+
 pub fn sys_close(fd: usize) -> isize {
     syscall(SYSCALL_CLOSE, [fd, 0, 0])
 }
@@ -60,6 +149,14 @@ pub fn sys_pipe(pipe: &mut [usize]) -> isize {
     syscall(SYSCALL_PIPE, [pipe.as_mut_ptr() as usize, 0, 0])
 }
 
+pub fn sys_getdents(fd: usize, buf: &mut [u8]) -> isize {
+    syscall(SYSCALL_GETDENTS, [fd, buf.as_mut_ptr() as usize, buf.len()])
+}
+
+pub fn sys_lseek(fd: usize, offset: isize, whence: usize) -> isize {
+    syscall(SYSCALL_LSEEK, [fd, offset as usize, whence])
+}
+
 pub fn sys_read(fd: usize, buffer: &mut [u8]) -> isize {
     syscall(
         SYSCALL_READ,
@@ -71,15 +168,54 @@ pub fn sys_write(fd: usize, buffer: &[u8]) -> isize {
     syscall(SYSCALL_WRITE, [fd, buffer.as_ptr() as usize, buffer.len()])
 }
 
+pub fn sys_sendfile(out_fd: usize, in_fd: usize, offset: *mut usize, count: usize) -> isize {
+    syscall4(SYSCALL_SENDFILE, [out_fd, in_fd, offset as usize, count])
+}
+
+pub fn sys_personality(persona: u32) -> isize {
+    syscall(SYSCALL_PERSONALITY, [persona as usize, 0, 0])
+}
+
+pub fn sys_signalfd(fd: usize, mask: i32, flags: usize) -> isize {
+    syscall(SYSCALL_SIGNALFD, [fd, mask as u32 as usize, flags])
+}
+
+pub fn sys_timerfd_create(clock_id: usize, flags: usize) -> isize {
+    syscall(SYSCALL_TIMERFD_CREATE, [clock_id, flags, 0])
+}
+
+pub fn sys_timerfd_settime(fd: usize, new: &ITimerSpec, old: *mut ITimerSpec) -> isize {
+    syscall(
+        SYSCALL_TIMERFD_SETTIME,
+        [fd, new as *const _ as usize, old as usize],
+    )
+}
+
 pub fn sys_exit(exit_code: i32) -> ! {
     syscall(SYSCALL_EXIT, [exit_code as usize, 0, 0]);
     panic!("sys_exit never returns!");
 }
 
+pub fn sys_set_tid_address(tidptr: usize) -> isize {
+    syscall(SYSCALL_SET_TID_ADDRESS, [tidptr, 0, 0])
+}
+
+pub fn sys_futex(uaddr: *mut u32, op: u32, val: u32) -> isize {
+    syscall(SYSCALL_FUTEX, [uaddr as usize, op as usize, val as usize])
+}
+
+pub fn sys_set_robust_list(head: usize, len: usize) -> isize {
+    syscall(SYSCALL_SET_ROBUST_LIST, [head, len, 0])
+}
+
 pub fn sys_sleep(sleep_ms: usize) -> isize {
     syscall(SYSCALL_SLEEP, [sleep_ms, 0, 0])
 }
 
+pub fn sys_kexec(image: &[u8], entry: usize) -> isize {
+    syscall(SYSCALL_KEXEC, [image.as_ptr() as usize, image.len(), entry])
+}
+
 pub fn sys_yield() -> isize {
     syscall(SYSCALL_YIELD, [0, 0, 0])
 }
@@ -88,16 +224,85 @@ pub fn sys_kill(pid: usize, signal: i32) -> isize {
     syscall(SYSCALL_KILL, [pid, signal as usize, 0])
 }
 
-pub fn sys_get_time() -> isize {
-    syscall(SYSCALL_GET_TIME, [0, 0, 0])
+pub fn sys_set_priority(prio: isize) -> isize {
+    syscall(SYSCALL_SET_PRIORITY, [prio as usize, 0, 0])
+}
+
+pub fn sys_shutdown(exit_code: usize) -> ! {
+    syscall(SYSCALL_SHUTDOWN, [exit_code, 0, 0]);
+    panic!("sys_shutdown never returns!");
+}
+
+pub fn sys_getpgid(pid: usize) -> isize {
+    syscall(SYSCALL_GETPGID, [pid, 0, 0])
+}
+
+pub fn sys_getsid(pid: usize) -> isize {
+    syscall(SYSCALL_GETSID, [pid, 0, 0])
+}
+
+pub fn sys_setsid() -> isize {
+    syscall(SYSCALL_SETSID, [0, 0, 0])
+}
+
+pub fn sys_getrlimit(resource: usize, rlim: &mut RLimit) -> isize {
+    syscall(SYSCALL_GETRLIMIT, [resource, rlim as *mut _ as usize, 0])
+}
+
+pub fn sys_setrlimit(resource: usize, limit: usize) -> isize {
+    syscall(SYSCALL_SETRLIMIT, [resource, limit, 0])
+}
+
+pub fn sys_prctl(option: usize, arg: usize) -> isize {
+    syscall(SYSCALL_PRCTL, [option, arg, 0])
+}
+
+pub fn sys_get_time(ts: *mut TimeVal, tz: usize) -> isize {
+    syscall(SYSCALL_GET_TIME, [ts as usize, tz, 0])
 }
 
 pub fn sys_getpid() -> isize {
     syscall(SYSCALL_GETPID, [0, 0, 0])
 }
 
-pub fn sys_fork() -> isize {
-    syscall(SYSCALL_FORK, [0, 0, 0])
+pub fn sys_getppid() -> isize {
+    syscall(SYSCALL_GETPPID, [0, 0, 0])
+}
+
+pub fn sys_shm_get(key: usize, size: usize) -> isize {
+    syscall(SYSCALL_SHM_GET, [key, size, 0])
+}
+
+pub fn sys_shm_attach(id: usize, prot: usize) -> isize {
+    syscall(SYSCALL_SHM_ATTACH, [id, prot, 0])
+}
+
+pub fn sys_shm_detach(id: usize) -> isize {
+    syscall(SYSCALL_SHM_DETACH, [id, 0, 0])
+}
+
+pub fn sys_socket(domain: usize, socket_type: usize, protocol: usize) -> isize {
+    syscall(SYSCALL_SOCKET, [domain, socket_type, protocol])
+}
+
+pub fn sys_bind(fd: usize, name: &str) -> isize {
+    syscall(SYSCALL_BIND, [fd, name.as_ptr() as usize, 0])
+}
+
+pub fn sys_sendmsg(fd: usize, msg: &MsgHdr) -> isize {
+    syscall(SYSCALL_SENDMSG, [fd, msg as *const _ as usize, 0])
+}
+
+pub fn sys_recvmsg(fd: usize, msg: &mut MsgHdr) -> isize {
+    syscall(SYSCALL_RECVMSG, [fd, msg as *mut _ as usize, 0])
+}
+
+pub fn sys_sbrk(size: isize) -> isize {
+    syscall(SYSCALL_SBRK, [size as usize, 0, 0])
+}
+
+pub fn sys_clone(flags: usize, stack: usize, entry: usize, arg: usize) -> isize {
+    syscall4(SYSCALL_CLONE, [flags, stack, entry, arg])
 }
 
 pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
@@ -107,10 +312,49 @@ pub fn sys_exec(path: &str, args: &[*const u8]) -> isize {
     )
 }
 
+pub fn sys_mmap(start: usize, len: usize, prot: usize, flags: usize) -> isize {
+    syscall4(SYSCALL_MMAP, [start, len, prot, flags])
+}
+
+pub fn sys_mprotect(start: usize, len: usize, prot: usize) -> isize {
+    syscall(SYSCALL_MPROTECT, [start, len, prot])
+}
+
+pub fn sys_mincore(start: usize, len: usize, vec: &mut [u8]) -> isize {
+    syscall(SYSCALL_MINCORE, [start, len, vec.as_mut_ptr() as usize])
+}
+
+pub fn sys_munmap(start: usize, len: usize) -> isize {
+    syscall(SYSCALL_MUNMAP, [start, len, 0])
+}
+
+pub fn sys_set_mempolicy(node: usize) -> isize {
+    syscall(SYSCALL_SET_MEMPOLICY, [node, 0, 0])
+}
+
 pub fn sys_waitpid(pid: isize, exit_code: *mut i32) -> isize {
     syscall(SYSCALL_WAITPID, [pid as usize, exit_code as usize, 0])
 }
 
+pub fn sys_process_vm_readv(pid: usize, local: &mut [u8], remote: usize) -> isize {
+    syscall4(
+        SYSCALL_PROCESS_VM_READV,
+        [pid, local.as_mut_ptr() as usize, remote, local.len()],
+    )
+}
+
+pub fn sys_spawn(path: &str) -> isize {
+    syscall(SYSCALL_SPAWN, [path.as_ptr() as usize, 0, 0])
+}
+
+pub fn sys_submit_batch(ops: &mut [SyscallOp]) -> isize {
+    syscall(SYSCALL_SUBMIT_BATCH, [ops.as_mut_ptr() as usize, ops.len(), 0])
+}
+
+pub fn sys_fincore(fd: usize, offset: usize, len: usize, vec: &mut [u8]) -> isize {
+    syscall4(SYSCALL_FINCORE, [fd, offset, len, vec.as_mut_ptr() as usize])
+}
+
 pub fn sys_thread_create(entry: usize, arg: usize) -> isize {
     syscall(SYSCALL_THREAD_CREATE, [entry, arg, 0])
 }
@@ -159,6 +403,10 @@ pub fn sys_condvar_wait(condvar_id: usize, mutex_id: usize) -> isize {
     syscall(SYSCALL_CONDVAR_WAIT, [condvar_id, mutex_id, 0])
 }
 
+pub fn sys_yield_until(addr: *const u32, expected: u32, timeout_ms: usize) -> isize {
+    syscall(SYSCALL_YIELD_UNTIL, [addr as usize, expected as usize, timeout_ms])
+}
+
 
 pub fn sys_framebuffer() -> isize {
     syscall(SYSCALL_FRAMEBUFFER, [0, 0, 0])
@@ -174,4 +422,32 @@ pub fn sys_event_get() -> isize {
 
 pub fn sys_key_pressed() -> isize {
     syscall(SYSCALL_KEY_PRESSED, [0, 0, 0])
-}
\ No newline at end of file
+}
+
+pub fn sys_validate() -> isize {
+    syscall(SYSCALL_VALIDATE, [0, 0, 0])
+}
+
+pub fn sys_fault_inject(kind: usize, times: usize, probability: usize) -> isize {
+    syscall(SYSCALL_FAULT_INJECT, [kind, times, probability])
+}
+
+pub fn sys_sched_latency_hist(buf: &mut [u64]) -> isize {
+    syscall(SYSCALL_SCHED_LATENCY_HIST, [buf.as_mut_ptr() as usize, buf.len(), 0])
+}
+
+pub fn sys_meminfo(stats: &mut HeapStats) -> isize {
+    syscall(SYSCALL_MEMINFO, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_tlb_stats(stats: &mut TlbStats) -> isize {
+    syscall(SYSCALL_TLB_STATS, [stats as *mut _ as usize, 0, 0])
+}
+
+pub fn sys_get_cycles() -> isize {
+    syscall(SYSCALL_GET_CYCLES, [0, 0, 0])
+}
+
+pub fn sys_task_info(info: &mut TaskInfo) -> isize {
+    syscall(SYSCALL_TASK_INFO, [info as *mut _ as usize, 0, 0])
+}
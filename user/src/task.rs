@@ -6,18 +6,130 @@ pub fn exit(exit_code: i32) -> ! {
 pub fn yield_() -> isize {
     sys_yield()
 }
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeVal {
+    pub sec: usize,
+    pub usec: usize,
+}
+
+/// time since boot in ms
 pub fn get_time() -> isize {
-    sys_get_time()
+    let mut ts = TimeVal::default();
+    match sys_get_time(&mut ts, 0) {
+        0 => (ts.sec * 1000 + ts.usec / 1000) as isize,
+        _ => -1,
+    }
+}
+/// time since boot up to a microsecond
+pub fn gettimeofday(ts: &mut TimeVal) -> isize {
+    sys_get_time(ts, 0)
+}
+/// clock cycles of the hart, only good for comparing intervals since its
+/// clock rate is unknown
+pub fn get_cycles() -> usize {
+    sys_get_cycles() as usize
 }
 pub fn getpid() -> isize {
     sys_getpid()
 }
+/// pid of the parent, or of initproc once the parent has exited
+pub fn getppid() -> isize {
+    sys_getppid()
+}
+pub fn getpgid(pid: usize) -> isize {
+    sys_getpgid(pid)
+}
+pub fn getsid(pid: usize) -> isize {
+    sys_getsid(pid)
+}
+pub fn setsid() -> isize {
+    sys_setsid()
+}
+/// move the program break by size bytes, return the old one or -1
+pub fn sbrk(size: isize) -> isize {
+    sys_sbrk(size)
+}
+
+pub const PROT_READ: usize = 1 << 0;
+pub const PROT_WRITE: usize = 1 << 1;
+pub const PROT_EXEC: usize = 1 << 2;
+/// mmap pages stay shared with the children forked later
+pub const MAP_SHARED: usize = 0x01;
+/// mmap pages are copied on write in the children forked later, the default
+pub const MAP_PRIVATE: usize = 0x02;
+/// accepted by mmap, every mapping is anonymous
+pub const MAP_ANONYMOUS: usize = 0x20;
+/// mmap every page of the mapping right away
+pub const MAP_POPULATE: usize = 0x8000;
+
+/// map len bytes of zeroed memory at the page aligned start, len rounded up
+/// to whole pages, return 0 or -1, or -EINVAL if len is 0 or the range
+/// overflows
+pub fn mmap(start: usize, len: usize, prot: usize) -> isize {
+    sys_mmap(start, len, prot, 0)
+}
+/// mmap with MAP_* flags, return -ENOMEM and map nothing if no memory is
+/// left for MAP_POPULATE
+pub fn mmap_flags(start: usize, len: usize, prot: usize, flags: usize) -> isize {
+    sys_mmap(start, len, prot, flags)
+}
+/// change the protection of the mapped pages in [start, start + len),
+/// return 0 or -1
+pub fn mprotect(start: usize, len: usize, prot: usize) -> isize {
+    sys_mprotect(start, len, prot)
+}
+/// set vec[i] to 1 if the i-th page of [start, start + len) has its frame,
+/// otherwise 0, return 0 or -ENOMEM if a page is not mapped
+pub fn mincore(start: usize, len: usize, vec: &mut [u8]) -> isize {
+    sys_mincore(start, len, vec)
+}
+/// unmap exactly a range mapped by mmap, len rounded up as mmap does,
+/// return 0 or -1, or -EINVAL if len is 0 or the range overflows
+pub fn munmap(start: usize, len: usize) -> isize {
+    sys_munmap(start, len)
+}
+
+/// key of shm_get creating a new segment every time
+pub const IPC_PRIVATE: usize = 0;
+
+/// get the shared memory segment of key, created with size bytes if there
+/// is none, return its id or -1
+pub fn shm_get(key: usize, size: usize) -> isize {
+    sys_shm_get(key, size)
+}
+/// map the segment id with the protection of mmap, return its address or -1
+pub fn shm_attach(id: usize, prot: usize) -> isize {
+    sys_shm_attach(id, prot)
+}
+/// unmap every attachment of the segment id, return 0 or -1
+pub fn shm_detach(id: usize) -> isize {
+    sys_shm_detach(id)
+}
 pub fn fork() -> isize {
-    sys_fork()
+    sys_clone(0, 0, 0, 0)
+}
+
+/// clone shares the address space, the child is a thread of the process
+pub const CLONE_VM: usize = 0x100;
+/// clone shares the fd table instead of copying it, CLONE_VM needs it
+pub const CLONE_FILES: usize = 0x400;
+
+/// create a child starting at entry with arg, on stack unless it is 0, or
+/// returning 0 like fork if entry is 0 and the child is not a thread. entry
+/// must not return but exit. Return the tid of a thread, the pid of a
+/// process, or -EINVAL for flags not supported
+pub fn clone(flags: usize, stack: usize, entry: usize, arg: usize) -> isize {
+    sys_clone(flags, stack, entry, arg)
 }
 pub fn exec(path: &str, args: &[*const u8]) -> isize {
     sys_exec(path, args)
 }
+/// start the app at path, which should end with '\0', in a new child
+/// process without arguments, return its pid
+pub fn spawn(path: &str) -> isize {
+    sys_spawn(path)
+}
 pub fn wait(exit_code: &mut i32) -> isize {
     loop {
         match sys_waitpid(-1, exit_code as *mut _) {
@@ -46,24 +158,139 @@ pub fn waitpid_nb(pid: usize, exit_code: &mut i32) -> isize {
     sys_waitpid(pid as isize, exit_code as *mut _)
 }
 
+/// wait for pid, or any child if pid is -1, without reading its exit code
+pub fn waitpid_discard(pid: isize) -> isize {
+    loop {
+        match sys_waitpid(pid, core::ptr::null_mut()) {
+            -2 => {
+                yield_();
+            }
+            // -1 or a real pid
+            exit_pid => return exit_pid,
+        }
+    }
+}
+
+/// stop the batch after this op if it returns a negative value
+pub const OP_STOP_ON_ERROR: usize = 1;
+
+/// One syscall of a batch, `result` is filled in by the kernel.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct SyscallOp {
+    pub nr: usize,
+    pub args: [usize; 3],
+    pub flags: usize,
+    pub result: isize,
+}
+
+impl SyscallOp {
+    pub fn new(nr: usize, args: [usize; 3], flags: usize) -> Self {
+        Self { nr, args, flags, result: 0 }
+    }
+}
+
+/// run all ops within a single trap, return how many were run
+pub fn submit_batch(ops: &mut [SyscallOp]) -> isize {
+    sys_submit_batch(ops)
+}
+
 bitflags! {
     pub struct SignalFlags: i32 {
         const SIGINT    = 1 << 2;
         const SIGILL    = 1 << 4;
         const SIGABRT   = 1 << 6;
+        const SIGBUS    = 1 << 7;
         const SIGFPE    = 1 << 8;
+        const SIGKILL   = 1 << 9;
         const SIGSEGV   = 1 << 11;
+        const SIGALRM   = 1 << 14;
+        const SIGCONT   = 1 << 18;
+        const SIGSTOP   = 1 << 19;
     }
 }
 
+bitflags! {
+    pub struct Personality: u32 {
+        const ADDR_NO_RANDOMIZE = 0x0040000;
+        const STRICT_POINTERS   = 0x1000_0000;
+    }
+}
+
+/// passing this persona only queries the current one
+pub const PERSONALITY_QUERY: u32 = 0xffff_ffff;
+
+pub fn personality(persona: u32) -> isize {
+    sys_personality(persona)
+}
+
+pub const PR_GET_DUMPABLE: usize = 3;
+pub const PR_SET_DUMPABLE: usize = 4;
+
+pub fn prctl(option: usize, arg: usize) -> isize {
+    sys_prctl(option, arg)
+}
+
+/// Copy `local.len()` bytes at `remote` in the address space of `pid`.
+pub fn process_vm_readv(pid: usize, local: &mut [u8], remote: usize) -> isize {
+    sys_process_vm_readv(pid, local, remote)
+}
+
+pub const RLIMIT_NPROC: usize = 6;
+pub const RLIMIT_NOFILE: usize = 7;
+pub const RLIMIT_AS: usize = 9;
+pub const RLIM_INFINITY: usize = usize::MAX;
+
+/// both fields hold the one limit the kernel keeps
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RLimit {
+    pub rlim_cur: usize,
+    pub rlim_max: usize,
+}
+
+/// limits are inherited by children and may only be lowered, return 0,
+/// -EINVAL for an unknown resource or -EPERM when raising a limit
+pub fn setrlimit(resource: usize, limit: usize) -> isize {
+    sys_setrlimit(resource, limit)
+}
+pub fn getrlimit(resource: usize, rlim: &mut RLimit) -> isize {
+    sys_getrlimit(resource, rlim)
+}
+
 pub fn kill(pid: usize, signal: i32) -> isize {
     sys_kill(pid, signal)
 }
 
+/// the current thread runs in proportion to prio, which must be at least 2
+pub fn set_priority(prio: isize) -> isize {
+    sys_set_priority(prio)
+}
+
+/// write the files back to the disk and power off, the host sees a failure
+/// unless exit_code is 0
+pub fn shutdown(exit_code: usize) -> ! {
+    sys_shutdown(exit_code)
+}
+
 pub fn sleep(sleep_ms: usize) {
     sys_sleep(sleep_ms);
 }
 
+/// prefer memory node `node` for the frames of the current thread
+pub fn set_mempolicy(node: usize) -> isize {
+    sys_set_mempolicy(node)
+}
+
+/// physical address the kernel loads a kexec'ed image at
+pub const KEXEC_LOAD_ADDR: usize = 0x8600_0000;
+
+/// replace the kernel with `image` and jump to the physical address `entry`,
+/// only returns on failure
+pub fn kexec(image: &[u8], entry: usize) -> isize {
+    sys_kexec(image, entry)
+}
+
 pub fn thread_create(entry: usize, arg: usize) -> isize {
     sys_thread_create(entry, arg)
 }
@@ -79,4 +306,137 @@ pub fn waittid(tid: usize) -> isize {
             exit_code => return exit_code,
         }
     }
-}
\ No newline at end of file
+}
+
+pub const FUTEX_WAITERS: u32 = 0x8000_0000;
+pub const FUTEX_OWNER_DIED: u32 = 0x4000_0000;
+/// a locked robust futex holds `gettid() + 1` in these bits
+pub const FUTEX_TID_MASK: u32 = 0x3fff_ffff;
+
+#[repr(C)]
+pub struct RobustListHead {
+    pub list: usize,
+    pub futex_offset: isize,
+    pub list_op_pending: usize,
+}
+
+pub fn set_robust_list(head: usize, len: usize) -> isize {
+    sys_set_robust_list(head, len)
+}
+
+/// have the kernel zero the word at tidptr when this thread exits, return
+/// the tid of the thread
+pub fn set_tid_address(tidptr: *mut u32) -> isize {
+    sys_set_tid_address(tidptr as usize)
+}
+/// the thread pointer, to the thread-local storage of this thread at the
+/// top of its user stack
+pub fn thread_pointer() -> usize {
+    let tp: usize;
+    unsafe {
+        core::arch::asm!("mv {}, tp", out(reg) tp);
+    }
+    tp
+}
+
+/// run the kernel self checks, 0 if all of them pass, -EPERM unless called
+/// by initproc
+pub fn validate() -> isize {
+    sys_validate()
+}
+
+/// the next frame allocations fail
+pub const FAULT_FRAME_ALLOC: usize = 0;
+/// the next execs reject the ELF image
+pub const FAULT_ELF_PARSE: usize = 1;
+/// the next copies from user space fault in the kernel
+pub const FAULT_USER_COPY: usize = 2;
+/// arming it overflows the kernel stack of the caller right away, which
+/// halts the kernel
+pub const FAULT_KSTACK_OVERFLOW: usize = 3;
+
+/// make each of the next `times` operations of `kind` fail with
+/// `probability` percent, return -EPERM unless called by initproc in a debug
+/// kernel with FAULT_INJECTION
+pub fn fault_inject(kind: usize, times: usize, probability: usize) -> isize {
+    sys_fault_inject(kind, times, probability)
+}
+
+/// bucket 0 counts delays of 0 cycles, bucket i counts delays in
+/// [2^(i-1), 2^i) and the last one everything longer
+pub const SCHED_LATENCY_BUCKETS: usize = 24;
+
+/// copy the scheduling latency histogram into buf as far as it fits,
+/// return the number of resumes measured
+pub fn sched_latency_hist(buf: &mut [u64]) -> isize {
+    sys_sched_latency_hist(buf)
+}
+
+/// usage of the kernel heap and of the frames
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HeapStats {
+    /// bytes allocated now
+    pub allocated: usize,
+    /// most bytes ever allocated at once
+    pub peak: usize,
+    /// successful allocations so far
+    pub allocs: usize,
+    /// allocations that found no room
+    pub failed: usize,
+    /// frames handed out now, to the kernel space as well
+    pub frames: usize,
+}
+
+pub fn meminfo(stats: &mut HeapStats) -> isize {
+    sys_meminfo(stats)
+}
+
+/// TLB flushes done by the kernel so far
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TlbStats {
+    /// flushes of every page
+    pub full_flushes: usize,
+    /// flushes of a single page
+    pub page_flushes: usize,
+    /// times the ASIDs ran out, each one flushing every page
+    pub asid_rollovers: usize,
+}
+
+pub fn tlb_stats(stats: &mut TlbStats) -> isize {
+    sys_tlb_stats(stats)
+}
+
+/// syscall ids counted one by one, larger ones share the last count
+pub const MAX_SYSCALL_NUM: usize = 500;
+
+/// syscall statistics and times of the calling thread
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TaskInfo {
+    pub syscall_times: [u32; MAX_SYSCALL_NUM],
+    /// when the first and the latest syscall were made in ms
+    pub first_time_ms: usize,
+    pub last_time_ms: usize,
+    /// time run in user mode and in the kernel in us, not the time sleeping
+    pub user_time_us: usize,
+    pub kernel_time_us: usize,
+}
+
+impl Default for TaskInfo {
+    fn default() -> Self {
+        Self {
+            syscall_times: [0; MAX_SYSCALL_NUM],
+            first_time_ms: 0,
+            last_time_ms: 0,
+            user_time_us: 0,
+            kernel_time_us: 0,
+        }
+    }
+}
+
+/// the counts include this call
+pub fn task_info(info: &mut TaskInfo) -> isize {
+    sys_task_info(info)
+}